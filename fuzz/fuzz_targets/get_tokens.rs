@@ -0,0 +1,85 @@
+//! Write a random stream of tokens to a loopback link, and then read it back using
+//! `Link::get_expr()` and `Link::get_token()`.
+//!
+//! The token stream is not required to form valid expressions; reading it back must
+//! return an `Error` instead of panicking.
+//!
+//! Run using:
+//!
+//! ```shell
+//! $ cargo +nightly fuzz run get_tokens
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use wstp::Link;
+
+fuzz_target!(|data: &[u8]| {
+    let mut link = Link::new_loopback().expect("failed to create loopback link");
+
+    if put_tokens(&mut link, data).is_err() {
+        return;
+    }
+
+    // Alternate between reading whole expressions and single tokens, so that reads
+    // start partway through an expression.
+    for read_whole_expr in [true, false].iter().cycle() {
+        if !link.is_ready() {
+            break;
+        }
+
+        let result = if *read_whole_expr {
+            link.get_expr().map(drop)
+        } else {
+            link.get_token().map(drop)
+        };
+
+        if result.is_err() {
+            break;
+        }
+    }
+});
+
+/// Interpret `data` as a sequence of put operations.
+fn put_tokens(link: &mut Link, data: &[u8]) -> Result<(), wstp::Error> {
+    let mut bytes = data.iter().copied();
+
+    while let Some(op) = bytes.next() {
+        let arg = bytes.next().unwrap_or(0);
+
+        match op % 9 {
+            0 => link.put_i64(i64::from(arg as i8))?,
+            1 => link.put_f64(f64::from(arg) / 7.0)?,
+            2 => link.put_function(None, usize::from(arg))?,
+            3 => link.put_function("System`List", usize::from(arg % 4))?,
+            4 => link.put_symbol(if arg % 2 == 0 { "System`Null" } else { "x" })?,
+            5 => {
+                let len = usize::from(arg);
+                let bytes: Vec<u8> = bytes.by_ref().take(len).collect();
+                link.put_utf8_str(&bytes)?
+            },
+            6 => {
+                let units: Vec<u16> = bytes
+                    .by_ref()
+                    .take(usize::from(arg))
+                    .map(|byte| (u16::from(byte) << 8) | 0xD8)
+                    .collect();
+                link.put_utf16_str(&units)?
+            },
+            7 => {
+                let units: Vec<u32> = bytes
+                    .by_ref()
+                    .take(usize::from(arg))
+                    .map(|byte| u32::from(byte) << 16)
+                    .collect();
+                link.put_utf32_str(&units)?
+            },
+            8 => link.put_raw_type(i32::from(arg))?,
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Reading and writing numbers that are too large or too precise to be represented by
+//! a machine integer or real.
+
+use std::os::raw::c_char;
+
+use crate::{sys, Error, Link, LinkStr, TokenType};
+
+/// # Arbitrary-precision numbers
+impl Link {
+    /// Read the next integer or real number on this link as a string of digits.
+    ///
+    /// Unlike [`Link::get_i64()`] and [`Link::get_f64()`], this does not fail or lose
+    /// precision if the number cannot be represented by a machine integer or real.
+    ///
+    /// The returned string uses Wolfram Language number syntax, e.g. `"-12"` or
+    /// `"1.5`20.*^30"`.
+    ///
+    /// *WSTP C API Documentation:* [`WSGetNumberAsUTF8String()`](https://reference.wolfram.com/language/ref/c/WSGetNumberAsString.html)
+    pub fn get_number_str<'link>(&'link mut self) -> Result<LinkStr<'link, str>, Error> {
+        let mut c_string: *const u8 = std::ptr::null();
+        let mut num_bytes: i32 = 0;
+        let mut num_chars = 0;
+
+        if unsafe {
+            sys::WSGetNumberAsUTF8String(
+                self.raw_link,
+                &mut c_string,
+                &mut num_bytes,
+                &mut num_chars,
+            )
+        } == 0
+        {
+            // NOTE: According to the documentation, we do NOT have to release
+            //      `string` if the function returns an error.
+            return Err(self.error_or_unknown());
+        }
+
+        unsafe { LinkStr::new(self, c_string, num_bytes, false) }
+    }
+
+    /// Read an integer of any size from this link, as a string of decimal digits.
+    ///
+    /// Returns an error if the next token on this link is not an integer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64(-1234).unwrap();
+    ///
+    /// assert_eq!(link.get_bigint_string().unwrap(), "-1234");
+    /// ```
+    pub fn get_bigint_string(&mut self) -> Result<String, Error> {
+        let type_ = self.get_type()?;
+
+        if type_ != TokenType::Integer {
+            return Err(Error::custom(format!(
+                "get_bigint_string(): expected integer token, got {:?}",
+                type_
+            )));
+        }
+
+        Ok(self.get_number_str()?.as_str().to_owned())
+    }
+
+    /// Read a real number of any precision from this link, as a string.
+    ///
+    /// The returned string uses Wolfram Language number syntax, and includes the
+    /// precision of the number, e.g. `"3.14159265358979323846`20."` or
+    /// `"1.5`20.*^30"`.
+    ///
+    /// Returns an error if the next token on this link is not a real number.
+    pub fn get_real_string(&mut self) -> Result<String, Error> {
+        let type_ = self.get_type()?;
+
+        if type_ != TokenType::Real {
+            return Err(Error::custom(format!(
+                "get_real_string(): expected real token, got {:?}",
+                type_
+            )));
+        }
+
+        Ok(self.get_number_str()?.as_str().to_owned())
+    }
+
+    /// Read an integer of any size from this link.
+    ///
+    /// This function is only available when the `num-bigint` feature is enabled.
+    ///
+    /// See [`Link::get_bigint_string()`].
+    #[cfg(feature = "num-bigint")]
+    pub fn get_bigint(&mut self) -> Result<num_bigint::BigInt, Error> {
+        let digits = self.get_bigint_string()?;
+
+        digits.parse().map_err(|err| {
+            Error::with_source(
+                format!(
+                    "get_bigint(): unable to parse integer '{}': {}",
+                    digits, err
+                ),
+                err,
+            )
+        })
+    }
+
+    /// Read a real number of any precision from this link.
+    ///
+    /// The precision of the returned number is the number of significant digits sent
+    /// on the link.
+    ///
+    /// This function is only available when the `dashu-float` feature is enabled.
+    ///
+    /// See [`Link::get_real_string()`].
+    #[cfg(feature = "dashu-float")]
+    pub fn get_big_real(&mut self) -> Result<dashu_float::DBig, Error> {
+        let string = self.get_real_string()?;
+
+        let (mantissa, exponent) = split_real_string(&string);
+
+        let decimal = match exponent {
+            Some(exponent) => format!("{}e{}", mantissa, exponent),
+            None => mantissa.to_owned(),
+        };
+
+        decimal.parse().map_err(|err| {
+            Error::with_source(
+                format!(
+                    "get_big_real(): unable to parse real number '{}': {}",
+                    string, err
+                ),
+                err,
+            )
+        })
+    }
+
+    /// Put an integer of any size, given as a string of decimal digits.
+    ///
+    /// `digits` may be preceded by a `-` sign. Returns an error if `digits` is not a
+    /// valid integer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_bigint_str("-123456789012345678901234567890").unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_bigint_string().unwrap(),
+    ///     "-123456789012345678901234567890"
+    /// );
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutData()`](https://reference.wolfram.com/language/ref/c/WSPutData.html)
+    pub fn put_bigint_str(&mut self, digits: &str) -> Result<(), Error> {
+        let magnitude = digits.strip_prefix('-').unwrap_or(digits);
+
+        if magnitude.is_empty() || !magnitude.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(Error::custom(format!(
+                "put_bigint_str(): not a valid integer: '{}'",
+                digits
+            )));
+        }
+
+        let len = i32::try_from(digits.len()).map_err(|_| {
+            Error::custom(format!(
+                "put_bigint_str(): integer has too many digits: {}",
+                digits.len()
+            ))
+        })?;
+
+        crate::strict::on_put_expr(self, "put_bigint_str")?;
+
+        let ok = unsafe {
+            sys::WSPutNext(self.raw_link, i32::from(sys::WSTKINT)) != 0
+                && sys::WSPutSize(self.raw_link, len) != 0
+                && sys::WSPutData(self.raw_link, digits.as_ptr() as *const c_char, len)
+                    != 0
+        };
+
+        if !ok {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
+    /// Put an integer of any size.
+    ///
+    /// This function is only available when the `num-bigint` feature is enabled.
+    ///
+    /// See [`Link::put_bigint_str()`].
+    #[cfg(feature = "num-bigint")]
+    pub fn put_bigint(&mut self, value: &num_bigint::BigInt) -> Result<(), Error> {
+        self.put_bigint_str(&value.to_string())
+    }
+}
+
+/// Split a real number in Wolfram Language syntax into its digits and its base 10
+/// exponent, discarding any precision or accuracy specification.
+///
+/// For example, `"1.5`20.*^30"` is split into `("1.5", Some("30"))`.
+#[cfg_attr(not(feature = "dashu-float"), allow(dead_code))]
+fn split_real_string(string: &str) -> (&str, Option<&str>) {
+    let (number, exponent) = match string.split_once("*^") {
+        Some((number, exponent)) => (number, Some(exponent)),
+        None => (string, None),
+    };
+
+    let mantissa = match number.split_once('`') {
+        Some((mantissa, _precision)) => mantissa,
+        None => number,
+    };
+
+    (mantissa, exponent)
+}
+
+#[test]
+fn test_split_real_string() {
+    assert_eq!(split_real_string("1.5"), ("1.5", None));
+    assert_eq!(split_real_string("1.5`20."), ("1.5", None));
+    assert_eq!(split_real_string("-1.5`20.*^-30"), ("-1.5", Some("-30")));
+    assert_eq!(split_real_string("1.5``10.*^30"), ("1.5", Some("30")));
+}
@@ -0,0 +1,179 @@
+//! Fluent API for writing expressions to a [`Link`].
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link, PutOnLink};
+
+/// Builder that writes a single expression to a [`Link`], checking that every function
+/// expression receives the number of arguments it was declared with.
+///
+/// Construct an [`ExprBuilder`] using [`Link::builder()`]. Each method writes part of
+/// the expression and returns the builder, so calls can be chained using `?`.
+/// [`ExprBuilder::finish()`] must be called once the expression is complete; it
+/// returns an error if any function expression is still missing arguments.
+///
+/// # Example
+///
+/// Write the expression `Plus[1, Times[2.5, x]]`:
+///
+/// ```
+/// use wstp::Link;
+///
+/// # fn example() -> Result<(), wstp::Error> {
+/// let mut link = Link::new_loopback()?;
+///
+/// link.builder()
+///     .function("System`Plus", 2)?
+///     .i64(1)?
+///     .function("System`Times", 2)?
+///     .f64(2.5)?
+///     .symbol("Global`x")?
+///     .finish()?;
+/// # Ok(())
+/// # }
+/// # example().unwrap();
+/// ```
+///
+/// Forgetting an argument is reported by [`ExprBuilder::finish()`]:
+///
+/// ```
+/// use wstp::Link;
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// let result = link
+///     .builder()
+///     .function("System`Plus", 2)
+///     .and_then(|builder| builder.i64(1))
+///     .and_then(|builder| builder.finish());
+///
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug)]
+pub struct ExprBuilder<'link> {
+    link: &'link mut Link,
+    /// Number of expressions still to be written for each function expression being
+    /// built, innermost last. The first entry is the top-level expression itself.
+    remaining: Vec<usize>,
+}
+
+/// # Building expressions
+impl Link {
+    /// Construct an [`ExprBuilder`] that writes a single expression to this link.
+    pub fn builder(&mut self) -> ExprBuilder {
+        ExprBuilder {
+            link: self,
+            remaining: vec![1],
+        }
+    }
+}
+
+impl<'link> ExprBuilder<'link> {
+    /// Write a function expression with `count` arguments.
+    ///
+    /// If `head` is `None`, the head of the function is the next expression written,
+    /// followed by the `count` arguments.
+    ///
+    /// See [`Link::put_function()`].
+    pub fn function<'h, H: Into<Option<&'h str>>>(
+        mut self,
+        head: H,
+        count: usize,
+    ) -> Result<Self, Error> {
+        let head = head.into();
+
+        self.begin("function")?;
+        self.link.put_function(head, count)?;
+
+        let pending = if head.is_some() { count } else { count + 1 };
+
+        if pending > 0 {
+            self.remaining.push(pending);
+        }
+
+        Ok(self)
+    }
+
+    /// Write an integer.
+    pub fn i64(mut self, value: i64) -> Result<Self, Error> {
+        self.begin("integer")?;
+        self.link.put_i64(value)?;
+        Ok(self)
+    }
+
+    /// Write a real number.
+    pub fn f64(mut self, value: f64) -> Result<Self, Error> {
+        self.begin("real")?;
+        self.link.put_f64(value)?;
+        Ok(self)
+    }
+
+    /// Write a string.
+    pub fn str(mut self, value: &str) -> Result<Self, Error> {
+        self.begin("string")?;
+        self.link.put_str(value)?;
+        Ok(self)
+    }
+
+    /// Write a symbol.
+    pub fn symbol(mut self, symbol: &str) -> Result<Self, Error> {
+        self.begin("symbol")?;
+        self.link.put_symbol(symbol)?;
+        Ok(self)
+    }
+
+    /// Write an [`Expr`].
+    pub fn expr(mut self, expr: &Expr) -> Result<Self, Error> {
+        self.begin("expression")?;
+        self.link.put_expr(expr)?;
+        Ok(self)
+    }
+
+    /// Write any value that implements [`PutOnLink`].
+    pub fn value<T: PutOnLink + ?Sized>(mut self, value: &T) -> Result<Self, Error> {
+        self.begin("value")?;
+        self.link.put(value)?;
+        Ok(self)
+    }
+
+    /// Returns `true` if the expression being built is complete.
+    pub fn is_complete(&self) -> bool {
+        self.remaining.is_empty()
+    }
+
+    /// Finish building the expression.
+    ///
+    /// Returns an error if the expression is not complete.
+    pub fn finish(self) -> Result<(), Error> {
+        match self.remaining.last() {
+            None => Ok(()),
+            Some(count) => Err(Error::custom(format!(
+                "ExprBuilder::finish(): expression is incomplete: {} more expression(s) \
+                 expected",
+                count
+            ))),
+        }
+    }
+
+    /// Claim the next argument slot for an expression of kind `what`, which is about to
+    /// be written.
+    fn begin(&mut self, what: &str) -> Result<(), Error> {
+        let count = match self.remaining.last_mut() {
+            Some(count) => count,
+            None => {
+                return Err(Error::custom(format!(
+                    "ExprBuilder: cannot write {}: expression is already complete",
+                    what
+                )))
+            },
+        };
+
+        *count -= 1;
+
+        while self.remaining.last() == Some(&0) {
+            self.remaining.pop();
+        }
+
+        Ok(())
+    }
+}
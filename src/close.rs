@@ -0,0 +1,164 @@
+//! Close behavior of [`Link`], and reporting of errors that occur when a link is closed.
+//!
+//! [`Link`] is closed when it is dropped, and [`Drop::drop()`] has no way to return an
+//! error to the caller. Without a hook, an error affecting the final data written to a
+//! link (for example, the link dying before buffered data was delivered) would go
+//! unnoticed.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    link_state,
+    sys::{self, WSLINK},
+    Error, Link,
+};
+
+/// What happens to data still waiting to be sent when a [`Link`] is closed.
+///
+/// See [`Link::set_close_mode()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CloseMode {
+    /// Close the link without first flushing it.
+    ///
+    /// WSTP does not guarantee that data still buffered when the link is closed will be
+    /// delivered to the peer.
+    ///
+    /// This is the default.
+    Abrupt,
+    /// Flush the link before closing it, so that all data written to the link is
+    /// delivered to the peer.
+    ///
+    /// If a [`Link`] using this mode is dropped and the flush fails, the error is passed
+    /// to the hook registered with [`set_close_error_hook()`].
+    Flush,
+}
+
+/// Hook registered with [`set_close_error_hook()`].
+pub type CloseErrorHook = Arc<dyn Fn(&Link, &Error) + Send + Sync + 'static>;
+
+static CLOSE_ERROR_HOOK: Lazy<RwLock<Option<CloseErrorHook>>> =
+    Lazy::new(|| RwLock::new(None));
+
+/// Register a process-wide hook that is called when an error occurs while a [`Link`] is
+/// being closed.
+///
+/// Currently, this is an error from the final flush of a link using
+/// [`CloseMode::Flush`]. Errors that the link was already in before it was closed are
+/// not reported.
+///
+/// The hook is called from [`Drop::drop()`], before [`WSClose()`][sys::WSClose] is
+/// called on the link. The [`Link`] passed to the hook is the link being closed.
+///
+/// Any previously registered hook is replaced. Panics that occur inside the hook are
+/// caught and ignored. The hook may itself call [`set_close_error_hook()`] or
+/// [`take_close_error_hook()`]; an invocation of the hook that is already in progress
+/// is not affected.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use wstp::Link;
+///
+/// wstp::set_close_error_hook(Arc::new(|link: &Link, err: &wstp::Error| {
+///     eprintln!("error closing link {}: {}", link.link_name(), err);
+/// }));
+/// ```
+pub fn set_close_error_hook(hook: CloseErrorHook) {
+    let mut guard = CLOSE_ERROR_HOOK
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    *guard = Some(hook);
+}
+
+/// Unregister the hook set by [`set_close_error_hook()`], returning it.
+pub fn take_close_error_hook() -> Option<CloseErrorHook> {
+    let mut guard = CLOSE_ERROR_HOOK
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    guard.take()
+}
+
+/// Report `err` to the registered close error hook, if any.
+fn report_close_error(link: &Link, err: &Error) {
+    // Note: The lock is released before the hook is called, so that the hook can
+    //       replace itself without deadlocking.
+    let hook: Option<CloseErrorHook> = CLOSE_ERROR_HOOK
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+
+    if let Some(hook) = hook {
+        // Don't let a panic in the user's hook unwind out of `Link::drop()`.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(link, err)));
+    }
+}
+
+/// # Close behavior
+impl Link {
+    /// Set what happens to data still waiting to be sent when this link is dropped.
+    ///
+    /// The default is [`CloseMode::Abrupt`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{CloseMode, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_close_mode(CloseMode::Flush);
+    ///
+    /// assert_eq!(link.close_mode(), CloseMode::Flush);
+    /// ```
+    pub fn set_close_mode(&mut self, mode: CloseMode) {
+        link_state::with(self.raw_link, |state| {
+            state.close_mode = match mode {
+                CloseMode::Abrupt => None,
+                CloseMode::Flush => Some(mode),
+            };
+        })
+    }
+
+    /// Get the [`CloseMode`] used when this link is dropped.
+    ///
+    /// See [`Link::set_close_mode()`].
+    pub fn close_mode(&self) -> CloseMode {
+        link_state::with_existing(self.raw_link, |state| state.close_mode)
+            .flatten()
+            .unwrap_or(CloseMode::Abrupt)
+    }
+}
+
+/// Called from `Link::drop()` before the link is closed.
+pub(crate) fn on_drop(link: &mut Link) {
+    match link.close_mode() {
+        CloseMode::Abrupt => (),
+        CloseMode::Flush => {
+            // Note: An error the link was already in is not caused by closing it.
+            if unsafe { sys::WSError(link.raw_link) } != sys::MLEOK {
+                return;
+            }
+
+            if let Err(err) = link.flush() {
+                report_close_error(link, &err);
+            }
+        },
+    }
+}
+
+/// Release the per-link state stored by this crate, and close `raw_link`.
+pub(crate) unsafe fn release(raw_link: WSLINK) {
+    link_state::close(raw_link);
+}
+
+/// Move the per-link state stored by this crate for `from` to `to`, discarding any state
+/// previously stored for `to`.
+pub(crate) fn transfer_state(from: WSLINK, to: WSLINK) {
+    link_state::transfer(from, to);
+}
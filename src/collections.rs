@@ -0,0 +1,154 @@
+//! Conversions between Wolfram Language collection expressions and Rust collections.
+
+use crate::{Error, Link};
+
+/// # Lists
+impl Link {
+    /// Put a `List[...]` expression containing `elements`.
+    ///
+    /// `put_element` is called to write each element to this link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_list(&[1.5, 2.5, 3.5], |link, value: &f64| link.put_f64(*value))
+    ///     .unwrap();
+    ///
+    /// let out: Vec<f64> = link.get_list(Link::get_f64).unwrap();
+    ///
+    /// assert_eq!(out, vec![1.5, 2.5, 3.5]);
+    /// ```
+    pub fn put_list<I, T, P>(
+        &mut self,
+        elements: I,
+        mut put_element: P,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+        P: FnMut(&mut Link, T) -> Result<(), Error>,
+    {
+        let elements = elements.into_iter();
+
+        self.put_function("System`List", elements.len())?;
+
+        for element in elements {
+            put_element(self, element)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a `List[...]` expression into a collection, e.g. a [`Vec`].
+    ///
+    /// `get_element` is called to read each element from this link.
+    ///
+    /// See [`Link::put_list()`] for an example.
+    pub fn get_list<C, T, G>(&mut self, mut get_element: G) -> Result<C, Error>
+    where
+        C: FromIterator<T>,
+        G: FnMut(&mut Link) -> Result<T, Error>,
+    {
+        let length = self.test_head("System`List")?;
+
+        (0..length).map(|_| get_element(self)).collect()
+    }
+}
+
+/// # Associations
+impl Link {
+    /// Put an `Association[key -> value, ...]` expression containing `entries`.
+    ///
+    /// `put_key` and `put_value` are called to write each key and value to this link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let map = BTreeMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+    ///
+    /// link.put_association(
+    ///     &map,
+    ///     |link, key: &String| link.put_str(key),
+    ///     |link, value: &i64| link.put_i64(*value),
+    /// )
+    /// .unwrap();
+    ///
+    /// let out: BTreeMap<String, i64> =
+    ///     link.get_association(Link::get_string, Link::get_i64).unwrap();
+    ///
+    /// assert_eq!(out, map);
+    /// ```
+    pub fn put_association<I, K, V, PK, PV>(
+        &mut self,
+        entries: I,
+        mut put_key: PK,
+        mut put_value: PV,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        I::IntoIter: ExactSizeIterator,
+        PK: FnMut(&mut Link, K) -> Result<(), Error>,
+        PV: FnMut(&mut Link, V) -> Result<(), Error>,
+    {
+        let entries = entries.into_iter();
+
+        self.put_function("System`Association", entries.len())?;
+
+        for (key, value) in entries {
+            self.put_function("System`Rule", 2)?;
+            put_key(self, key)?;
+            put_value(self, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read an `Association[key -> value, ...]` expression into a collection of
+    /// `(key, value)` pairs, e.g. a [`HashMap`][std::collections::HashMap] or
+    /// [`BTreeMap`][std::collections::BTreeMap].
+    ///
+    /// `get_key` and `get_value` are called to read each key and value from this link.
+    /// Both `Rule` and `RuleDelayed` entries are accepted.
+    ///
+    /// See [`Link::put_association()`] for an example.
+    pub fn get_association<C, K, V, GK, GV>(
+        &mut self,
+        mut get_key: GK,
+        mut get_value: GV,
+    ) -> Result<C, Error>
+    where
+        C: FromIterator<(K, V)>,
+        GK: FnMut(&mut Link) -> Result<K, Error>,
+        GV: FnMut(&mut Link) -> Result<V, Error>,
+    {
+        let length = self.test_head("System`Association")?;
+
+        (0..length)
+            .map(|_| {
+                let rule_length =
+                    self.expect_normal_with_head(&["System`Rule", "System`RuleDelayed"])?;
+
+                if rule_length != 2 {
+                    return Err(Error::custom(format!(
+                        "get_association(): expected rule with 2 arguments, got {}",
+                        rule_length
+                    )));
+                }
+
+                let key = get_key(self)?;
+                let value = get_value(self)?;
+
+                Ok((key, value))
+            })
+            .collect()
+    }
+}
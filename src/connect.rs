@@ -0,0 +1,183 @@
+//! Racing connection attempts to multiple addresses.
+
+use std::net::{self, SocketAddr};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Link, YieldParameters};
+
+/// Delay between starting consecutive connection attempts in
+/// [`Link::tcpip_connect_racing()`], as recommended by RFC 8305.
+pub const DEFAULT_CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Maximum time a single connection attempt made by [`Link::tcpip_connect_racing()`]
+/// may spend activating its link.
+const CONNECTION_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// # Racing connections
+impl Link {
+    /// Connect to an existing WSTP [`TCPIP`][crate::Protocol::TCPIP] link listening at
+    /// `addr`, racing connection attempts to each address.
+    ///
+    /// Unlike [`Link::tcpip_connect()`], which tries each address in turn and waits for
+    /// each attempt to fail before trying the next, this function starts a new attempt
+    /// every `attempt_delay` without waiting for earlier attempts to finish, and returns
+    /// the first link that connects successfully. Addresses are tried alternating
+    /// between IPv6 and IPv4 ("Happy Eyeballs", RFC 8305), so a single unreachable
+    /// address family does not delay the connection.
+    ///
+    /// The links are activated, so that a failure to reach the peer is detected before
+    /// this function returns. Attempts that are still activating when the race is over,
+    /// or that take longer than 30 seconds to activate, are abandoned, and their links
+    /// are closed.
+    ///
+    /// To control how the host name is resolved, pass a list of addresses resolved
+    /// ahead of time (e.g. `&[SocketAddr]`), or a custom type implementing
+    /// [`ToSocketAddrs`][net::ToSocketAddrs].
+    ///
+    /// If every attempt fails, the error from the last attempt to fail is returned.
+    pub fn tcpip_connect_racing<A: net::ToSocketAddrs>(
+        addr: A,
+        attempt_delay: Duration,
+    ) -> Result<Self, Error> {
+        let addrs: Vec<SocketAddr> = addr
+            .to_socket_addrs()
+            .map_err(|err| {
+                Error::with_source(
+                    format!("error connecting to TCPIP Link address: {}", err),
+                    err,
+                )
+            })?
+            .collect();
+
+        let addrs = interleave_address_families(addrs);
+
+        if addrs.is_empty() {
+            return Err(Error::custom("socket address list is empty".to_owned()));
+        }
+
+        let (sender, receiver) = mpsc::channel::<Result<Link, Error>>();
+
+        // Set when this function returns, to abandon attempts that are still in progress.
+        let race = RaceOver(Arc::new(AtomicBool::new(false)));
+
+        let mut started = 0;
+        let mut finished = 0;
+        let mut last_error = None;
+
+        for addr in addrs.iter().copied() {
+            let sender = sender.clone();
+            let is_over = Arc::clone(&race.0);
+
+            thread::spawn(move || {
+                let result = Link::tcpip_connect(addr).and_then(|mut link| {
+                    activate_attempt(&mut link, is_over)?;
+                    Ok(link)
+                });
+
+                // Note: If another attempt has already won, the receiver is gone, and
+                //       the link is closed when the unsent result is dropped.
+                let _ = sender.send(result);
+            });
+
+            started += 1;
+
+            // Wait for the next attempt to be started, returning early if an attempt
+            // succeeds in the meantime.
+            if started < addrs.len() {
+                while let Ok(result) = receiver.recv_timeout(attempt_delay) {
+                    finished += 1;
+
+                    match result {
+                        Ok(link) => return Ok(link),
+                        Err(err) => last_error = Some(err),
+                    }
+
+                    // If every attempt so far has failed, start the next one immediately.
+                    if finished == started {
+                        break;
+                    }
+                }
+            }
+        }
+
+        drop(sender);
+
+        while finished < started {
+            let result = match receiver.recv() {
+                Ok(result) => result,
+                Err(mpsc::RecvError) => break,
+            };
+
+            finished += 1;
+
+            match result {
+                Ok(link) => return Ok(link),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            Error::custom("all connection attempts failed".to_owned())
+        }))
+    }
+}
+
+/// Sets the wrapped flag when dropped.
+struct RaceOver(Arc<AtomicBool>);
+
+impl Drop for RaceOver {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Activate `link`, abandoning the attempt if `is_over` is set, or if activation takes
+/// longer than [`CONNECTION_ATTEMPT_TIMEOUT`].
+fn activate_attempt(link: &mut Link, is_over: Arc<AtomicBool>) -> Result<(), Error> {
+    let deadline = Instant::now() + CONNECTION_ATTEMPT_TIMEOUT;
+
+    // Note: WSActivate() calls the yield function while it is blocked, and returns an
+    //       error once the yield function returns `false`.
+    link.set_yield_function(move |_: YieldParameters| {
+        !is_over.load(Ordering::SeqCst) && Instant::now() < deadline
+    })?;
+
+    let result = link.activate();
+
+    link.clear_yield_function();
+
+    result
+}
+
+/// Reorder `addrs` so that IPv6 and IPv4 addresses alternate, starting with the family
+/// of the first address, while preserving the relative order within each family.
+fn interleave_address_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_v6 = match addrs.first() {
+        Some(addr) => addr.is_ipv6(),
+        None => return addrs,
+    };
+
+    let (mut primary, mut secondary): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_is_v6);
+
+    let mut result = Vec::with_capacity(primary.len() + secondary.len());
+
+    primary.reverse();
+    secondary.reverse();
+
+    loop {
+        match (primary.pop(), secondary.pop()) {
+            (None, None) => return result,
+            (first, second) => {
+                result.extend(first);
+                result.extend(second);
+            },
+        }
+    }
+}
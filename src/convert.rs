@@ -0,0 +1,367 @@
+//! Conversions between Rust values and Wolfram Language expressions on a [`Link`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use wolfram_expr::{Expr, Symbol};
+
+use crate::{Error, Link, TokenType};
+
+/// A value that can be written to a [`Link`] as a Wolfram Language expression.
+///
+/// | Rust type                             | Wolfram Language expression      |
+/// |---------------------------------------|----------------------------------|
+/// | integers, [`f32`], [`f64`]            | `Integer`, `Real`                |
+/// | [`bool`]                              | `True` or `False`                |
+/// | [`str`], [`String`]                   | `String`                         |
+/// | [`Symbol`], [`Expr`]                  | the symbol or expression         |
+/// | `[T]`, [`Vec<T>`], tuples             | `List[...]`                      |
+/// | [`Option<T>`]                         | the value, or `Null` if `None`   |
+/// | [`HashMap<K, V>`], [`BTreeMap<K, V>`] | `Association[key -> value, ...]` |
+///
+/// See [`Link::put()`].
+///
+/// # Deriving
+///
+/// When the `derive` feature is enabled, [`PutOnLink`] and [`GetFromLink`] can be
+/// derived for structs. By default, a struct is written as `Global`Name[field, ...]`.
+/// The `#[wstp(head = "...")]` attribute changes the head symbol, and
+/// `#[wstp(association)]` writes a struct with named fields as
+/// `Association["field" -> value, ...]` instead. When reading, both `"field" -> value`
+/// and `"field" :> value` rules are accepted.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use wstp::{GetFromLink, Link, PutOnLink};
+///
+/// #[derive(PutOnLink, GetFromLink, Debug, PartialEq)]
+/// #[wstp(head = "Global`Point")]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// link.put(&Point { x: 1.0, y: 2.0 }).unwrap();
+///
+/// assert_eq!(link.get::<Point>().unwrap(), Point { x: 1.0, y: 2.0 });
+/// # }
+/// ```
+pub trait PutOnLink {
+    /// Write this value to `link`.
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error>;
+}
+
+/// A value that can be read from a Wolfram Language expression on a [`Link`].
+///
+/// This trait is implemented for the same types as [`PutOnLink`], except [`str`] and
+/// slices. Reading an integer type returns an error if the value read does not fit in
+/// that type.
+///
+/// See [`Link::get()`].
+pub trait GetFromLink: Sized {
+    /// Read a value of this type from `link`.
+    fn get_from_link(link: &mut Link) -> Result<Self, Error>;
+}
+
+/// # Reading and writing Rust values
+impl Link {
+    /// Write `value` to this link.
+    ///
+    /// See [`PutOnLink`] for the expression each type is written as.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put(&(5, "five", vec![Some(2.5), None])).unwrap();
+    ///
+    /// let (int, string, reals): (i64, String, Vec<Option<f64>>) = link.get().unwrap();
+    ///
+    /// assert_eq!(int, 5);
+    /// assert_eq!(string, "five");
+    /// assert_eq!(reals, vec![Some(2.5), None]);
+    /// ```
+    pub fn put<T: PutOnLink + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.put_on_link(self)
+    }
+
+    /// Read a value of type `T` from this link.
+    ///
+    /// See [`Link::put()`] for an example.
+    pub fn get<T: GetFromLink>(&mut self) -> Result<T, Error> {
+        T::get_from_link(self)
+    }
+}
+
+//======================================
+// PutOnLink impls
+//======================================
+
+macro_rules! impl_put_on_link_for_int {
+    ($($type:ty),*) => {
+        $(
+            impl PutOnLink for $type {
+                fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+                    link.put_i64(i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_put_on_link_for_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl PutOnLink for f32 {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_f64(f64::from(*self))
+    }
+}
+
+impl PutOnLink for f64 {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_f64(*self)
+    }
+}
+
+impl PutOnLink for bool {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_symbol(if *self { "System`True" } else { "System`False" })
+    }
+}
+
+impl PutOnLink for str {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_str(self)
+    }
+}
+
+impl PutOnLink for String {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_str(self)
+    }
+}
+
+impl PutOnLink for Symbol {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_symbol(self.as_str())
+    }
+}
+
+impl PutOnLink for Expr {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_expr(self)
+    }
+}
+
+impl<T: PutOnLink + ?Sized> PutOnLink for &T {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        T::put_on_link(self, link)
+    }
+}
+
+impl<T: PutOnLink> PutOnLink for [T] {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_list(self, |link, element| element.put_on_link(link))
+    }
+}
+
+impl<T: PutOnLink> PutOnLink for Vec<T> {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        self.as_slice().put_on_link(link)
+    }
+}
+
+impl<T: PutOnLink> PutOnLink for Option<T> {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        match self {
+            Some(value) => value.put_on_link(link),
+            None => link.put_symbol("System`Null"),
+        }
+    }
+}
+
+impl<K: PutOnLink, V: PutOnLink, S> PutOnLink for HashMap<K, V, S> {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_association(
+            self,
+            |link, key| key.put_on_link(link),
+            |link, value| value.put_on_link(link),
+        )
+    }
+}
+
+impl<K: PutOnLink, V: PutOnLink> PutOnLink for BTreeMap<K, V> {
+    fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_association(
+            self,
+            |link, key| key.put_on_link(link),
+            |link, value| value.put_on_link(link),
+        )
+    }
+}
+
+//======================================
+// GetFromLink impls
+//======================================
+
+macro_rules! impl_get_from_link_for_int {
+    ($($type:ty),*) => {
+        $(
+            impl GetFromLink for $type {
+                fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+                    let value: i64 = link.get_i64()?;
+
+                    <$type>::try_from(value).map_err(|_| {
+                        Error::custom(format!(
+                            "integer {} read from link does not fit in {}",
+                            value,
+                            stringify!($type)
+                        ))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_get_from_link_for_int!(i8, i16, i32, u8, u16, u32);
+
+impl GetFromLink for i64 {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_i64()
+    }
+}
+
+impl GetFromLink for f32 {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_f32()
+    }
+}
+
+impl GetFromLink for f64 {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_f64()
+    }
+}
+
+impl GetFromLink for bool {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        let symbol = link.get_symbol()?;
+
+        match symbol.as_str() {
+            "System`True" => Ok(true),
+            "System`False" => Ok(false),
+            other => Err(Error::custom(format!(
+                "expected True or False, got symbol {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl GetFromLink for String {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_string()
+    }
+}
+
+impl GetFromLink for Symbol {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_symbol()
+    }
+}
+
+impl GetFromLink for Expr {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_expr()
+    }
+}
+
+impl<T: GetFromLink> GetFromLink for Vec<T> {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_list(T::get_from_link)
+    }
+}
+
+impl<T: GetFromLink> GetFromLink for Option<T> {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        let is_null = match link.get_type()? {
+            TokenType::Symbol => link.peek(|link| match link.get_symbol_ref() {
+                Ok(symbol) => symbol.as_str() == "System`Null",
+                Err(_) => false,
+            })?,
+            _ => false,
+        };
+
+        if is_null {
+            let _: Symbol = link.get_symbol()?;
+            return Ok(None);
+        }
+
+        T::get_from_link(link).map(Some)
+    }
+}
+
+impl<K, V> GetFromLink for HashMap<K, V>
+where
+    K: GetFromLink + Eq + Hash,
+    V: GetFromLink,
+{
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_association(K::get_from_link, V::get_from_link)
+    }
+}
+
+impl<K: GetFromLink + Ord, V: GetFromLink> GetFromLink for BTreeMap<K, V> {
+    fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+        link.get_association(K::get_from_link, V::get_from_link)
+    }
+}
+
+//======================================
+// Tuple impls
+//======================================
+
+macro_rules! impl_for_tuple {
+    ($len:literal; $($name:ident),+) => {
+        impl<$($name: PutOnLink),+> PutOnLink for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn put_on_link(&self, link: &mut Link) -> Result<(), Error> {
+                let ($($name,)+) = self;
+
+                link.put_function("System`List", $len)?;
+                $($name.put_on_link(link)?;)+
+
+                Ok(())
+            }
+        }
+
+        impl<$($name: GetFromLink),+> GetFromLink for ($($name,)+) {
+            fn get_from_link(link: &mut Link) -> Result<Self, Error> {
+                let length = link.test_head("System`List")?;
+
+                if length != $len {
+                    return Err(Error::custom(format!(
+                        "expected list of length {} for tuple, got length {}",
+                        $len, length
+                    )));
+                }
+
+                Ok(($($name::get_from_link(link)?,)+))
+            }
+        }
+    };
+}
+
+impl_for_tuple!(1; A);
+impl_for_tuple!(2; A, B);
+impl_for_tuple!(3; A, B, C);
+impl_for_tuple!(4; A, B, C, D);
+impl_for_tuple!(5; A, B, C, D, E);
+impl_for_tuple!(6; A, B, C, D, E, F);
@@ -0,0 +1,334 @@
+//! Raw transfer of the textual data of an atomic expression.
+
+use std::io;
+use std::os::raw::{c_char, c_int};
+
+use crate::{progress, sys, Error, Link, Progress, TokenType};
+
+/// Writer used to put the textual data of an atomic expression on a [`Link`] in
+/// pieces.
+///
+/// Returned by [`Link::begin_data()`].
+///
+/// [`LinkDataWriter::finish()`] must be called after all of the declared bytes have
+/// been written. If a `LinkDataWriter` is dropped before then, the data written to the
+/// link will be malformed.
+///
+/// [`LinkDataWriter`] implements [`io::Write`], so [`io::copy()`] can be used to write
+/// the contents of an [`io::Read`] source to a link.
+pub struct LinkDataWriter<'link> {
+    link: &'link mut Link,
+    /// Size declared to [`Link::begin_data()`].
+    total: usize,
+    /// Number of bytes that have not yet been written.
+    remaining: usize,
+}
+
+/// Reader used to get the textual data of an atomic expression from a [`Link`] in
+/// pieces.
+///
+/// Returned by [`Link::data_reader()`].
+///
+/// [`LinkDataReader`] implements [`io::Read`], so [`io::copy()`] can be used to write
+/// the data read from a link to an [`io::Write`] destination.
+pub struct LinkDataReader<'link> {
+    link: &'link mut Link,
+    token_type: TokenType,
+    /// Number of bytes read so far, reported to the progress observer.
+    transferred: u64,
+}
+
+/// # Raw data
+impl Link {
+    /// Get the number of bytes of the textual data of the current atomic expression
+    /// that remain to be read from this link.
+    ///
+    /// *WSTP C API Documentation:* [`WSBytesToGet()`](https://reference.wolfram.com/language/ref/c/WSBytesToGet.html)
+    pub fn bytes_to_get(&self) -> Result<usize, Error> {
+        let mut left: c_int = 0;
+
+        if unsafe { sys::WSBytesToGet(self.raw_link, &mut left) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        byte_count_to_usize(left, "WSBytesToGet()")
+    }
+
+    /// Get the number of bytes of the textual data of the current atomic expression
+    /// that remain to be written to this link.
+    ///
+    /// This is the size declared using `WSPutSize()`, minus the number of bytes
+    /// already written.
+    ///
+    /// *WSTP C API Documentation:* [`WSBytesToPut()`](https://reference.wolfram.com/language/ref/c/WSBytesToPut.html)
+    pub fn bytes_to_put(&self) -> Result<usize, Error> {
+        let mut left: c_int = 0;
+
+        if unsafe { sys::WSBytesToPut(self.raw_link, &mut left) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        byte_count_to_usize(left, "WSBytesToPut()")
+    }
+
+    /// Begin putting an atomic expression of type `token_type`, whose textual data is
+    /// exactly `size` bytes long and will be written in pieces using the returned
+    /// [`LinkDataWriter`].
+    ///
+    /// Returns an error if `token_type` is [`TokenType::Function`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use wstp::{Link, TokenType};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let mut writer = link.begin_data(TokenType::String, 11).unwrap();
+    /// writer.write_all(b"hello").unwrap();
+    /// writer.write_all(b" world").unwrap();
+    /// writer.finish().unwrap();
+    ///
+    /// assert_eq!(link.get_string().unwrap(), "hello world");
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutData()`](https://reference.wolfram.com/language/ref/c/WSPutData.html)
+    pub fn begin_data(
+        &mut self,
+        token_type: TokenType,
+        size: usize,
+    ) -> Result<LinkDataWriter, Error> {
+        let token = match token_type {
+            TokenType::Integer => sys::WSTKINT,
+            TokenType::Real => sys::WSTKREAL,
+            TokenType::String => sys::WSTKSTR,
+            TokenType::Symbol => sys::WSTKSYM,
+            TokenType::Function => {
+                return Err(Error::custom(
+                    "begin_data(): token type must be atomic, got Function".to_owned(),
+                ))
+            },
+        };
+
+        let abi_size = c_int::try_from(size).map_err(|_| {
+            Error::custom(format!("begin_data(): size overflows i32: {}", size))
+        })?;
+
+        crate::strict::on_begin_data(self, "begin_data", size)?;
+
+        let ok = unsafe {
+            sys::WSPutNext(self.raw_link, c_int::from(token)) != 0
+                && sys::WSPutSize(self.raw_link, abi_size) != 0
+        };
+
+        if !ok {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(LinkDataWriter {
+            link: self,
+            total: size,
+            remaining: size,
+        })
+    }
+
+    /// Begin getting the textual data of the next expression on this link, which must
+    /// be atomic, in pieces using the returned [`LinkDataReader`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use wstp::{Link, TokenType};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.put_str("hello world").unwrap();
+    ///
+    /// let mut reader = link.data_reader().unwrap();
+    /// assert_eq!(reader.token_type(), TokenType::String);
+    ///
+    /// let mut data = String::new();
+    /// reader.read_to_string(&mut data).unwrap();
+    ///
+    /// assert_eq!(data, "hello world");
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSGetData()`](https://reference.wolfram.com/language/ref/c/WSGetData.html)
+    pub fn data_reader(&mut self) -> Result<LinkDataReader, Error> {
+        let token = unsafe { sys::WSGetNext(self.raw_link) };
+
+        let token_type = match u8::try_from(token) {
+            Ok(sys::WSTKINT) => TokenType::Integer,
+            Ok(sys::WSTKREAL) => TokenType::Real,
+            Ok(sys::WSTKSTR) => TokenType::String,
+            Ok(sys::WSTKSYM) => TokenType::Symbol,
+            Ok(sys::WSTKFUNC) => {
+                return Err(Error::custom(
+                    "data_reader(): expected atomic expression, got function".to_owned(),
+                ))
+            },
+            _ => return Err(self.error_or_unknown()),
+        };
+
+        Ok(LinkDataReader {
+            link: self,
+            token_type,
+            transferred: 0,
+        })
+    }
+}
+
+impl<'link> LinkDataWriter<'link> {
+    /// Write the next piece of the data.
+    ///
+    /// Returns an error if `chunk` contains more bytes than remain to be written.
+    pub fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        if chunk.len() > self.remaining {
+            return Err(Error::custom(format!(
+                "LinkDataWriter: chunk of {} bytes exceeds the {} bytes remaining",
+                chunk.len(),
+                self.remaining
+            )));
+        }
+
+        // `chunk.len()` is at most the size passed to `begin_data()`, which fits in a
+        // c_int.
+        let len = chunk.len() as c_int;
+
+        let result = unsafe {
+            sys::WSPutData(self.link.raw_link, chunk.as_ptr() as *const c_char, len)
+        };
+
+        if result == 0 {
+            return Err(self.link.error_or_unknown());
+        }
+
+        self.remaining -= chunk.len();
+
+        if self.remaining == 0 {
+            crate::strict::on_data_complete(self.link);
+        }
+
+        progress::report(
+            self.link,
+            Progress {
+                transferred: (self.total - self.remaining) as u64,
+                total: Some(self.total as u64),
+            },
+        )
+    }
+
+    /// Returns the number of bytes that have not yet been written.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Finish writing the data.
+    ///
+    /// Returns an error if fewer bytes were written than were declared to
+    /// [`Link::begin_data()`].
+    pub fn finish(self) -> Result<(), Error> {
+        if self.remaining != 0 {
+            return Err(Error::custom(format!(
+                "LinkDataWriter: data is incomplete; {} more bytes expected",
+                self.remaining
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'link> io::Write for LinkDataWriter<'link> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = std::cmp::min(buf.len(), self.remaining);
+
+        if len == 0 && !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "LinkDataWriter: data is already complete",
+            ));
+        }
+
+        self.write_chunk(&buf[..len]).map_err(io::Error::other)?;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'link> LinkDataReader<'link> {
+    /// Get the type of the atomic expression being read.
+    pub fn token_type(&self) -> TokenType {
+        self.token_type
+    }
+
+    /// Returns the number of bytes that have not yet been read.
+    ///
+    /// See [`Link::bytes_to_get()`].
+    pub fn remaining(&self) -> Result<usize, Error> {
+        self.link.bytes_to_get()
+    }
+
+    /// Read the next piece of the data into `buffer`, returning the number of bytes
+    /// read.
+    ///
+    /// Returns `0` once all of the data has been read.
+    pub fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let left = self.link.bytes_to_get()?;
+
+        if buffer.is_empty() || left == 0 {
+            return Ok(0);
+        }
+
+        let len = c_int::try_from(buffer.len()).unwrap_or(c_int::MAX);
+        let mut got: c_int = 0;
+
+        let result = unsafe {
+            sys::WSGetData(
+                self.link.raw_link,
+                buffer.as_mut_ptr() as *mut c_char,
+                len,
+                &mut got,
+            )
+        };
+
+        if result == 0 {
+            return Err(self.link.error_or_unknown());
+        }
+
+        let got = byte_count_to_usize(got, "WSGetData()")?;
+
+        let total = self.transferred + left as u64;
+        self.transferred += got as u64;
+
+        progress::report(
+            self.link,
+            Progress {
+                transferred: self.transferred,
+                total: Some(total),
+            },
+        )?;
+
+        Ok(got)
+    }
+}
+
+impl<'link> io::Read for LinkDataReader<'link> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_chunk(buf).map_err(io::Error::other)
+    }
+}
+
+fn byte_count_to_usize(left: c_int, function: &str) -> Result<usize, Error> {
+    usize::try_from(left).map_err(|_| {
+        Error::custom(format!(
+            "invalid byte count returned by {}: {}",
+            function, left
+        ))
+    })
+}
@@ -0,0 +1,124 @@
+//! Readable formatting of the data available to read from a [`Link`], for debugging.
+
+use std::fmt::Write;
+
+use crate::{Error, Link, Token};
+
+/// Maximum number of tokens formatted by [`Link::dump_pending()`].
+const MAX_TOKENS: usize = 1000;
+
+/// Maximum number of characters of a string or symbol formatted by
+/// [`Link::dump_pending()`].
+const MAX_STRING_CHARS: usize = 60;
+
+/// # Debugging
+impl Link {
+    /// Format the data currently available to read from this link as a readable
+    /// string, without consuming it.
+    ///
+    /// Each token is printed on its own line, indented according to its nesting depth.
+    /// Long strings are truncated.
+    ///
+    /// This is intended to be used for `println!()` debugging of a stuck protocol
+    /// exchange. The format of the returned string is not stable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("System`List", 2).unwrap();
+    /// link.put_i64(5).unwrap();
+    /// link.put_str("second").unwrap();
+    ///
+    /// let dump = link.dump_pending().unwrap();
+    ///
+    /// assert!(dump.contains("\"second\""));
+    ///
+    /// // The data has not been consumed.
+    /// assert_eq!(link.test_head("System`List").unwrap(), 2);
+    /// ```
+    pub fn dump_pending(&mut self) -> Result<String, Error> {
+        self.peek(dump_tokens)
+    }
+}
+
+fn dump_tokens(link: &mut Link) -> String {
+    let mut out = String::new();
+
+    // Number of elements (including the head) remaining in each enclosing function.
+    let mut remaining: Vec<usize> = Vec::new();
+
+    for _ in 0..MAX_TOKENS {
+        if !link.is_ready() {
+            return out;
+        }
+
+        let indent = "  ".repeat(remaining.len());
+
+        let token = match link.get_token() {
+            Ok(token) => token,
+            Err(err) => {
+                let _ = writeln!(out, "{}<error: {}>", indent, err);
+                return out;
+            },
+        };
+
+        let is_function = match token {
+            Token::Integer(value) => {
+                let _ = writeln!(out, "{}{}", indent, value);
+                false
+            },
+            Token::Real(value) => {
+                let _ = writeln!(out, "{}{:?}", indent, value);
+                false
+            },
+            Token::String(ref string) => {
+                let _ = writeln!(out, "{}{:?}", indent, truncate(string.as_str()));
+                false
+            },
+            Token::Symbol(ref symbol) => {
+                let _ = writeln!(out, "{}{}", indent, truncate(symbol.as_str()));
+                false
+            },
+            Token::Function { length } => {
+                let _ = writeln!(out, "{}<function with {} element(s)>", indent, length);
+                remaining.push(length + 1);
+                true
+            },
+        };
+
+        if !is_function {
+            consume(&mut remaining);
+        }
+    }
+
+    out.push_str("...\n");
+
+    out
+}
+
+fn consume(remaining: &mut Vec<usize>) {
+    while let Some(innermost) = remaining.last_mut() {
+        *innermost -= 1;
+
+        if *innermost != 0 {
+            return;
+        }
+
+        // The function is complete, which fills a slot in its parent.
+        remaining.pop();
+    }
+}
+
+fn truncate(string: &str) -> String {
+    if string.chars().count() <= MAX_STRING_CHARS {
+        return string.to_owned();
+    }
+
+    let mut truncated: String = string.chars().take(MAX_STRING_CHARS).collect();
+    truncated.push_str("...");
+    truncated
+}
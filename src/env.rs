@@ -14,10 +14,23 @@
 //! hidden global variables in the WSTP C library. Where possible, `wstp` should avoid
 //! exposing this detail of the WSTP C API.
 //!
+//! The shared environment is initialized with default parameters the first time it is
+//! used. Programs that need different parameters can use [`EnvironmentBuilder`] to
+//! initialize it explicitly before any links are created, and programs that need
+//! deterministic teardown can use [`initialize()`] and [`Environment::shutdown()`].
+//!
+//! Programs that need isolated environments, e.g. with different parameters, can
+//! create them using [`EnvironmentBuilder::build()`], and create links in them using
+//! [`Link::new_loopback_in()`][crate::Link::new_loopback_in] and related methods.
+//!
 //! # Safety
 //!
-//! If the determination is made in the future to expose [`WstpEnv`] publically from `wstp`,
-//! some safety conditions will need to be satisfied:
+//! [`WstpEnv`] itself is not exposed publically from `wstp`; the public [`Environment`]
+//! type is only a handle to one. Because a [`Link`][crate::Link] does not keep a
+//! reference to the environment it was created in, an environment is never
+//! deinitialized implicitly when the last [`Environment`] handle is dropped. The
+//! following safety conditions must instead be satisfied by callers of
+//! [`Environment::shutdown()`]:
 //!
 //!   * A [`Link`][crate::Link] MUST NOT be able to outlive the `WstpEnv` that its
 //!     creation was associated with.
@@ -26,7 +39,8 @@
 
 use std::{
     ops::Deref,
-    sync::{Mutex, MutexGuard},
+    os::raw::{c_long, c_ulong},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
 use once_cell::sync::Lazy;
@@ -36,8 +50,68 @@ use crate::{sys, Error};
 /// The standard WSTP environment object.
 ///
 /// *WSTP C API Documentation:* [`stdenv`](https://reference.wolfram.com/language/ref/c/stdenv.html)
-static STDENV: Lazy<Mutex<Option<WstpEnv>>> =
-    Lazy::new(|| Mutex::new(Some(initialize().unwrap())));
+static STDENV: Lazy<Arc<Mutex<EnvState>>> =
+    Lazy::new(|| Arc::new(Mutex::new(EnvState::Uninitialized)));
+
+#[derive(Debug)]
+enum EnvState {
+    /// The environment will be initialized with default parameters the first time it
+    /// is used. Only [`struct@STDENV`] is ever in this state.
+    Uninitialized,
+    Initialized(WstpEnv),
+    /// [`Environment::shutdown()`] or [`shutdown()`] has been called.
+    Shutdown,
+}
+
+/// Builder for the parameters used to initialize the WSTP environment.
+///
+/// The WSTP environment used by this library is initialized automatically, with
+/// default parameters, the first time it is needed (typically when the first
+/// [`Link`][crate::Link] is created). To use different parameters, call
+/// [`EnvironmentBuilder::initialize()`] before any links are created.
+///
+/// # Example
+///
+/// Prevent WSTP from installing its own handler for `SIGINT`:
+///
+/// ```
+/// use wstp::EnvironmentBuilder;
+///
+/// const SIGINT: i32 = 2;
+///
+/// EnvironmentBuilder::new()
+///     .do_not_handle_signal(SIGINT)
+///     .initialize()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvironmentBuilder {
+    thread_safe_links: bool,
+    unhandled_signals: Vec<i32>,
+    no_signal_handlers: bool,
+}
+
+/// Signals which WSTP installs handlers for that conflict with the signal handling of
+/// typical Rust host applications. See [`EnvironmentBuilder::handle_signals()`].
+///
+/// These signal numbers are the same on Linux, macOS, and Windows.
+const CONFLICTING_SIGNALS: [i32; 2] = [
+    2,  // SIGINT
+    11, // SIGSEGV
+];
+
+/// Handle to a WSTP environment.
+///
+/// This is either a handle to the shared environment used by default by this library,
+/// returned by [`initialize()`] and [`EnvironmentBuilder::initialize()`], or to an
+/// isolated environment created using [`EnvironmentBuilder::build()`].
+///
+/// The environment remains initialized when this handle is dropped. Use
+/// [`Environment::shutdown()`] to deinitialize it.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    state: Arc<Mutex<EnvState>>,
+}
 
 /// Private. A WSTP library environment.
 ///
@@ -45,27 +119,193 @@ static STDENV: Lazy<Mutex<Option<WstpEnv>>> =
 ///
 /// See [`initialize()`].
 ///
+/// This type intentionally does not implement [`Drop`]; see the note on
+/// [`crate::env`].
+///
 /// *WSTP C API Documentation:* [`WSENV`](https://reference.wolfram.com/language/ref/c/WSENV.html).
+#[derive(Debug)]
 pub(crate) struct WstpEnv {
     pub raw_env: sys::WSENV,
 }
 
 unsafe impl Send for WstpEnv {}
 
-/// An RAII guard that provides scoped access to the `STDENV` static.
-pub(crate) struct StdEnv {
-    guard: MutexGuard<'static, Option<WstpEnv>>,
+/// An RAII guard that provides scoped access to an initialized WSTP environment.
+pub(crate) struct EnvGuard<'a> {
+    guard: MutexGuard<'a, EnvState>,
 }
 
-impl Deref for StdEnv {
+impl Deref for EnvGuard<'_> {
     type Target = WstpEnv;
 
     fn deref(&self) -> &WstpEnv {
-        match self.guard.as_ref() {
-            Some(stdenv) => stdenv,
-            None => panic!("STDENV has been shutdown."),
+        match *self.guard {
+            EnvState::Initialized(ref env) => env,
+            EnvState::Uninitialized | EnvState::Shutdown => {
+                unreachable!("EnvGuard for uninitialized WSTP environment")
+            },
+        }
+    }
+}
+
+impl EnvironmentBuilder {
+    /// Construct a builder with the default environment parameters.
+    pub fn new() -> Self {
+        EnvironmentBuilder::default()
+    }
+
+    /// If `enable` is `true`, the links created in this environment can safely be used
+    /// from multiple threads at the same time.
+    pub fn thread_safe_links(mut self, enable: bool) -> Self {
+        self.thread_safe_links = enable;
+        self
+    }
+
+    /// Prevent WSTP from installing its own handler for the signal `signum`.
+    ///
+    /// By default, WSTP installs handlers for several signals (e.g. `SIGINT` and
+    /// `SIGPIPE`) when the environment is initialized, which can conflict with the
+    /// signal handling of the host application.
+    pub fn do_not_handle_signal(mut self, signum: i32) -> Self {
+        self.unhandled_signals.push(signum);
+        self
+    }
+
+    /// If `enable` is `false`, prevent WSTP from installing its own handlers for
+    /// `SIGINT` and `SIGSEGV`.
+    ///
+    /// The handlers WSTP installs for these signals replace those of the host
+    /// application, e.g. preventing Ctrl-C from being handled by the application, or
+    /// interfering with the stack overflow detection of the Rust runtime.
+    ///
+    /// Other signals can be excluded using
+    /// [`do_not_handle_signal()`][EnvironmentBuilder::do_not_handle_signal].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::EnvironmentBuilder;
+    ///
+    /// EnvironmentBuilder::new()
+    ///     .handle_signals(false)
+    ///     .initialize()
+    ///     .unwrap();
+    /// ```
+    pub fn handle_signals(mut self, enable: bool) -> Self {
+        self.no_signal_handlers = !enable;
+        self
+    }
+
+    /// Initialize the WSTP environment used by this library with these parameters.
+    ///
+    /// Returns an error if the environment has already been initialized, either by a
+    /// previous call to this function or because it was used to create a link.
+    ///
+    /// Use [`EnvironmentBuilder::build()`] to create a separate environment instead.
+    pub fn initialize(self) -> Result<Environment, Error> {
+        let mut guard = lock_state(&STDENV)?;
+
+        match *guard {
+            EnvState::Uninitialized => (),
+            EnvState::Initialized(_) => {
+                return Err(Error::custom(
+                    "EnvironmentBuilder::initialize(): the WSTP environment has already \
+                     been initialized"
+                        .to_owned(),
+                ))
+            },
+            EnvState::Shutdown => {
+                return Err(Error::custom(
+                    "EnvironmentBuilder::initialize(): the WSTP environment has been \
+                     shut down"
+                        .to_owned(),
+                ))
+            },
+        }
+
+        *guard = EnvState::Initialized(init_env(&self)?);
+
+        Ok(Environment::shared())
+    }
+
+    /// Create a new WSTP environment with these parameters, separate from the shared
+    /// environment used by default by this library.
+    ///
+    /// Links can be created in the returned environment using
+    /// [`Link::new_loopback_in()`][crate::Link::new_loopback_in],
+    /// [`Link::listen_in()`][crate::Link::listen_in],
+    /// [`Link::connect_in()`][crate::Link::connect_in], and
+    /// [`Link::open_with_args_in()`][crate::Link::open_with_args_in].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{EnvironmentBuilder, Link};
+    ///
+    /// let env = EnvironmentBuilder::new().build().unwrap();
+    ///
+    /// let mut link = Link::new_loopback_in(&env).unwrap();
+    ///
+    /// link.put_i64(5).unwrap();
+    /// assert_eq!(link.get_i64().unwrap(), 5);
+    ///
+    /// drop(link);
+    ///
+    /// // SAFETY: All links created in `env` have been closed.
+    /// unsafe { env.shutdown().unwrap() };
+    /// ```
+    pub fn build(self) -> Result<Environment, Error> {
+        let env = init_env(&self)?;
+
+        Ok(Environment {
+            state: Arc::new(Mutex::new(EnvState::Initialized(env))),
+        })
+    }
+
+    /// Apply these parameters to `params`, which was created by `WSNewParameters()`.
+    ///
+    /// Note: Errors are constructed using [`Error::custom()`], because there is no
+    ///       initialized environment that could be used to describe the error code.
+    unsafe fn apply(&self, params: sys::WSEnvironmentParameter) -> Result<(), Error> {
+        let EnvironmentBuilder {
+            thread_safe_links,
+            ref unhandled_signals,
+            no_signal_handlers,
+        } = *self;
+
+        if thread_safe_links {
+            check_parameter(
+                "WSSetThreadSafeLinksParameter",
+                sys::WSSetThreadSafeLinksParameter(params),
+            )?;
+        }
+
+        let default_unhandled: &[i32] = if no_signal_handlers {
+            &CONFLICTING_SIGNALS
+        } else {
+            &[]
+        };
+
+        for &signum in unhandled_signals.iter().chain(default_unhandled) {
+            check_parameter(
+                "WSDoNotHandleSignalParameter",
+                sys::WSDoNotHandleSignalParameter(params, signum),
+            )?;
         }
+
+        Ok(())
+    }
+}
+
+fn check_parameter(name: &str, code: c_long) -> Result<(), Error> {
+    if code != c_long::from(sys::MLEOK) {
+        return Err(Error::custom(format!(
+            "{}() failed with WSTP error code {}",
+            name, code
+        )));
     }
+
+    Ok(())
 }
 
 /// Private.
@@ -73,25 +313,111 @@ impl Deref for StdEnv {
 /// NOTE: This function should remain private. See note on [`crate::env`].
 ///
 /// *WSTP C API Documentation:* [`WSInitialize()`](https://reference.wolfram.com/language/ref/c/WSInitialize.html)
-fn initialize() -> Result<WstpEnv, Error> {
+fn init_env(builder: &EnvironmentBuilder) -> Result<WstpEnv, Error> {
     let raw_env: sys::WSENV;
 
     // TODO: Is this thread-safe?
     //       Is it safe to call WSInitialize() multiple times in the same process?
     unsafe {
-        raw_env = sys::WSInitialize(std::ptr::null_mut());
+        let params = sys::WSNewParameters(
+            sys::WSREVISION as c_ulong,
+            sys::WSAPIREVISION as c_ulong,
+        );
+
+        if params.is_null() {
+            return Err(Error::custom("WSNewParameters() failed".to_owned()));
+        }
+
+        let result = builder.apply(params).map(|()| sys::WSInitialize(params));
+
+        sys::WSReleaseParameters(params);
+
+        raw_env = result?;
     }
 
     if raw_env.is_null() {
         return Err(Error::custom(
             // TODO: Is there an internal error string which could be included here?
-            format!("WSInitialize() failed"),
+            "WSInitialize() failed".to_owned(),
         ));
     }
 
     Ok(WstpEnv { raw_env })
 }
 
+/// Initialize the WSTP environment used by this library, if it has not been
+/// initialized already.
+///
+/// The environment is initialized automatically the first time it is needed, so
+/// calling this function is optional. It is useful for controlling when initialization
+/// happens, and for obtaining an [`Environment`] handle which can later be used to
+/// deinitialize the environment using [`Environment::shutdown()`].
+///
+/// Returns an error if the environment has been shut down.
+///
+/// # Example
+///
+/// ```
+/// let env = wstp::initialize().unwrap();
+///
+/// assert!(wstp::is_initialized());
+///
+/// // ... create, use, and close links ...
+///
+/// // SAFETY: All links created by this program have been closed.
+/// unsafe { env.shutdown().unwrap() };
+///
+/// assert!(!wstp::is_initialized());
+/// ```
+pub fn initialize() -> Result<Environment, Error> {
+    let _: EnvGuard = stdenv()?;
+
+    Ok(Environment::shared())
+}
+
+/// Returns `true` if the WSTP environment used by this library is currently
+/// initialized.
+pub fn is_initialized() -> bool {
+    match STDENV.lock() {
+        Ok(guard) => matches!(*guard, EnvState::Initialized(_)),
+        Err(_) => false,
+    }
+}
+
+impl Environment {
+    /// Get a handle to the shared environment used by default by this library.
+    pub(crate) fn shared() -> Self {
+        Environment {
+            state: Arc::clone(&STDENV),
+        }
+    }
+
+    /// Acquire a lock on this environment, initializing it with the default parameters
+    /// if necessary.
+    pub(crate) fn lock(&self) -> Result<EnvGuard, Error> {
+        lock_env(&self.state)
+    }
+
+    /// Deinitialize this WSTP environment.
+    ///
+    /// The WSTP C library runs several background threads. Shutting down the
+    /// environment before `main()` returns ensures that these threads have stopped,
+    /// avoiding an ungraceful shutdown.
+    ///
+    /// Once the environment has been shut down, it cannot be initialized again.
+    ///
+    /// # Safety
+    ///
+    /// All [`Link`][crate::Link] objects created in this environment must have been
+    /// dropped before this function is called.
+    ///
+    /// *WSTP C API Documentation:* [`WSDeinitialize()`](https://reference.wolfram.com/language/ref/c/WSDeinitialize.html)
+    pub unsafe fn shutdown(self) -> Result<(), Error> {
+        let _: bool = shutdown_state(&self.state)?;
+
+        Ok(())
+    }
+}
 
 /// Deinitialize the [`WSENV`] static maintained by this library.
 ///
@@ -101,6 +427,8 @@ fn initialize() -> Result<WstpEnv, Error> {
 /// error messages being printed. This function is an escape hatch to permit users of this
 /// library to ensure that all background thread shutdown before `main()` returns.
 ///
+/// Prefer [`Environment::shutdown()`], using the handle returned by [`initialize()`].
+///
 /// TODO: Make this function obsolete, either by changing the WSTP C library
 ///       implementation, or, perhaps easier, maintain a reference count of the number of
 ///       [`Link`] objects that have been created, and (re-)initialize and deinitialize
@@ -114,15 +442,18 @@ fn initialize() -> Result<WstpEnv, Error> {
 /// [`Link`] objects created by your code have been dropped.
 #[doc(hidden)]
 pub unsafe fn shutdown() -> Result<bool, Error> {
-    let mut guard = STDENV.lock().map_err(|err| {
-        Error::custom(format!("Unable to acquire lock on STDENV: {}", err))
-    })?;
-
-    let was_initialized = if let Some(env) = guard.take() {
-        env.deinitialize();
-        true
-    } else {
-        false
+    shutdown_state(&STDENV)
+}
+
+unsafe fn shutdown_state(state: &Mutex<EnvState>) -> Result<bool, Error> {
+    let mut guard = lock_state(state)?;
+
+    let was_initialized = match std::mem::replace(&mut *guard, EnvState::Shutdown) {
+        EnvState::Initialized(env) => {
+            env.deinitialize();
+            true
+        },
+        EnvState::Uninitialized | EnvState::Shutdown => false,
     };
 
     Ok(was_initialized)
@@ -143,21 +474,35 @@ impl WstpEnv {
     }
 }
 
-/// Acquire a lock on [`struct@STDENV`].
-pub(crate) fn stdenv() -> Result<StdEnv, Error> {
-    let guard = STDENV.lock().map_err(|err| {
-        Error::custom(format!("Unable to acquire lock on STDENV: {}", err))
-    })?;
-
-    Ok(StdEnv { guard })
+/// Acquire a lock on [`struct@STDENV`], initializing it with the default parameters if
+/// necessary.
+pub(crate) fn stdenv() -> Result<EnvGuard<'static>, Error> {
+    lock_env(&STDENV)
 }
 
-impl Drop for WstpEnv {
-    fn drop(&mut self) {
-        let WstpEnv { raw_env } = *self;
+fn lock_env(state: &Mutex<EnvState>) -> Result<EnvGuard, Error> {
+    let mut guard = lock_state(state)?;
 
-        unsafe {
-            sys::WSDeinitialize(raw_env);
-        }
+    match *guard {
+        EnvState::Uninitialized => {
+            *guard = EnvState::Initialized(init_env(&EnvironmentBuilder::new())?);
+        },
+        EnvState::Initialized(_) => (),
+        EnvState::Shutdown => {
+            return Err(Error::custom(
+                "the WSTP environment has been shut down".to_owned(),
+            ))
+        },
     }
+
+    Ok(EnvGuard { guard })
+}
+
+fn lock_state(state: &Mutex<EnvState>) -> Result<MutexGuard<EnvState>, Error> {
+    state.lock().map_err(|err| {
+        Error::custom(format!(
+            "Unable to acquire lock on WSTP environment: {}",
+            err
+        ))
+    })
 }
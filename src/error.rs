@@ -2,15 +2,128 @@ use std::{
     ffi::CStr,
     fmt::{self, Debug, Display},
     os::raw::c_char,
+    sync::Arc,
 };
 
 /// WSTP link error.
 ///
-/// Use [`Error::code()`] to retrieve the WSTP error code, if applicable.
-#[derive(Clone, PartialEq)]
+/// Use [`Error::kind()`] to determine the category of the error, and
+/// [`Error::code()`] to retrieve the WSTP error code, if applicable.
+///
+/// Use [`Error::is_link_dead()`] and [`Error::is_closed_by_peer()`] to check whether the
+/// link an error occurred on can still be used.
+///
+/// Use [`Error::is_timeout()`] to check whether an operation failed because it did not
+/// complete within its timeout.
+///
+/// Use [`Error::is_shutdown()`] to check whether an operation failed because the
+/// [`LinkServer`][crate::LinkServer] it was waiting on was shut down.
+///
+/// If this error was caused by another error, e.g. an [`std::io::Error`] or a failed
+/// numeric conversion, the underlying error is available from
+/// [`std::error::Error::source()`].
+///
+/// Errors that occur while reading a nested expression record where in the expression
+/// they occurred; see [`Error::context()`].
+#[derive(Clone)]
 pub struct Error {
     pub(crate) code: Option<i32>,
     pub(crate) message: String,
+    pub(crate) kind: ErrorKind,
+    pub(crate) source: Option<Arc<dyn std::error::Error + Send + Sync + 'static>>,
+    /// Descriptions of the operations being performed when this error occurred,
+    /// innermost first.
+    pub(crate) context: Vec<String>,
+}
+
+/// Category of an [`Error`].
+///
+/// Returned by [`Error::kind()`].
+///
+/// Errors reported by the WSTP library are categorized by their error code. Codes that
+/// do not correspond to one of the variants below are represented as
+/// [`ErrorKind::Other`].
+///
+/// See the [`WSError()`](https://reference.wolfram.com/language/ref/c/WSError.html)
+/// documentation for a description of each WSTP error code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The link died unexpectedly. ([`WSEDEAD`][crate::sys::WSEDEAD])
+    Dead,
+    /// The other side of the link closed the connection.
+    /// ([`WSECLOSED`][crate::sys::WSECLOSED])
+    Closed,
+    /// Memory could not be allocated. ([`WSEMEM`][crate::sys::WSEMEM])
+    OutOfMemory,
+    /// Data was read in an order inconsistent with the data available on the link.
+    /// ([`WSEGSEQ`][crate::sys::WSEGSEQ])
+    GetSequence,
+    /// Data was written in an inconsistent order. ([`WSEPSEQ`][crate::sys::WSEPSEQ])
+    PutSequence,
+    /// The data read was inconsistent or could not be converted to the requested type.
+    /// ([`WSEGBAD`][crate::sys::WSEGBAD], [`WSEOVFL`][crate::sys::WSEOVFL],
+    /// [`WSEGSCONVERT`][crate::sys::WSEGSCONVERT])
+    BadData,
+    /// A WSTP protocol error occurred. ([`WSEPROTOCOL`][crate::sys::WSEPROTOCOL])
+    Protocol,
+    /// An invalid argument or parameter was passed to a WSTP function.
+    /// ([`WSEBADARGUMENT`][crate::sys::WSEBADARGUMENT],
+    /// [`WSEBADPARAM`][crate::sys::WSEBADPARAM])
+    BadArgument,
+    /// The requested link name is already in use.
+    /// ([`WSENAMETAKEN`][crate::sys::WSENAMETAKEN])
+    NameTaken,
+    /// A connection could not be established.
+    /// ([`WSECONNECT`][crate::sys::WSECONNECT], [`WSEACCEPT`][crate::sys::WSEACCEPT])
+    Connect,
+    /// A program could not be launched. ([`WSELAUNCH`][crate::sys::WSELAUNCH],
+    /// [`WSELAUNCHFAILED`][crate::sys::WSELAUNCHFAILED])
+    Launch,
+    /// The operation was aborted. ([`WSEABORT`][crate::sys::WSEABORT])
+    Aborted,
+    /// A blocking operation was abandoned because the yield function of the link
+    /// returned `false`, instead of waiting for the operation to complete.
+    /// ([`WSEBACKOUT`][crate::sys::WSEBACKOUT])
+    ///
+    /// See [`Error::is_would_block()`].
+    WouldBlock,
+    /// An operation did not complete within its timeout.
+    ///
+    /// See [`Error::is_timeout()`].
+    TimedOut,
+    /// The [`LinkServer`][crate::LinkServer] being waited on was shut down.
+    ///
+    /// See [`Error::is_shutdown()`].
+    Shutdown,
+    /// An error detected by this library, rather than reported by WSTP, e.g. because
+    /// the data read from a link did not have the expected shape.
+    Custom,
+    /// A WSTP error code without a more specific [`ErrorKind`].
+    Other(i32),
+}
+
+impl ErrorKind {
+    pub(crate) fn from_code(code: i32) -> Self {
+        use crate::sys;
+
+        match code {
+            sys::WSEDEAD => ErrorKind::Dead,
+            sys::WSECLOSED => ErrorKind::Closed,
+            sys::WSEMEM => ErrorKind::OutOfMemory,
+            sys::WSEGSEQ => ErrorKind::GetSequence,
+            sys::WSEPSEQ => ErrorKind::PutSequence,
+            sys::WSEGBAD | sys::WSEOVFL | sys::WSEGSCONVERT => ErrorKind::BadData,
+            sys::WSEPROTOCOL => ErrorKind::Protocol,
+            sys::WSEBADARGUMENT | sys::WSEBADPARAM => ErrorKind::BadArgument,
+            sys::WSENAMETAKEN => ErrorKind::NameTaken,
+            sys::WSECONNECT | sys::WSEACCEPT => ErrorKind::Connect,
+            sys::WSELAUNCH | sys::WSELAUNCHFAILED => ErrorKind::Launch,
+            sys::WSEABORT => ErrorKind::Aborted,
+            sys::WSEBACKOUT => ErrorKind::WouldBlock,
+            other => ErrorKind::Other(other),
+        }
+    }
 }
 
 impl Error {
@@ -22,51 +135,240 @@ impl Error {
         self.code
     }
 
+    /// Get the category of this error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{ErrorKind, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.put_str("not an integer").unwrap();
+    ///
+    /// let err = link.get_i64().unwrap_err();
+    ///
+    /// assert_eq!(err.kind(), ErrorKind::GetSequence);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Get descriptions of the operations that were being performed when this error
+    /// occurred, innermost first.
+    ///
+    /// These are also included in the [`Display`] output of this error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("System`List", 2).unwrap();
+    /// link.put_i64(1).unwrap();
+    /// link.put_symbol("x").unwrap();
+    ///
+    /// let err = link.get_expr().unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.context(),
+    ///     ["while reading argument 2 of 2 of a function expression"]
+    /// );
+    /// ```
+    pub fn context(&self) -> &[String] {
+        &self.context
+    }
+
+    /// Returns `true` if the link this error occurred on is no longer usable, either
+    /// because it died unexpectedly or because the other side closed the connection.
+    ///
+    /// Errors of this kind are not caused by incorrect use of the link; a common way to
+    /// handle them is to establish a new connection.
+    ///
+    /// See also [`Error::is_closed_by_peer()`].
+    pub fn is_link_dead(&self) -> bool {
+        matches!(self.kind, ErrorKind::Dead | ErrorKind::Closed)
+    }
+
+    /// Returns `true` if this error occurred because the other side of the link closed
+    /// the connection.
+    ///
+    /// This corresponds to the [`ErrorKind::Closed`] error kind.
+    pub fn is_closed_by_peer(&self) -> bool {
+        self.kind == ErrorKind::Closed
+    }
+
+    /// Returns `true` if this error occurred because a blocking operation was abandoned
+    /// instead of waiting for the link to become ready.
+    ///
+    /// This corresponds to the [`ErrorKind::WouldBlock`] error kind, which is reported
+    /// when the yield function of the link (see
+    /// [`Link::set_yield_function()`][crate::Link::set_yield_function]) returns `false`
+    /// while a call is blocked. The operation can be retried once the error has been
+    /// cleared using [`Link::clear_error()`][crate::Link::clear_error].
+    pub fn is_would_block(&self) -> bool {
+        self.kind == ErrorKind::WouldBlock
+    }
+
+    /// Returns `true` if this error occurred because an operation did not complete
+    /// within its timeout.
+    ///
+    /// See e.g. [`Link::get_expr_with_timeout()`][crate::Link::get_expr_with_timeout].
+    pub fn is_timeout(&self) -> bool {
+        self.kind == ErrorKind::TimedOut
+    }
+
+    /// Returns `true` if this error occurred because the
+    /// [`LinkServer`][crate::LinkServer] being waited on was shut down.
+    ///
+    /// See [`LinkServer::shutdown()`][crate::LinkServer::shutdown].
+    pub fn is_shutdown(&self) -> bool {
+        self.kind == ErrorKind::Shutdown
+    }
+
     pub(crate) fn custom(message: String) -> Self {
         Error {
             code: None,
             message,
+            kind: ErrorKind::Custom,
+            source: None,
+            context: Vec::new(),
+        }
+    }
+
+    /// Construct an error caused by `source`, which will be returned by
+    /// [`std::error::Error::source()`].
+    pub(crate) fn with_source<E>(message: String, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Error {
+            source: Some(Arc::new(source)),
+            ..Error::custom(message)
+        }
+    }
+
+    /// Record that this error occurred while performing the operation described by
+    /// `context`.
+    pub(crate) fn with_context<F>(mut self, context: F) -> Self
+    where
+        F: FnOnce() -> String,
+    {
+        self.context.push(context());
+        self
+    }
+
+    pub(crate) fn timeout(timeout: std::time::Duration) -> Self {
+        Error {
+            code: None,
+            message: format!("timed out after {:?} waiting for data", timeout),
+            kind: ErrorKind::TimedOut,
+            source: None,
+            context: Vec::new(),
+        }
+    }
+
+    pub(crate) fn link_server_shutdown() -> Self {
+        Error {
+            code: None,
+            message: "LinkServer was shut down".to_owned(),
+            kind: ErrorKind::Shutdown,
+            source: None,
+            context: Vec::new(),
         }
     }
 
+    /// Construct an error from a WSTP error `code`, without a description of the
+    /// code.
+    ///
+    /// Note: This intentionally does not look up the error string using the shared
+    ///       WSTP environment, because doing so would initialize that environment with
+    ///       the default parameters, preventing a later call to
+    ///       [`EnvironmentBuilder::initialize()`][crate::EnvironmentBuilder::initialize].
+    ///       Use [`Error::from_code_in()`] when the environment is known.
     pub(crate) fn from_code(code: i32) -> Self {
-        // Lookup the error string describing this error code.
-        let message: String = crate::env::stdenv()
-            .ok()
-            .and_then(|stdenv| unsafe {
-                let code_long = std::os::raw::c_long::try_from(code).unwrap();
+        Error::from_code_with_message(code, None)
+    }
 
-                // Note: We do not need to free this, because it's scoped to our eternal
-                //       STDENV instance.
-                let message_cptr: *const c_char =
-                    crate::sys::WSErrorString(stdenv.raw_env, code_long);
+    /// Construct an error from a WSTP error `code`, using `raw_env` to look up the
+    /// string describing the code.
+    ///
+    /// *WSTP C API Documentation:* [`WSErrorString()`](https://reference.wolfram.com/language/ref/c/WSErrorString.html)
+    pub(crate) fn from_code_in(raw_env: crate::sys::WSENV, code: i32) -> Self {
+        let message: Option<String> = unsafe {
+            let code_long = std::os::raw::c_long::try_from(code).unwrap();
+
+            // Note: We do not need to free this, because it's scoped to `raw_env`.
+            let message_cptr: *const c_char =
+                crate::sys::WSErrorString(raw_env, code_long);
 
-                if message_cptr.is_null() {
-                    return None;
-                }
+            if message_cptr.is_null() {
+                None
+            } else {
+                CStr::from_ptr(message_cptr)
+                    .to_str()
+                    .ok()
+                    .map(ToOwned::to_owned)
+            }
+        };
 
-                let message_cstr = CStr::from_ptr(message_cptr);
+        Error::from_code_with_message(code, message)
+    }
 
-                Some(message_cstr.to_str().ok()?.to_owned())
-            })
-            .unwrap_or_else(|| format!("WSTP error code {} occurred.", code));
+    fn from_code_with_message(code: i32, message: Option<String>) -> Self {
+        let message =
+            message.unwrap_or_else(|| format!("WSTP error code {} occurred.", code));
 
         Error {
             code: Some(code),
             message,
+            kind: ErrorKind::from_code(code),
+            source: None,
+            context: Vec::new(),
         }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let Error { code, message } = self;
+        let Error {
+            code,
+            message,
+            context,
+            ..
+        } = self;
 
         if let Some(code) = code {
-            write!(f, "WSTP error (code {}): {}", code, message)
+            write!(f, "WSTP error (code {}): {}", code, message)?;
         } else {
-            write!(f, "WSTP error: {}", message)
+            write!(f, "WSTP error: {}", message)?;
         }
+
+        for context in context {
+            write!(f, ", {}", context)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        // Note: `source` is not compared, because `dyn std::error::Error` does not
+        //       implement `PartialEq`.
+        let Error {
+            code,
+            message,
+            kind,
+            source: _,
+            context,
+        } = self;
+
+        *code == other.code
+            && *message == other.message
+            && *kind == other.kind
+            && *context == other.context
     }
 }
 
@@ -77,4 +379,11 @@ impl Debug for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.source {
+            Some(ref source) => Some(&**source),
+            None => None,
+        }
+    }
+}
@@ -2,7 +2,10 @@ use std::ffi::{CStr, CString};
 use std::iter::FromIterator;
 use std::{convert::TryFrom, fmt, os::raw::c_char};
 
+use wolfram_expr::Symbol;
+
 use crate::{
+    progress::{self, Progress},
     sys::{
         self, WSGetArgCount, WSGetInteger16, WSGetInteger32, WSGetInteger64,
         WSGetInteger8, WSGetReal32, WSGetReal64, WSGetUTF16String, WSGetUTF32String,
@@ -10,7 +13,7 @@ use crate::{
         WSReleaseUTF32String, WSReleaseUTF32Symbol, WSReleaseUTF8String,
         WSReleaseUTF8Symbol,
     },
-    Error, Link, Utf16Str, Utf32Str, Utf8Str,
+    Error, Link, Ucs2Str, Utf16Str, Utf32Str, Utf8Str,
 };
 
 /// Basic unit of expression data read from a [`Link`].
@@ -53,12 +56,42 @@ pub enum TokenType {
     Function,
 }
 
+/// Owned version of [`Token`], which does not borrow from the [`Link`] it was read from.
+///
+/// This is the item type of the [`Tokens`] iterator.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum OwnedToken {
+    Integer(i64),
+    Real(f64),
+    Symbol(String),
+    String(String),
+
+    /// A function expression with `length` elements.
+    ///
+    /// See [`Token::Function`].
+    Function {
+        length: usize,
+    },
+}
+
+/// Iterator over the tokens of the next expression available to read from a [`Link`].
+///
+/// Constructed by [`Link::tokens()`].
+#[derive(Debug)]
+pub struct Tokens<'link> {
+    link: &'link mut Link,
+    /// Number of tokens that must still be read to complete the expression.
+    remaining: usize,
+}
+
 /// String borrowed from a [`Link`].
 ///
 /// `LinkStr` is returned from:
 ///
 /// * [`Link::get_string_ref()`]
-/// * [`Link::get_symbol_ref()`].
+/// * [`Link::get_symbol_ref()`]
+/// * [`Link::get_byte_str()`].
 ///
 /// When `LinkStr` is dropped, the string is deallocated by the `Link`.
 ///
@@ -94,6 +127,17 @@ pub unsafe trait LinkStrType: fmt::Debug {
 
     unsafe fn from_slice_unchecked<'s>(slice: &'s [Self::Element]) -> &'s Self;
 
+    /// Returns `true` if `slice` is validly encoded data for this string type.
+    ///
+    /// This is called on the data read from a link before
+    /// [`from_slice_unchecked()`][LinkStrType::from_slice_unchecked] is used to convert
+    /// it. The default implementation returns `true`, which is only correct for types
+    /// that accept any sequence of elements.
+    fn is_valid(slice: &[Self::Element]) -> bool {
+        let _ = slice;
+        true
+    }
+
     unsafe fn release(
         link: &Link,
         ptr: *const Self::Element,
@@ -109,18 +153,38 @@ pub unsafe trait LinkStrType: fmt::Debug {
 impl Link {
     /// Get the type of the next token available to read on this link.
     ///
+    /// The token is not consumed, so this can be used to decide which getter to use to
+    /// read the next expression.
+    ///
     /// See also [`Link::get_token()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, TokenType};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.put_f64(2.5).unwrap();
+    ///
+    /// let value: f64 = match link.get_type().unwrap() {
+    ///     TokenType::Integer => link.get_i64().unwrap() as f64,
+    ///     TokenType::Real => link.get_f64().unwrap(),
+    ///     other => panic!("unexpected token: {:?}", other),
+    /// };
+    ///
+    /// assert_eq!(value, 2.5);
+    /// ```
     pub fn get_type(&self) -> Result<TokenType, Error> {
         use wstp_sys::{WSTKFUNC, WSTKINT, WSTKREAL, WSTKSTR, WSTKSYM};
 
         let type_: i32 = self.get_raw_type()?;
 
-        let token_type = match u8::try_from(type_).unwrap() {
-            WSTKINT => TokenType::Integer,
-            WSTKREAL => TokenType::Real,
-            WSTKSTR => TokenType::String,
-            WSTKSYM => TokenType::Symbol,
-            WSTKFUNC => TokenType::Function,
+        let token_type = match u8::try_from(type_) {
+            Ok(WSTKINT) => TokenType::Integer,
+            Ok(WSTKREAL) => TokenType::Real,
+            Ok(WSTKSTR) => TokenType::String,
+            Ok(WSTKSYM) => TokenType::Symbol,
+            Ok(WSTKFUNC) => TokenType::Function,
             _ => return Err(Error::custom(format!("unknown WSLINK type: {}", type_))),
         };
 
@@ -166,6 +230,35 @@ impl Link {
         Ok(token)
     }
 
+    /// Returns an iterator over the tokens of the next expression on this link.
+    ///
+    /// The iterator returns `None` once every token of the expression has been read,
+    /// or after an error has been returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, OwnedToken};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.put(&(5, "second")).unwrap();
+    ///
+    /// let tokens: Vec<OwnedToken> = link.tokens().collect::<Result<_, _>>().unwrap();
+    ///
+    /// assert_eq!(tokens, vec![
+    ///     OwnedToken::Function { length: 2 },
+    ///     OwnedToken::Symbol("System`List".to_owned()),
+    ///     OwnedToken::Integer(5),
+    ///     OwnedToken::String("second".to_owned()),
+    /// ]);
+    /// ```
+    pub fn tokens(&mut self) -> Tokens {
+        Tokens {
+            link: self,
+            remaining: 1,
+        }
+    }
+
     /// Get the raw type of the next token available to read on this link.
     ///
     /// If the returned type is [`WSTKERR`][sys::WSTKERR], an error is returned.
@@ -213,14 +306,7 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let num_bytes = usize::try_from(num_bytes).unwrap();
-
-        Ok(LinkStr {
-            link: self,
-            ptr: c_string,
-            length: num_bytes,
-            is_symbol: false,
-        })
+        unsafe { LinkStr::new(self, c_string, num_bytes, false) }
     }
 
     /// Convenience wrapper around [`Link::get_string_ref()`].
@@ -248,14 +334,37 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let num_bytes = usize::try_from(num_bytes).unwrap();
+        unsafe { LinkStr::new(self, c_string, num_bytes, true) }
+    }
 
-        Ok(LinkStr {
-            link: self,
-            ptr: c_string,
-            length: num_bytes,
-            is_symbol: true,
-        })
+    /// Read a symbol from this link.
+    ///
+    /// Returns an error if the symbol name read from the link is not an absolute symbol
+    /// name, i.e. if it does not have a context.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::Symbol;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_symbol("System`Plus").unwrap();
+    ///
+    /// assert_eq!(link.get_symbol().unwrap(), Symbol::new("System`Plus"));
+    /// ```
+    pub fn get_symbol(&mut self) -> Result<Symbol, Error> {
+        let symbol_ref = self.get_symbol_ref()?;
+        let symbol_str: &str = symbol_ref.as_str();
+
+        match Symbol::try_new(symbol_str) {
+            Some(symbol) => Ok(symbol),
+            None => Err(Error::custom(format!(
+                "symbol name '{}' has no context",
+                symbol_str
+            ))),
+        }
     }
 
     //==================================
@@ -279,16 +388,7 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let num_bytes = usize::try_from(num_bytes).unwrap();
-
-        Ok(LinkStr {
-            link: self,
-
-            ptr: c_string,
-            length: num_bytes,
-
-            is_symbol: false,
-        })
+        unsafe { LinkStr::new(self, c_string, num_bytes, false) }
     }
 
     /// *WSTP C API Documentation:* [`WSGetUTF16String()`](https://reference.wolfram.com/language/ref/c/WSGetUTF16String.html)
@@ -308,16 +408,25 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let num_elems = usize::try_from(num_elems).unwrap();
+        unsafe { LinkStr::new(self, c_string, num_elems, false) }
+    }
 
-        Ok(LinkStr {
-            link: self,
+    /// *WSTP C API Documentation:* [`WSGetUCS2String()`](https://reference.wolfram.com/language/ref/c/WSGetUCS2String.html)
+    pub fn get_ucs2_str<'link>(
+        &'link mut self,
+    ) -> Result<LinkStr<'link, Ucs2Str>, Error> {
+        let mut c_string: *const u16 = std::ptr::null();
+        let mut num_elems: i32 = 0;
 
-            ptr: c_string,
-            length: num_elems,
+        if unsafe { sys::WSGetUCS2String(self.raw_link, &mut c_string, &mut num_elems) }
+            == 0
+        {
+            // NOTE: According to the documentation, we do NOT have to release
+            //      `string` if the function returns an error.
+            return Err(self.error_or_unknown());
+        }
 
-            is_symbol: false,
-        })
+        unsafe { LinkStr::new(self, c_string, num_elems, false) }
     }
 
     /// *WSTP C API Documentation:* [`WSGetUTF32String()`](https://reference.wolfram.com/language/ref/c/WSGetUTF32String.html)
@@ -334,15 +443,121 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let num_elems = usize::try_from(num_elems).unwrap();
+        unsafe { LinkStr::new(self, c_string, num_elems, false) }
+    }
 
-        Ok(LinkStr {
-            link: self,
+    /// Read a string from this link as a sequence of bytes, one per character.
+    ///
+    /// Characters whose code points do not fit in a single byte are replaced by
+    /// `missing`. This is useful for reading Latin-1 or binary data that has been sent
+    /// as a string, without requiring the data to be valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_byte_string(&[0xE9, 0x00, 0xFF]).unwrap();
+    ///
+    /// assert_eq!(link.get_byte_str(b'?').unwrap().get(), &[0xE9, 0x00, 0xFF]);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSGetByteString()`](https://reference.wolfram.com/language/ref/c/WSGetByteString.html)
+    pub fn get_byte_str<'link>(
+        &'link mut self,
+        missing: u8,
+    ) -> Result<LinkStr<'link, [u8]>, Error> {
+        let mut c_string: *const u8 = std::ptr::null();
+        let mut num_bytes: i32 = 0;
 
-            ptr: c_string,
-            length: num_elems,
+        if unsafe {
+            sys::WSGetByteString(
+                self.raw_link,
+                &mut c_string,
+                &mut num_bytes,
+                std::os::raw::c_long::from(missing),
+            )
+        } == 0
+        {
+            // NOTE: According to the documentation, we do NOT have to release
+            //      `string` if the function returns an error.
+            return Err(self.error_or_unknown());
+        }
 
-            is_symbol: false,
+        unsafe { LinkStr::new(self, c_string, num_bytes, false) }
+    }
+
+    /// Convenience wrapper around [`Link::get_byte_str()`].
+    pub fn get_byte_string(&mut self, missing: u8) -> Result<Vec<u8>, Error> {
+        Ok(self.get_byte_str(missing)?.get().to_vec())
+    }
+
+    /// Read a string from this link in pieces of at most `chunk_size` characters.
+    ///
+    /// This avoids holding the complete string in a single allocation, which can be
+    /// useful when reading very large strings.
+    ///
+    /// Dropping the returned iterator before it is exhausted discards the rest of the
+    /// string.
+    ///
+    /// Each chunk is reported to the [progress observer][Link::set_progress_observer].
+    /// If the observer cancels the transfer, the iterator returns an error and stops,
+    /// leaving the rest of the string on the link.
+    ///
+    /// Returns an error if the next expression on this link is not a string.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `chunk_size` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_str("hello world").unwrap();
+    ///
+    /// let chunks: Vec<String> = link
+    ///     .get_string_chunks(4)
+    ///     .unwrap()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(chunks, ["hell", "o wo", "rld"]);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSGetUTF32Characters()`](https://reference.wolfram.com/language/ref/c/WSGetUTF32Characters.html)
+    pub fn get_string_chunks<'link>(
+        &'link mut self,
+        chunk_size: usize,
+    ) -> Result<StringChunks<'link>, Error> {
+        assert!(
+            chunk_size > 0,
+            "get_string_chunks(): chunk_size must be non-zero"
+        );
+
+        let type_ = self.raw_get_next()?;
+
+        if type_ != i32::from(sys::WSTKSTR) {
+            return Err(Error::custom(format!(
+                "get_string_chunks(): expected string, got token type {}",
+                type_
+            )));
+        }
+
+        // Note: Using UTF-32 ensures that a chunk boundary never falls in the middle of
+        //       a multi-unit character.
+        let buffer_len = i32::try_from(chunk_size).unwrap_or(i32::MAX);
+
+        Ok(StringChunks {
+            link: self,
+            buffer: vec![0; buffer_len as usize],
+            done: false,
+            transferred: 0,
         })
     }
 
@@ -405,9 +620,117 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let len = usize::try_from(len).expect("c_int overflows usize");
+        abi_len_to_usize(len, "WSTestHead() argument count")
+    }
+
+    /// Read the head of a function expression whose head is the symbol `expected_head`,
+    /// and return the number of elements it has.
+    ///
+    /// If the incoming expression is not a function with head `expected_head`, the
+    /// returned error names both the expected head and the expression that was found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("System`Rule", 2).unwrap();
+    /// link.put_str("key").unwrap();
+    /// link.put_i64(5).unwrap();
+    ///
+    /// let error = link.expect_normal("System`List").unwrap_err();
+    ///
+    /// let message = error.to_string();
+    ///
+    /// assert!(message.contains("expected System`List[...] expression"));
+    /// assert!(message.contains("got System`Rule[...] expression"));
+    /// ```
+    pub fn expect_normal(&mut self, expected_head: &str) -> Result<usize, Error> {
+        self.expect_normal_with_head(&[expected_head])
+    }
+
+    /// Read the head of a function expression whose head is one of the symbols in
+    /// `heads`, and return the number of elements it has.
+    ///
+    /// See [`Link::expect_normal()`].
+    pub(crate) fn expect_normal_with_head(
+        &mut self,
+        heads: &[&str],
+    ) -> Result<usize, Error> {
+        let length = match self.get_token()? {
+            Token::Function { length } => length,
+            Token::Integer(_) => return Err(expected_normal(heads, "Integer")),
+            Token::Real(_) => return Err(expected_normal(heads, "Real")),
+            Token::String(_) => return Err(expected_normal(heads, "String")),
+            Token::Symbol(symbol) => {
+                let found = format!("symbol {}", symbol.as_str());
+                return Err(expected_normal(heads, &found));
+            },
+        };
+
+        if self.get_type()? != TokenType::Symbol {
+            return Err(expected_normal(
+                heads,
+                "function expression with a non-symbol head",
+            ));
+        }
+
+        let head = self.get_symbol_ref()?;
+
+        if !heads.contains(&head.as_str()) {
+            let found = format!("{}[...] expression", head.as_str());
+            return Err(expected_normal(heads, &found));
+        }
+
+        Ok(length)
+    }
+
+    /// Read the head of a function expression whose head is the symbol `expected_head`,
+    /// and which has `count` elements.
+    ///
+    /// See [`Link::expect_normal()`].
+    pub(crate) fn expect_normal_with_arg_count(
+        &mut self,
+        expected_head: &str,
+        count: usize,
+    ) -> Result<(), Error> {
+        let length = self.expect_normal(expected_head)?;
+
+        if length != count {
+            return Err(Error::custom(format!(
+                "expected {}[...] expression with {} argument(s), got {} argument(s)",
+                expected_head, count, length
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check that the incoming expression is a function with head `symbol`, and return
+    /// the number of elements it has.
+    ///
+    /// Returns an error if the incoming expression is not a function with head
+    /// `symbol`. This is equivalent to [`Link::expect_normal()`].
+    ///
+    /// See also [`Link::test_head()`].
+    ///
+    /// *WSTP C API Documentation:* [`WSCheckFunction()`](https://reference.wolfram.com/language/ref/c/WSCheckFunction.html)
+    pub fn check_function(&mut self, symbol: &str) -> Result<usize, Error> {
+        self.expect_normal(symbol)
+    }
 
-        Ok(len)
+    /// Check that the incoming expression is a function with head `symbol` and `count`
+    /// elements.
+    ///
+    /// *WSTP C API Documentation:* [`WSCheckFunctionWithArgCount()`](https://reference.wolfram.com/language/ref/c/WSCheckFunctionWithArgCount.html)
+    pub fn check_function_with_arg_count(
+        &mut self,
+        symbol: &str,
+        count: usize,
+    ) -> Result<(), Error> {
+        self.expect_normal_with_arg_count(symbol, count)
     }
 
     /// *WSTP C API Documentation:* [`WSGetArgCount()`](https://reference.wolfram.com/language/ref/c/WSGetArgCount.html)
@@ -418,13 +741,7 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let arg_count = usize::try_from(arg_count)
-            // This really shouldn't happen on any modern 32/64 bit OS. If this
-            // condition *is* reached, it's more likely going to be do to an ABI or
-            // numeric environment handling issue.
-            .expect("WSTKFUNC argument count could not be converted to usize");
-
-        Ok(arg_count)
+        abi_len_to_usize(arg_count, "WSTKFUNC argument count")
     }
 
     //==================================
@@ -485,6 +802,82 @@ impl Link {
         Ok(real)
     }
 
+    //==================================
+    // Complex and rational numbers
+    //==================================
+
+    /// Read a `Complex[re, im]` expression from this link, returning `(re, im)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_complex(1.5, -2.0).unwrap();
+    ///
+    /// assert_eq!(link.get_complex().unwrap(), (1.5, -2.0));
+    /// ```
+    pub fn get_complex(&mut self) -> Result<(f64, f64), Error> {
+        self.expect_normal_with_arg_count("System`Complex", 2)?;
+
+        let re = self.get_f64()?;
+        let im = self.get_f64()?;
+
+        Ok((re, im))
+    }
+
+    /// Read a `Rational[numerator, denominator]` expression from this link, returning
+    /// `(numerator, denominator)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_rational(3, 4).unwrap();
+    ///
+    /// assert_eq!(link.get_rational().unwrap(), (3, 4));
+    /// ```
+    pub fn get_rational(&mut self) -> Result<(i64, i64), Error> {
+        self.expect_normal_with_arg_count("System`Rational", 2)?;
+
+        let numerator = self.get_i64()?;
+        let denominator = self.get_i64()?;
+
+        Ok((numerator, denominator))
+    }
+
+    //==================================
+    // Numeric arrays
+    //==================================
+
+    /// Get a multidimensional array of any [`ArrayElement`] type.
+    ///
+    /// This is a generic alternative to the `get_*_array()` methods, e.g.
+    /// [`Link::get_i32_array()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Array, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_array::<i16>(&[1, 2, 3, 4, 5, 6], &[2, 3]).unwrap();
+    ///
+    /// let out: Array<i16> = link.get_array().unwrap();
+    ///
+    /// assert_eq!(out.data(), &[1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(out.dimensions(), &[2, 3]);
+    /// ```
+    pub fn get_array<T: ArrayElement>(&mut self) -> Result<Array<T>, Error> {
+        T::get_array(self)
+    }
+
     //==================================
     // Integer numeric arrays
     //==================================
@@ -508,22 +901,30 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSGetInteger64Array()`](https://reference.wolfram.com/language/ref/c/WSGetInteger64Array.html)
     pub fn get_i64_array(&mut self) -> Result<Array<i64>, Error> {
-        unsafe { self.get_array(sys::WSGetInteger64Array, sys::WSReleaseInteger64Array) }
+        unsafe {
+            self.get_raw_array(sys::WSGetInteger64Array, sys::WSReleaseInteger64Array)
+        }
     }
 
     /// *WSTP C API Documentation:* [`WSGetInteger32Array()`](https://reference.wolfram.com/language/ref/c/WSGetInteger32Array.html)
     pub fn get_i32_array(&mut self) -> Result<Array<i32>, Error> {
-        unsafe { self.get_array(sys::WSGetInteger32Array, sys::WSReleaseInteger32Array) }
+        unsafe {
+            self.get_raw_array(sys::WSGetInteger32Array, sys::WSReleaseInteger32Array)
+        }
     }
 
     /// *WSTP C API Documentation:* [`WSGetInteger16Array()`](https://reference.wolfram.com/language/ref/c/WSGetInteger16Array.html)
     pub fn get_i16_array(&mut self) -> Result<Array<i16>, Error> {
-        unsafe { self.get_array(sys::WSGetInteger16Array, sys::WSReleaseInteger16Array) }
+        unsafe {
+            self.get_raw_array(sys::WSGetInteger16Array, sys::WSReleaseInteger16Array)
+        }
     }
 
     /// *WSTP C API Documentation:* [`WSGetInteger8Array()`](https://reference.wolfram.com/language/ref/c/WSGetInteger8Array.html)
     pub fn get_u8_array(&mut self) -> Result<Array<u8>, Error> {
-        unsafe { self.get_array(sys::WSGetInteger8Array, sys::WSReleaseInteger8Array) }
+        unsafe {
+            self.get_raw_array(sys::WSGetInteger8Array, sys::WSReleaseInteger8Array)
+        }
     }
 
     //==================================
@@ -550,16 +951,33 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSGetReal64Array()`](https://reference.wolfram.com/language/ref/c/WSGetReal64Array.html)
     pub fn get_f64_array(&mut self) -> Result<Array<f64>, Error> {
-        unsafe { self.get_array(sys::WSGetReal64Array, sys::WSReleaseReal64Array) }
+        unsafe { self.get_raw_array(sys::WSGetReal64Array, sys::WSReleaseReal64Array) }
     }
 
+    /// Get a multidimensional array of [`f32`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_f32_array(&[0.5, 1.5, 2.5, 3.5], &[2, 2]).unwrap();
+    ///
+    /// let out = link.get_f32_array().unwrap();
+    ///
+    /// assert_eq!(out.data(), &[0.5, 1.5, 2.5, 3.5]);
+    /// assert_eq!(out.dimensions(), &[2, 2]);
+    /// ```
+    ///
     /// *WSTP C API Documentation:* [`WSGetReal32Array()`](https://reference.wolfram.com/language/ref/c/WSGetReal32Array.html)
     pub fn get_f32_array(&mut self) -> Result<Array<f32>, Error> {
-        unsafe { self.get_array(sys::WSGetReal32Array, sys::WSReleaseReal32Array) }
+        unsafe { self.get_raw_array(sys::WSGetReal32Array, sys::WSReleaseReal32Array) }
     }
 
     #[allow(non_snake_case)]
-    unsafe fn get_array<T: 'static>(
+    unsafe fn get_raw_array<T: 'static>(
         &mut self,
         WSGetTArray: unsafe extern "C" fn(
             sys::WSLINK,
@@ -597,33 +1015,114 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
-        let depth: usize =
-            usize::try_from(depth).expect("WSGet*Array depth overflows usize");
+        let release = move |link: &Link| {
+            WSReleaseTArray(link.raw_link, data_ptr, dims_ptr, heads_ptr, depth);
+        };
 
-        let dims: &[i32] = { std::slice::from_raw_parts(dims_ptr, depth) };
-        let dims: Vec<usize> = Vec::from_iter(dims.iter().map(|&val| {
-            usize::try_from(val)
-                .expect("WSGetInteger64Array dimension size overflows usize")
-        }));
+        let checked_dims =
+            abi_len_to_usize(depth, "WSGet*Array() depth").and_then(|len| {
+                let dims: &[i32] = std::slice::from_raw_parts(dims_ptr, len);
+
+                dims.iter()
+                    .map(|&dim| abi_len_to_usize(dim, "WSGet*Array() dimension"))
+                    .collect::<Result<Vec<usize>, Error>>()
+            });
+
+        let dims: Vec<usize> = match checked_dims {
+            Ok(dims) => dims,
+            Err(err) => {
+                release(self);
+                return Err(err);
+            },
+        };
 
         Ok(Array {
             link: self,
             data_ptr,
-            release_callback: Box::new(move |link: &Link| {
-                WSReleaseTArray(
-                    link.raw_link,
-                    data_ptr,
-                    dims_ptr,
-                    heads_ptr,
-                    depth as i32,
-                );
-            }),
+            release_callback: Box::new(release),
             dimensions: dims,
         })
     }
 }
 
+impl<'link> From<Token<'link>> for OwnedToken {
+    fn from(token: Token<'link>) -> Self {
+        match token {
+            Token::Integer(value) => OwnedToken::Integer(value),
+            Token::Real(value) => OwnedToken::Real(value),
+            Token::Symbol(symbol) => OwnedToken::Symbol(symbol.as_str().to_owned()),
+            Token::String(string) => OwnedToken::String(string.as_str().to_owned()),
+            Token::Function { length } => OwnedToken::Function { length },
+        }
+    }
+}
+
+impl<'link> Iterator for Tokens<'link> {
+    type Item = Result<OwnedToken, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        let token = match self.link.get_token() {
+            Ok(token) => OwnedToken::from(token),
+            Err(error) => {
+                self.remaining = 0;
+                return Some(Err(error));
+            },
+        };
+
+        if let OwnedToken::Function { length } = token {
+            // The head of the function, followed by its elements.
+            self.remaining += length + 1;
+        }
+
+        Some(Ok(token))
+    }
+}
+
+impl<'link> std::iter::FusedIterator for Tokens<'link> {}
+
 impl<'link, T: LinkStrType + ?Sized> LinkStr<'link, T> {
+    /// Construct a [`LinkStr`] from the string data returned by a successful
+    /// `WSGet*String()` or `WSGet*Symbol()` call, validating the length and encoding of
+    /// the data.
+    ///
+    /// The string data is released if it is not valid.
+    pub(crate) unsafe fn new(
+        link: &'link Link,
+        ptr: *const T::Element,
+        len: i32,
+        is_symbol: bool,
+    ) -> Result<Self, Error> {
+        let length = match abi_len_to_usize(len, "string length") {
+            Ok(length) => length,
+            Err(err) => {
+                T::release(link, ptr, 0, is_symbol);
+                return Err(err);
+            },
+        };
+
+        let link_str = LinkStr {
+            link,
+            ptr,
+            length,
+            is_symbol,
+        };
+
+        if !T::is_valid(std::slice::from_raw_parts(ptr, length)) {
+            // Note: `link_str` is dropped here, which releases the string data.
+            return Err(Error::custom(
+                "string data read from link is not validly encoded".to_owned(),
+            ));
+        }
+
+        Ok(link_str)
+    }
+
     /// Get the string contained by this `LinkStr`.
     pub fn get<'this>(&'this self) -> &'this T {
         let LinkStr {
@@ -642,14 +1141,8 @@ impl<'link, T: LinkStrType + ?Sized> LinkStr<'link, T> {
             let slice: &'this [T::Element] = std::slice::from_raw_parts(ptr, length);
 
             // SAFETY:
-            //     This depends on the assumption that WSTP always returns correctly
-            //     encoded UTF-8/UTF-16/UTF-32/UCS-2. We do not do any validation of
-            //     the encoding here.
-            //
-            // TODO: Do we trust WSTP enough to always produce valid UTF-8 to
-            //       use `str::from_utf8_unchecked()` here? If a client writes malformed
-            //       data with WSPutUTF8String, does WSTP validate it and return an error,
-            //       or would it be passed through to unsuspecting us?
+            //     The encoding of the string data was validated when this `LinkStr`
+            //     was constructed by `LinkStr::new()`.
             T::from_slice_unchecked(slice)
         }
     }
@@ -693,6 +1186,10 @@ unsafe impl LinkStrType for str {
         str
     }
 
+    fn is_valid(slice: &[u8]) -> bool {
+        std::str::from_utf8(slice).is_ok()
+    }
+
     unsafe fn release(
         link: &Link,
         ptr: *const Self::Element,
@@ -709,6 +1206,29 @@ unsafe impl LinkStrType for str {
     }
 }
 
+unsafe impl LinkStrType for [u8] {
+    type Element = u8;
+
+    unsafe fn from_slice_unchecked<'s>(slice: &'s [Self::Element]) -> &'s Self {
+        slice
+    }
+
+    unsafe fn release(
+        link: &Link,
+        ptr: *const Self::Element,
+        len: usize,
+        is_symbol: bool,
+    ) {
+        let len = i32::try_from(len).expect("LinkStr usize length overflows i32");
+
+        // Deallocate the string data.
+        match is_symbol {
+            true => sys::WSReleaseByteSymbol(link.raw_link, ptr, len),
+            false => sys::WSReleaseByteString(link.raw_link, ptr, len),
+        }
+    }
+}
+
 unsafe impl LinkStrType for Utf8Str {
     type Element = u8;
 
@@ -718,6 +1238,10 @@ unsafe impl LinkStrType for Utf8Str {
         str
     }
 
+    fn is_valid(slice: &[u8]) -> bool {
+        std::str::from_utf8(slice).is_ok()
+    }
+
     unsafe fn release(
         link: &Link,
         ptr: *const Self::Element,
@@ -743,6 +1267,10 @@ unsafe impl LinkStrType for Utf16Str {
         str
     }
 
+    fn is_valid(slice: &[u16]) -> bool {
+        Utf16Str::from_utf16(slice).is_ok()
+    }
+
     unsafe fn release(
         link: &Link,
         ptr: *const Self::Element,
@@ -759,6 +1287,29 @@ unsafe impl LinkStrType for Utf16Str {
     }
 }
 
+unsafe impl LinkStrType for Ucs2Str {
+    type Element = u16;
+
+    unsafe fn from_slice_unchecked<'s>(slice: &'s [Self::Element]) -> &'s Self {
+        Ucs2Str::from_ucs2(slice)
+    }
+
+    unsafe fn release(
+        link: &Link,
+        ptr: *const Self::Element,
+        len: usize,
+        is_symbol: bool,
+    ) {
+        let len = i32::try_from(len).expect("LinkStr usize length overflows i32");
+
+        // Deallocate the string data.
+        match is_symbol {
+            true => sys::WSReleaseUCS2Symbol(link.raw_link, ptr, len),
+            false => sys::WSReleaseUCS2String(link.raw_link, ptr, len),
+        }
+    }
+}
+
 unsafe impl LinkStrType for Utf32Str {
     type Element = u32;
 
@@ -768,6 +1319,12 @@ unsafe impl LinkStrType for Utf32Str {
         str
     }
 
+    fn is_valid(slice: &[u32]) -> bool {
+        slice
+            .iter()
+            .all(|&code_point| char::from_u32(code_point).is_some())
+    }
+
     unsafe fn release(
         link: &Link,
         ptr: *const Self::Element,
@@ -785,6 +1342,27 @@ unsafe impl LinkStrType for Utf32Str {
 }
 
 
+/// Element type of a numeric [`Array`] that can be transferred over a [`Link`].
+///
+/// This trait is implemented for [`i64`], [`i32`], [`i16`], [`u8`], [`f64`], and
+/// [`f32`], and is used by [`Link::get_array()`] and [`Link::put_array()`].
+///
+/// WSTP does not support arrays of signed 8-bit integers, so this trait is not
+/// implemented for [`i8`].
+///
+/// This trait is sealed, and cannot be implemented outside of this crate.
+pub trait ArrayElement: Copy + 'static + private::Sealed {
+    #[doc(hidden)]
+    fn get_array(link: &mut Link) -> Result<Array<Self>, Error>;
+
+    #[doc(hidden)]
+    fn put_array(
+        link: &mut Link,
+        data: &[Self],
+        dimensions: &[usize],
+    ) -> Result<(), Error>;
+}
+
 /// Reference to a multidimensional rectangular array borrowed from a [`Link`].
 ///
 /// [`Array`] is returned from:
@@ -795,6 +1373,12 @@ unsafe impl LinkStrType for Utf32Str {
 /// * [`Link::get_u8_array()`]
 /// * [`Link::get_f64_array()`]
 /// * [`Link::get_f32_array()`]
+/// * [`Link::get_array()`]
+///
+/// The elements of an [`Array`] are not copied out of the buffer allocated by WSTP.
+/// The buffer is released using the corresponding `WSRelease*Array()` function when
+/// the [`Array`] is dropped. Use [`Array::to_vec()`] to get an owned copy of the
+/// elements that can outlive the borrow of the [`Link`].
 pub struct Array<'link, T> {
     link: &'link Link,
 
@@ -831,9 +1415,111 @@ impl<'link, T> Array<'link, T> {
     }
 
     /// Length of the first dimension of this array.
+    ///
+    /// See also [`Array::len()`].
     pub fn length(&self) -> usize {
         self.dimensions[0]
     }
+
+    /// Get the total number of elements in this array.
+    ///
+    /// This is the product of the [`dimensions()`][Array::dimensions] of this array.
+    pub fn len(&self) -> usize {
+        self.dimensions.iter().product()
+    }
+
+    /// Returns `true` if this array contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access the elements stored in this [`Array`] as a flat slice, in row-major
+    /// order.
+    ///
+    /// This is equivalent to [`Array::data()`].
+    pub fn as_slice(&self) -> &[T] {
+        self.data()
+    }
+
+    /// Copy the elements of this array into a [`Vec`], in row-major order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64_array(&[1, 2, 3, 4], &[2, 2]).unwrap();
+    ///
+    /// let (data, dimensions) = {
+    ///     let array = link.get_i64_array().unwrap();
+    ///     (array.to_vec(), array.dimensions().to_vec())
+    /// };
+    ///
+    /// // `link` is no longer borrowed.
+    /// link.put_i64(5).unwrap();
+    ///
+    /// assert_eq!(data, vec![1, 2, 3, 4]);
+    /// assert_eq!(dimensions, vec![2, 2]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.data().to_vec()
+    }
+
+    /// Iterate over the elements of this array, in row-major order.
+    pub fn iter(&self) -> std::slice::Iter<T> {
+        self.data().iter()
+    }
+
+    /// Change the dimensions of this array, without changing its elements.
+    ///
+    /// Returns an error, and leaves this array unchanged, if the product of
+    /// `dimensions` is not equal to [`Array::len()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64_array(&[1, 2, 3, 4, 5, 6], &[6]).unwrap();
+    ///
+    /// let mut array = link.get_i64_array().unwrap();
+    ///
+    /// array.reshape(&[2, 3]).unwrap();
+    /// assert_eq!(array.dimensions(), &[2, 3]);
+    ///
+    /// assert!(array.reshape(&[4, 2]).is_err());
+    /// assert_eq!(array.dimensions(), &[2, 3]);
+    /// ```
+    pub fn reshape(&mut self, dimensions: &[usize]) -> Result<(), Error> {
+        let new_len: usize = dimensions.iter().product();
+
+        if new_len != self.len() {
+            return Err(Error::custom(format!(
+                "cannot reshape array with dimensions {:?} to dimensions {:?}",
+                self.dimensions, dimensions
+            )));
+        }
+
+        self.dimensions = dimensions.to_vec();
+
+        Ok(())
+    }
+}
+
+impl<'a, 'link, T> IntoIterator for &'a Array<'link, T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl<'link, T> Drop for Array<'link, T> {
@@ -849,6 +1535,165 @@ impl<'link, T> Drop for Array<'link, T> {
     }
 }
 
+//======================================
+// ArrayElement impls
+//======================================
+
+macro_rules! impl_array_element {
+    ($($type:ty => $get:ident, $put:ident);* $(;)?) => {
+        $(
+            impl private::Sealed for $type {}
+
+            impl ArrayElement for $type {
+                fn get_array(link: &mut Link) -> Result<Array<Self>, Error> {
+                    link.$get()
+                }
+
+                fn put_array(
+                    link: &mut Link,
+                    data: &[Self],
+                    dimensions: &[usize],
+                ) -> Result<(), Error> {
+                    link.$put(data, dimensions)
+                }
+            }
+        )*
+    };
+}
+
+impl_array_element! {
+    i64 => get_i64_array, put_i64_array;
+    i32 => get_i32_array, put_i32_array;
+    i16 => get_i16_array, put_i16_array;
+    u8 => get_u8_array, put_u8_array;
+    f64 => get_f64_array, put_f64_array;
+    f32 => get_f32_array, put_f32_array;
+}
+
+mod private {
+    /// Supertrait of [`ArrayElement`][super::ArrayElement], which prevents it from being
+    /// implemented outside of this crate.
+    pub trait Sealed {}
+}
+
+//======================================
+// StringChunks
+//======================================
+
+/// Iterator over the pieces of a string read from a [`Link`].
+///
+/// Returned by [`Link::get_string_chunks()`].
+///
+/// If this iterator is dropped before the complete string has been read, the remaining
+/// characters are read and discarded, so that the link is left positioned after the
+/// string.
+pub struct StringChunks<'link> {
+    link: &'link mut Link,
+    buffer: Vec<u32>,
+    done: bool,
+    /// Number of characters read so far, reported to the progress observer.
+    transferred: u64,
+}
+
+impl<'link> Iterator for StringChunks<'link> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Result<String, Error>> {
+        if self.done {
+            return None;
+        }
+
+        let mut chars_left: i32 = 0;
+        let mut got: i32 = 0;
+
+        let result = unsafe {
+            sys::WSGetUTF32Characters(
+                self.link.raw_link,
+                &mut chars_left,
+                self.buffer.as_mut_ptr(),
+                self.buffer.len() as i32,
+                &mut got,
+            )
+        };
+
+        if result == 0 {
+            self.done = true;
+            return Some(Err(self.link.error_or_unknown()));
+        }
+
+        if chars_left == 0 {
+            self.done = true;
+        }
+
+        let got = match abi_len_to_usize(got, "WSGetUTF32Characters() length") {
+            Ok(got) => got,
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err));
+            },
+        };
+
+        if got == 0 && self.done {
+            return None;
+        }
+
+        self.transferred += got as u64;
+
+        let progress = Progress {
+            transferred: self.transferred,
+            total: Some(self.transferred + chars_left as u64),
+        };
+
+        if let Err(err) = progress::report(self.link, progress) {
+            self.done = true;
+            return Some(Err(err));
+        }
+
+        let chunk: Result<String, Error> = self.buffer[..got]
+            .iter()
+            .map(|&code_point| {
+                char::from_u32(code_point).ok_or_else(|| {
+                    Error::custom(format!(
+                        "get_string_chunks(): invalid UTF-32 code point: {:#x}",
+                        code_point
+                    ))
+                })
+            })
+            .collect();
+
+        Some(chunk)
+    }
+}
+
+impl<'link> Drop for StringChunks<'link> {
+    fn drop(&mut self) {
+        // Note: Stops once the end of the string is reached, or an error occurs.
+        while !self.done {
+            let _ = self.next();
+        }
+    }
+}
+
+fn expected_normal(heads: &[&str], found: &str) -> Error {
+    let expected: Vec<String> =
+        heads.iter().map(|head| format!("{}[...]", head)).collect();
+
+    Error::custom(format!(
+        "expected {} expression, got {}",
+        expected.join(" or "),
+        found
+    ))
+}
+
+/// Convert a length or count returned by a WSTP function to a `usize`.
+///
+/// WSTP should never return a negative length, but this is checked instead of assumed,
+/// because the data being described was written by the peer.
+fn abi_len_to_usize(len: i32, what: &str) -> Result<usize, Error> {
+    usize::try_from(len)
+        .map_err(|_| Error::custom(format!("invalid {} returned by WSTP: {}", what, len)))
+}
+
 //======================================
 // Formatting impls
 //======================================
@@ -0,0 +1,260 @@
+//! Memoization of evaluation results.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use wolfram_expr::Expr;
+
+use super::EvaluationBackend;
+
+/// Options controlling which results are stored by a [`CachingBackend`], and for how
+/// long.
+#[derive(Clone)]
+pub struct CacheOptions {
+    /// Maximum number of results stored at once. When the cache is full, the least
+    /// recently used result is evicted to make room for a new one.
+    pub max_entries: usize,
+    /// How long a stored result remains valid, or `None` if stored results never
+    /// expire.
+    pub time_to_live: Option<Duration>,
+    /// Returns `true` if the result of evaluating the specified expression can be
+    /// cached.
+    ///
+    /// Only expressions whose evaluation is pure, i.e. that always evaluate to the same
+    /// result and have no side effects, should be cached. The default predicate
+    /// returns `false` for every expression, so caching must be enabled explicitly.
+    pub is_cacheable: fn(&Expr) -> bool,
+}
+
+/// Evaluation backend wrapper that stores the results of evaluations, and returns the
+/// stored result when the same expression is evaluated again.
+///
+/// Only successful evaluations are cached. Results are keyed by the input expression,
+/// so two requests share a result only if their input expressions are equal.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::Expr;
+/// use wstp::kernel::{CacheOptions, CachingBackend, EvaluationBackend};
+///
+/// /// Fake backend that counts the evaluations it performs.
+/// struct CountingBackend(u32);
+///
+/// impl EvaluationBackend for CountingBackend {
+///     type Error = ();
+///
+///     fn evaluate(&mut self, expr: &Expr) -> Result<Expr, ()> {
+///         self.0 += 1;
+///         Ok(expr.clone())
+///     }
+///
+///     fn interrupt(&mut self) -> Result<(), ()> {
+///         Ok(())
+///     }
+///
+///     fn shutdown(&mut self) -> Result<(), ()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let options = CacheOptions {
+///     is_cacheable: |_| true,
+///     ..CacheOptions::default()
+/// };
+///
+/// let mut backend = CachingBackend::new(CountingBackend(0), options);
+///
+/// backend.evaluate(&Expr::from(1)).unwrap();
+/// backend.evaluate(&Expr::from(1)).unwrap();
+/// backend.evaluate(&Expr::from(2)).unwrap();
+///
+/// assert_eq!(backend.backend().0, 2);
+/// ```
+#[derive(Debug)]
+pub struct CachingBackend<B> {
+    backend: B,
+    options: CacheOptions,
+    entries: HashMap<Expr, CacheEntry>,
+    /// Stored expressions, ordered from least to most recently used.
+    recently_used: BTreeMap<u64, Expr>,
+    /// Incremented each time an entry is used.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    result: Expr,
+    /// When the evaluation that produced `result` finished.
+    inserted: Instant,
+    /// Key of this entry in `recently_used`.
+    last_used: u64,
+}
+
+impl Default for CacheOptions {
+    /// Store up to 1024 results that never expire. No expressions are cached.
+    fn default() -> Self {
+        CacheOptions {
+            max_entries: 1024,
+            time_to_live: None,
+            is_cacheable: |_| false,
+        }
+    }
+}
+
+impl fmt::Debug for CacheOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let CacheOptions {
+            max_entries,
+            time_to_live,
+            is_cacheable: _,
+        } = self;
+
+        f.debug_struct("CacheOptions")
+            .field("max_entries", max_entries)
+            .field("time_to_live", time_to_live)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: EvaluationBackend> CachingBackend<B> {
+    /// Construct a new [`CachingBackend`] that caches the results of evaluations
+    /// performed by `backend`.
+    pub fn new(backend: B, options: CacheOptions) -> Self {
+        CachingBackend {
+            backend,
+            options,
+            entries: HashMap::new(),
+            recently_used: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Get the underlying backend.
+    pub fn backend(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Convert this value into the underlying backend, discarding the cached results.
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    /// Number of results currently stored, including any that have expired but not yet
+    /// been evicted.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no results are currently stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Discard all stored results.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recently_used.clear();
+    }
+
+    /// Evaluate `expr`, returning a stored result if one is available.
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Expr, B::Error> {
+        if !(self.options.is_cacheable)(expr) || self.options.max_entries == 0 {
+            return self.backend.evaluate(expr);
+        }
+
+        let now = Instant::now();
+
+        if let Some(entry) = self.entries.get(expr) {
+            if !self.is_expired(entry, now) {
+                let result = entry.result.clone();
+                self.touch(expr);
+                return Ok(result);
+            }
+
+            self.remove(expr);
+        }
+
+        let result = self.backend.evaluate(expr)?;
+
+        self.make_room();
+
+        let last_used = self.tick();
+
+        self.recently_used.insert(last_used, expr.clone());
+        self.entries.insert(
+            expr.clone(),
+            CacheEntry {
+                result: result.clone(),
+                inserted: Instant::now(),
+                last_used,
+            },
+        );
+
+        Ok(result)
+    }
+
+    fn is_expired(&self, entry: &CacheEntry, now: Instant) -> bool {
+        match self.options.time_to_live {
+            Some(ttl) => now.duration_since(entry.inserted) >= ttl,
+            None => false,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Mark the entry for `expr` as the most recently used.
+    fn touch(&mut self, expr: &Expr) {
+        let last_used = self.tick();
+
+        if let Some(entry) = self.entries.get_mut(expr) {
+            let expr = self
+                .recently_used
+                .remove(&entry.last_used)
+                .expect("cache entry is missing from recently used order");
+
+            entry.last_used = last_used;
+            self.recently_used.insert(last_used, expr);
+        }
+    }
+
+    fn remove(&mut self, expr: &Expr) {
+        if let Some(entry) = self.entries.remove(expr) {
+            self.recently_used.remove(&entry.last_used);
+        }
+    }
+
+    /// Evict the least recently used entries until there is room to insert a new one.
+    fn make_room(&mut self) {
+        while self.entries.len() >= self.options.max_entries {
+            let least_recent = match self.recently_used.keys().next() {
+                Some(&last_used) => last_used,
+                None => break,
+            };
+
+            if let Some(expr) = self.recently_used.remove(&least_recent) {
+                self.entries.remove(&expr);
+            }
+        }
+    }
+}
+
+impl<B: EvaluationBackend> EvaluationBackend for CachingBackend<B> {
+    type Error = B::Error;
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<Expr, B::Error> {
+        CachingBackend::evaluate(self, expr)
+    }
+
+    fn interrupt(&mut self) -> Result<(), B::Error> {
+        self.backend.interrupt()
+    }
+
+    fn shutdown(&mut self) -> Result<(), B::Error> {
+        self.backend.shutdown()
+    }
+}
@@ -0,0 +1,167 @@
+//! Recognition of failure results returned by the Wolfram Language.
+
+use std::fmt::{self, Display};
+
+use wolfram_expr::{Expr, ExprKind};
+
+use crate::{Error as WstpError, Link};
+
+/// Failure result returned by a Wolfram Language evaluation.
+///
+/// Use [`WolframFailure::from_expr()`] to recognize a failure result, or
+/// [`WolframKernelProcess::evaluate_checked()`][super::WolframKernelProcess::evaluate_checked]
+/// to have failure results returned as errors.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum WolframFailure {
+    /// The symbol [`$Failed`](https://reference.wolfram.com/language/ref/$Failed.html).
+    Failed,
+    /// The symbol [`$Aborted`](https://reference.wolfram.com/language/ref/$Aborted.html).
+    Aborted,
+    /// A [`Failure[tag, assoc]`](https://reference.wolfram.com/language/ref/Failure.html)
+    /// expression.
+    Failure {
+        /// The failure tag, if it is a string or symbol.
+        tag: Option<String>,
+        /// The `"MessageTemplate"` of the failure, if it is a string.
+        message_template: Option<String>,
+        /// The complete `Failure[..]` expression.
+        expr: Expr,
+    },
+}
+
+impl WolframFailure {
+    /// Returns the failure represented by `expr`, or `None` if `expr` is not a failure
+    /// result.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::{Expr, Symbol};
+    /// use wstp::kernel::WolframFailure;
+    ///
+    /// let failed = Expr::symbol(Symbol::new("System`$Failed"));
+    ///
+    /// assert_eq!(WolframFailure::from_expr(&failed), Some(WolframFailure::Failed));
+    /// assert_eq!(WolframFailure::from_expr(&Expr::from(5)), None);
+    /// ```
+    pub fn from_expr(expr: &Expr) -> Option<WolframFailure> {
+        match expr.kind() {
+            ExprKind::Symbol(symbol) => match symbol.as_str() {
+                "System`$Failed" => Some(WolframFailure::Failed),
+                "System`$Aborted" => Some(WolframFailure::Aborted),
+                _ => None,
+            },
+            ExprKind::Normal(normal) => {
+                if !is_symbol(normal.head(), "System`Failure") {
+                    return None;
+                }
+
+                let tag = normal.elements().first().and_then(string_or_symbol_name);
+
+                let message_template = normal
+                    .elements()
+                    .get(1)
+                    .and_then(|assoc| association_lookup(assoc, "MessageTemplate"))
+                    .and_then(|template| match template.kind() {
+                        ExprKind::String(string) => Some(string.clone()),
+                        _ => None,
+                    });
+
+                Some(WolframFailure::Failure {
+                    tag,
+                    message_template,
+                    expr: expr.clone(),
+                })
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Display for WolframFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WolframFailure::Failed => write!(f, "evaluation returned $Failed"),
+            WolframFailure::Aborted => write!(f, "evaluation returned $Aborted"),
+            WolframFailure::Failure {
+                tag,
+                message_template,
+                expr: _,
+            } => {
+                write!(f, "evaluation returned Failure")?;
+
+                if let Some(tag) = tag {
+                    write!(f, "[{}]", tag)?;
+                }
+
+                if let Some(message) = message_template {
+                    write!(f, ": {}", message)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for WolframFailure {}
+
+impl Link {
+    /// Read an expression off of this link, returning `Ok(Err(failure))` if the
+    /// expression is a failure result recognized by [`WolframFailure::from_expr()`].
+    pub fn get_expr_or_failure(
+        &mut self,
+    ) -> Result<Result<Expr, WolframFailure>, WstpError> {
+        let expr = self.get_expr()?;
+
+        match WolframFailure::from_expr(&expr) {
+            Some(failure) => Ok(Err(failure)),
+            None => Ok(Ok(expr)),
+        }
+    }
+}
+
+//======================================
+// Utilities
+//======================================
+
+fn is_symbol(expr: &Expr, name: &str) -> bool {
+    match expr.kind() {
+        ExprKind::Symbol(symbol) => symbol.as_str() == name,
+        _ => false,
+    }
+}
+
+fn string_or_symbol_name(expr: &Expr) -> Option<String> {
+    match expr.kind() {
+        ExprKind::String(string) => Some(string.clone()),
+        ExprKind::Symbol(symbol) => Some(symbol.as_str().to_owned()),
+        _ => None,
+    }
+}
+
+/// Look up the value of the string `key` in an `Association[key -> value, ...]`
+/// expression.
+fn association_lookup<'e>(assoc: &'e Expr, key: &str) -> Option<&'e Expr> {
+    let assoc = match assoc.kind() {
+        ExprKind::Normal(normal) if is_symbol(normal.head(), "System`Association") => {
+            normal
+        },
+        _ => return None,
+    };
+
+    assoc.elements().iter().find_map(|rule| match rule.kind() {
+        ExprKind::Normal(rule)
+            if (is_symbol(rule.head(), "System`Rule")
+                || is_symbol(rule.head(), "System`RuleDelayed"))
+                && rule.elements().len() == 2 =>
+        {
+            match rule.elements()[0].kind() {
+                ExprKind::String(string) if string == key => Some(&rule.elements()[1]),
+                _ => None,
+            }
+        },
+        _ => None,
+    })
+}
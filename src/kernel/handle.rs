@@ -0,0 +1,161 @@
+//! [`InterruptHandle`], used to interrupt or abort an evaluation from another thread.
+
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{sys::WSLINK, ForceSend, Link, UrgentMessage};
+
+use super::Error;
+
+/// Link used by the [`InterruptHandle`]s of a kernel, or `None` if the link may no
+/// longer be used by them.
+type SharedRawLink = Arc<Mutex<ForceSend<Option<WSLINK>>>>;
+
+/// Handle used to interrupt or abort the evaluation performed by a Wolfram Kernel, from
+/// any thread.
+///
+/// [`KernelSession::evaluate()`][super::KernelSession::evaluate] and
+/// [`WolframKernelProcess::evaluate()`][super::WolframKernelProcess::evaluate] borrow
+/// the kernel mutably until the evaluation has finished, so an evaluation in progress
+/// can only be aborted using a handle that was obtained before it started.
+///
+/// Returned by [`KernelSession::interrupt_handle()`][super::KernelSession::interrupt_handle]
+/// and
+/// [`WolframKernelProcess::interrupt_handle()`][super::WolframKernelProcess::interrupt_handle].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::{path::PathBuf, time::Duration};
+///
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::kernel::WolframKernelProcess;
+///
+/// let exe = PathBuf::from(
+///     "/Applications/Mathematica.app/Contents/MacOS/WolframKernel"
+/// );
+///
+/// let mut kernel = WolframKernelProcess::launch(&exe).unwrap();
+///
+/// let handle = kernel.interrupt_handle();
+///
+/// std::thread::spawn(move || {
+///     std::thread::sleep(Duration::from_secs(1));
+///     handle.abort().unwrap();
+/// });
+///
+/// // Pause[10]
+/// let err = kernel
+///     .evaluate(&Expr::normal(Symbol::new("System`Pause"), vec![Expr::from(10)]))
+///     .unwrap_err();
+///
+/// assert!(err.is_aborted());
+/// ```
+#[derive(Clone)]
+pub struct InterruptHandle {
+    link: SharedRawLink,
+}
+
+/// Owner of the link used by [`InterruptHandle`]s, stored by the kernel types.
+///
+/// Handles may only send messages while the kernel type owns its link. The link is
+/// revoked before it is lent out as a `&mut Link` (which could be used to close or
+/// replace it), and when the kernel type is dropped.
+pub(super) struct HandleSource {
+    link: SharedRawLink,
+}
+
+impl InterruptHandle {
+    /// Send an interrupt message to the kernel.
+    ///
+    /// The kernel responds to an interrupt with an interrupt menu, which
+    /// [`WolframKernelProcess::evaluate()`][super::WolframKernelProcess::evaluate] and
+    /// [`KernelSession::evaluate()`][super::KernelSession::evaluate] answer by aborting
+    /// the evaluation.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn interrupt(&self) -> Result<(), Error> {
+        self.put_message(UrgentMessage::Interrupt)
+    }
+
+    /// Send an abort message to the kernel, aborting the evaluation currently in
+    /// progress.
+    ///
+    /// The pending call to `evaluate()` returns an error for which
+    /// [`Error::is_aborted()`] returns `true`.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn abort(&self) -> Result<(), Error> {
+        self.put_message(UrgentMessage::Abort)
+    }
+
+    fn put_message(&self, message: UrgentMessage) -> Result<(), Error> {
+        // Note: The lock is held while the message is put, so the kernel type cannot
+        //       revoke and close the link until the call has finished.
+        let guard = lock(&self.link);
+
+        let raw_link = guard.0.ok_or_else(|| {
+            Error::custom(
+                "kernel link is no longer available to interrupt handles".to_owned(),
+            )
+        })?;
+
+        // WSPutMessage() may be called from a thread other than the one reading from
+        // the link.
+        let mut link = ManuallyDrop::new(unsafe { Link::unchecked_new(raw_link) });
+
+        Ok(link.put_message(message)?)
+    }
+}
+
+impl HandleSource {
+    /// Construct a source whose handles may send messages on `link`.
+    pub(super) fn new(link: &Link) -> Self {
+        HandleSource {
+            link: Arc::new(Mutex::new(ForceSend(Some(link.raw_link)))),
+        }
+    }
+
+    pub(super) fn handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            link: Arc::clone(&self.link),
+        }
+    }
+
+    /// Allow handles to send messages on `link`, which is owned by the kernel type.
+    pub(super) fn publish(&self, link: &Link) {
+        lock(&self.link).0 = Some(link.raw_link);
+    }
+
+    /// Prevent handles from sending messages until the link is published again.
+    pub(super) fn revoke(&self) {
+        lock(&self.link).0 = None;
+    }
+}
+
+impl Drop for HandleSource {
+    fn drop(&mut self) {
+        self.revoke();
+    }
+}
+
+impl fmt::Debug for InterruptHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InterruptHandle")
+            .field("link", &lock(&self.link).0)
+            .finish()
+    }
+}
+
+impl fmt::Debug for HandleSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HandleSource")
+            .field("link", &lock(&self.link).0)
+            .finish()
+    }
+}
+
+fn lock(link: &SharedRawLink) -> MutexGuard<ForceSend<Option<WSLINK>>> {
+    link.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
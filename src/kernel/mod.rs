@@ -55,12 +55,40 @@
 //! #### Link packet methods
 //!
 //! * [`Link::put_eval_packet()`]
+//!
+//! # Evaluation backends
+//!
+//! Application code that only needs to evaluate expressions can be written against the
+//! [`EvaluationBackend`] trait instead of a concrete kernel type. This makes it possible
+//! to test that code using a fake backend that does not require a running Wolfram
+//! Kernel.
+//!
+//! [`WolframKernelProcess`] and [`KernelSession`] both implement [`EvaluationBackend`].
+//!
+//! Wrap a backend in a [`CachingBackend`] to reuse the results of repeated evaluations
+//! of the same expression.
 
-use std::{path::PathBuf, process};
+mod cache;
+mod failure;
+mod handle;
+mod session;
+mod trace;
+
+use std::{fmt, path::PathBuf, process};
 
 use wolfram_expr::Expr;
 
-use crate::{Error as WstpError, Link, Protocol};
+use crate::{sys, Error as WstpError, Link, Protocol, UrgentMessage};
+
+pub use self::{
+    cache::{CacheOptions, CachingBackend},
+    failure::WolframFailure,
+    handle::InterruptHandle,
+    session::KernelSession,
+    trace::{EvaluationTrace, PacketDirection, TracedPacket},
+};
+
+use self::{handle::HandleSource, trace::Tracer};
 
 /// Handle to a Wolfram Kernel process connected via WSTP.
 ///
@@ -70,24 +98,143 @@ use crate::{Error as WstpError, Link, Protocol};
 /// this kernel.
 #[derive(Debug)]
 pub struct WolframKernelProcess {
-    #[allow(dead_code)]
     process: process::Child,
+    // Note: Declared before `link`, so that interrupt handles are revoked before the
+    //       link is closed.
+    handles: HandleSource,
     link: Link,
+    /// If `true`, record an [`EvaluationTrace`] for each evaluation.
+    tracing: bool,
+    /// Trace recorded for the most recent evaluation.
+    last_trace: Option<EvaluationTrace>,
 }
 
 /// Wolfram Kernel process error.
+///
+/// Use [`Error::failure()`] to get the failure result that caused this error, if
+/// applicable.
 #[derive(Debug)]
-pub struct Error(String);
+pub struct Error {
+    message: String,
+    failure: Option<WolframFailure>,
+}
+
+/// A source of Wolfram Language evaluations.
+///
+/// This trait abstracts over the concrete type used to perform evaluations, so that
+/// application code can be written once and used with any evaluation source, including
+/// fake backends used for testing.
+///
+/// # Example
+///
+/// Test application code against a fake backend:
+///
+/// ```
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::kernel::EvaluationBackend;
+///
+/// /// Application code, generic over the evaluation backend.
+/// fn current_year<B: EvaluationBackend>(backend: &mut B) -> Result<Expr, B::Error> {
+///     backend.evaluate(&Expr::normal(Symbol::new("System`DateValue"), vec![
+///         Expr::string("Year")
+///     ]))
+/// }
+///
+/// /// Fake backend that always returns the same result.
+/// struct FakeBackend;
+///
+/// impl EvaluationBackend for FakeBackend {
+///     type Error = ();
+///
+///     fn evaluate(&mut self, _: &Expr) -> Result<Expr, ()> {
+///         Ok(Expr::from(2022i64))
+///     }
+///
+///     fn interrupt(&mut self) -> Result<(), ()> {
+///         Ok(())
+///     }
+///
+///     fn shutdown(&mut self) -> Result<(), ()> {
+///         Ok(())
+///     }
+/// }
+///
+/// assert_eq!(current_year(&mut FakeBackend), Ok(Expr::from(2022i64)));
+/// ```
+pub trait EvaluationBackend {
+    /// Error type returned by this backend.
+    type Error;
+
+    /// Evaluate `expr`, and return the result of the evaluation.
+    fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Self::Error>;
+
+    /// Request that the evaluation currently in progress (if any) be interrupted.
+    ///
+    /// Because this method takes `&mut self`, it cannot be called while
+    /// [`EvaluationBackend::evaluate()`] is running on the same backend. Backends that
+    /// support interrupting an evaluation from another thread provide a separate handle
+    /// for doing so, like [`InterruptHandle`].
+    fn interrupt(&mut self) -> Result<(), Self::Error>;
+
+    /// Shut down this backend.
+    ///
+    /// No further evaluations should be requested after this method has been called.
+    fn shutdown(&mut self) -> Result<(), Self::Error>;
+}
+
+impl Error {
+    fn custom(message: String) -> Self {
+        Error {
+            message,
+            failure: None,
+        }
+    }
+
+    /// Get the failure result returned by the evaluation that caused this error, if
+    /// applicable.
+    ///
+    /// See [`WolframKernelProcess::evaluate_checked()`].
+    pub fn failure(&self) -> Option<&WolframFailure> {
+        self.failure.as_ref()
+    }
+
+    /// Returns `true` if this error was caused by the evaluation being aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.failure == Some(WolframFailure::Aborted)
+    }
+}
 
 impl From<WstpError> for Error {
     fn from(err: WstpError) -> Error {
-        Error(format!("WSTP error: {err}"))
+        Error::custom(format!("WSTP error: {err}"))
     }
 }
 
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Error {
-        Error(format!("IO error: {err}"))
+        Error::custom(format!("IO error: {err}"))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Error {
+            message,
+            failure: _,
+        } = self;
+
+        write!(f, "Wolfram Kernel error: {}", message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<WolframFailure> for Error {
+    fn from(failure: WolframFailure) -> Error {
+        Error {
+            message: failure.to_string(),
+            failure: Some(failure),
+        }
     }
 }
 
@@ -125,7 +272,7 @@ impl WolframKernelProcess {
         let link: Link = match listener.join() {
             Ok(result) => result?,
             Err(panic) => {
-                return Err(Error(format!(
+                return Err(Error::custom(format!(
                     "unable to launch Wolfram Kernel: listening thread panicked: {:?}",
                     panic
                 )))
@@ -134,15 +281,217 @@ impl WolframKernelProcess {
 
         Ok(WolframKernelProcess {
             process: kernel_process,
+            handles: HandleSource::new(&link),
             link,
+            tracing: false,
+            last_trace: None,
         })
     }
 
     /// Get the WSTP [`Link`] connection used to communicate with this Wolfram Kernel
     /// process.
+    ///
+    /// [`InterruptHandle`]s of this kernel cannot be used until the next call to
+    /// [`WolframKernelProcess::evaluate()`], because the returned reference could be
+    /// used to replace or close the link.
     pub fn link(&mut self) -> &mut Link {
-        let WolframKernelProcess { process: _, link } = self;
-        link
+        self.handles.revoke();
+        &mut self.link
+    }
+
+    /// Get a handle that can be used to interrupt or abort evaluations performed by this
+    /// kernel, from any thread.
+    ///
+    /// See [`InterruptHandle`].
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.handles.handle()
+    }
+
+    /// Evaluate `expr` in this Wolfram Kernel process, and return the contents of the
+    /// resulting [`ReturnPacket`][ReturnPacket].
+    ///
+    /// Any other packets sent by the kernel before the `ReturnPacket` are discarded.
+    ///
+    /// If the evaluation is aborted or interrupted (using an [`InterruptHandle`]), an
+    /// error for which [`Error::is_aborted()`] returns `true` is returned. The link is
+    /// left at a packet boundary, so the kernel can be used for further evaluations
+    /// immediately.
+    ///
+    /// If [tracing][WolframKernelProcess::set_tracing] is enabled, the packets exchanged
+    /// during the evaluation are recorded, and can be retrieved using
+    /// [`WolframKernelProcess::take_trace()`].
+    ///
+    /// [ReturnPacket]: https://reference.wolfram.com/language/ref/ReturnPacket.html
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        let mut tracer = if self.tracing {
+            Some(Tracer::new())
+        } else {
+            None
+        };
+
+        self.handles.publish(&self.link);
+
+        let result = evaluate_on_link(&mut self.link, expr, tracer.as_mut());
+
+        if let Some(tracer) = tracer {
+            self.last_trace = Some(tracer.finish());
+        }
+
+        result
+    }
+
+    /// Enable or disable recording of an [`EvaluationTrace`] for each evaluation
+    /// performed by [`WolframKernelProcess::evaluate()`].
+    ///
+    /// Tracing is intended to be used to analyze the performance and correctness of
+    /// complex evaluations. Tracing reads ahead the contents of each packet, so it adds
+    /// overhead to every evaluation.
+    pub fn set_tracing(&mut self, enabled: bool) {
+        self.tracing = enabled;
+    }
+
+    /// Take the trace recorded for the most recent evaluation, if tracing was enabled
+    /// when it was performed.
+    ///
+    /// See [`WolframKernelProcess::set_tracing()`].
+    pub fn take_trace(&mut self) -> Option<EvaluationTrace> {
+        self.last_trace.take()
+    }
+
+
+    /// Evaluate `expr` in this Wolfram Kernel process, returning an error if the result
+    /// is `$Failed`, `$Aborted`, or a `Failure[..]` expression.
+    ///
+    /// The [`WolframFailure`] describing the failure result can be retrieved from the
+    /// returned error using [`Error::failure()`].
+    ///
+    /// See [`WolframKernelProcess::evaluate()`].
+    pub fn evaluate_checked(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        let result = self.evaluate(expr)?;
+
+        match WolframFailure::from_expr(&result) {
+            Some(failure) => Err(Error::from(failure)),
+            None => Ok(result),
+        }
+    }
+
+    /// Send an interrupt message to this Wolfram Kernel process.
+    ///
+    /// This method cannot be called while [`WolframKernelProcess::evaluate()`] is in
+    /// progress. Use [`WolframKernelProcess::interrupt_handle()`] to interrupt an
+    /// evaluation from another thread.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn interrupt(&mut self) -> Result<(), Error> {
+        self.put_message(UrgentMessage::Interrupt)
+    }
+
+    /// Send an abort message to this Wolfram Kernel process.
+    ///
+    /// This method cannot be called while [`WolframKernelProcess::evaluate()`] is in
+    /// progress. Use [`WolframKernelProcess::interrupt_handle()`] to abort an
+    /// evaluation from another thread.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn abort(&mut self) -> Result<(), Error> {
+        self.put_message(UrgentMessage::Abort)
+    }
+
+    fn put_message(&mut self, message: UrgentMessage) -> Result<(), Error> {
+        Ok(self.link.put_message(message)?)
+    }
+
+    /// Terminate this Wolfram Kernel process.
+    pub fn shutdown(&mut self) -> Result<(), Error> {
+        let process = &mut self.process;
+
+        process.kill()?;
+        let _: process::ExitStatus = process.wait()?;
+
+        Ok(())
+    }
+}
+
+/// Evaluate `expr` using the kernel connected to `link`.
+///
+/// See [`WolframKernelProcess::evaluate()`].
+fn evaluate_on_link(
+    link: &mut Link,
+    expr: &Expr,
+    mut tracer: Option<&mut Tracer>,
+) -> Result<Expr, Error> {
+    link.put_eval_packet(expr)?;
+    link.flush()?;
+
+    if let Some(tracer) = tracer.as_deref_mut() {
+        tracer.sent(sys::EVALUATEPKT, expr);
+    }
+
+    let mut aborted = false;
+
+    loop {
+        let packet: i32 = match link.raw_next_packet() {
+            Ok(packet) => packet,
+            // The link was interrupted by an abort while reading a packet. Recover the
+            // link, and discard packets until the `ReturnPacket` that ends the aborted
+            // evaluation.
+            Err(err) if err.code() == Some(sys::WSEABORT) => {
+                aborted = true;
+                link.clear_error();
+                link.new_packet()?;
+                continue;
+            },
+            Err(err) => return Err(Error::from(err)),
+        };
+
+        if let Some(tracer) = tracer.as_deref_mut() {
+            tracer.received(packet, link);
+        }
+
+        // The kernel responds to an interrupt message with an interrupt menu. There is no
+        // user to choose an option, so choose to abort the evaluation.
+        if packet == sys::MENUPKT {
+            link.new_packet()?;
+
+            link.put_str("a")?;
+            link.end_packet()?;
+            link.flush()?;
+
+            aborted = true;
+            continue;
+        }
+
+        if packet == sys::RETURNPKT {
+            let result = link.get_expr()?;
+            link.new_packet()?;
+
+            let is_aborted_result =
+                WolframFailure::from_expr(&result) == Some(WolframFailure::Aborted);
+
+            if aborted || is_aborted_result {
+                return Err(Error::from(WolframFailure::Aborted));
+            }
+
+            return Ok(result);
+        }
+
+        link.new_packet()?;
+    }
+}
+
+impl EvaluationBackend for WolframKernelProcess {
+    type Error = Error;
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        WolframKernelProcess::evaluate(self, expr)
+    }
+
+    fn interrupt(&mut self) -> Result<(), Error> {
+        WolframKernelProcess::interrupt(self)
+    }
+
+    fn shutdown(&mut self) -> Result<(), Error> {
+        WolframKernelProcess::shutdown(self)
     }
 }
 
@@ -0,0 +1,186 @@
+//! High-level evaluation session with a Wolfram Kernel connected by a [`Link`].
+
+use std::path::Path;
+
+use wolfram_expr::Expr;
+
+use crate::{Link, UrgentMessage};
+
+use super::{
+    evaluate_on_link, handle::HandleSource, Error, EvaluationBackend, InterruptHandle,
+    WolframFailure,
+};
+
+/// Evaluation session with a Wolfram Kernel connected by a [`Link`].
+///
+/// [`KernelSession`] handles the packet protocol used to communicate with the kernel:
+/// [`KernelSession::evaluate()`] sends an [`EvaluatePacket`][EvaluatePacket] and returns
+/// the contents of the [`ReturnPacket`][ReturnPacket] sent back by the kernel.
+///
+/// Unlike [`WolframKernelProcess`][super::WolframKernelProcess], a [`KernelSession`]
+/// can be used with a kernel that was started by another program, or is connected
+/// using any link protocol.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::kernel::KernelSession;
+///
+/// let kernel = Path::new(
+///     "/Applications/Mathematica.app/Contents/MacOS/WolframKernel"
+/// );
+///
+/// let mut session = KernelSession::launch(kernel).unwrap();
+///
+/// let result = session
+///     .evaluate(&Expr::normal(Symbol::new("System`Plus"), vec![
+///         Expr::from(2),
+///         Expr::from(2),
+///     ]))
+///     .unwrap();
+///
+/// assert_eq!(result, Expr::from(4));
+/// ```
+///
+/// [EvaluatePacket]: https://reference.wolfram.com/language/ref/EvaluatePacket.html
+/// [ReturnPacket]: https://reference.wolfram.com/language/ref/ReturnPacket.html
+#[derive(Debug)]
+pub struct KernelSession {
+    // Note: Declared before `link`, so that interrupt handles are revoked before the
+    //       link is closed.
+    handles: HandleSource,
+    link: Link,
+}
+
+impl KernelSession {
+    /// Construct a new session with the Wolfram Kernel connected to `link`.
+    pub fn new(link: Link) -> Self {
+        KernelSession {
+            handles: HandleSource::new(&link),
+            link,
+        }
+    }
+
+    /// Launch a new Wolfram Kernel, and construct a session with it.
+    ///
+    /// See [`Link::launch()`].
+    pub fn launch(kernel: &Path) -> Result<Self, Error> {
+        Ok(KernelSession::new(Link::launch(kernel)?))
+    }
+
+    /// Launch the Wolfram Kernel of the default Wolfram Language installation, and
+    /// construct a session with it.
+    ///
+    /// This method is only available when the `wolfram-app-discovery` feature is
+    /// enabled.
+    ///
+    /// See [`Link::launch_default()`].
+    #[cfg(feature = "wolfram-app-discovery")]
+    pub fn launch_default() -> Result<Self, Error> {
+        Ok(KernelSession::new(Link::launch_default()?))
+    }
+
+    /// Get the WSTP [`Link`] used to communicate with the kernel.
+    ///
+    /// [`InterruptHandle`]s of this session cannot be used until the next call to
+    /// [`KernelSession::evaluate()`], because the returned reference could be used to
+    /// replace or close the link.
+    pub fn link(&mut self) -> &mut Link {
+        self.handles.revoke();
+        &mut self.link
+    }
+
+    /// Convert this session into the underlying [`Link`].
+    ///
+    /// [`InterruptHandle`]s of this session can no longer be used.
+    pub fn into_inner(self) -> Link {
+        let KernelSession { handles, link } = self;
+        drop(handles);
+        link
+    }
+
+    /// Get a handle that can be used to interrupt or abort evaluations performed by this
+    /// session, from any thread.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.handles.handle()
+    }
+
+    /// Evaluate `expr`, and return the contents of the resulting
+    /// [`ReturnPacket`][ReturnPacket].
+    ///
+    /// Any other packets sent by the kernel before the `ReturnPacket` are discarded.
+    ///
+    /// If the evaluation is aborted or interrupted using an [`InterruptHandle`], an
+    /// error for which [`Error::is_aborted()`] returns `true` is returned.
+    ///
+    /// [ReturnPacket]: https://reference.wolfram.com/language/ref/ReturnPacket.html
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        self.handles.publish(&self.link);
+
+        evaluate_on_link(&mut self.link, expr, None)
+    }
+
+    /// Evaluate `expr`, returning an error if the result is `$Failed`, `$Aborted`, or a
+    /// `Failure[..]` expression.
+    ///
+    /// See [`KernelSession::evaluate()`].
+    pub fn evaluate_checked(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        let result = self.evaluate(expr)?;
+
+        match WolframFailure::from_expr(&result) {
+            Some(failure) => Err(Error::from(failure)),
+            None => Ok(result),
+        }
+    }
+
+    /// Send an interrupt message to the kernel.
+    ///
+    /// This method cannot be called while [`KernelSession::evaluate()`] is in progress.
+    /// Use [`KernelSession::interrupt_handle()`] to interrupt an evaluation from
+    /// another thread.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn interrupt(&mut self) -> Result<(), Error> {
+        Ok(self.link.put_message(UrgentMessage::Interrupt)?)
+    }
+
+    /// Send an abort message to the kernel.
+    ///
+    /// This method cannot be called while [`KernelSession::evaluate()`] is in progress.
+    /// Use [`KernelSession::interrupt_handle()`] to abort an evaluation from another
+    /// thread.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn abort(&mut self) -> Result<(), Error> {
+        Ok(self.link.put_message(UrgentMessage::Abort)?)
+    }
+
+    /// Send a terminate message to the kernel, requesting that it exit.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn shutdown(&mut self) -> Result<(), Error> {
+        self.link.put_message(UrgentMessage::Terminate)?;
+        self.link.flush()?;
+
+        Ok(())
+    }
+}
+
+impl EvaluationBackend for KernelSession {
+    type Error = Error;
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        KernelSession::evaluate(self, expr)
+    }
+
+    fn interrupt(&mut self) -> Result<(), Error> {
+        KernelSession::interrupt(self)
+    }
+
+    fn shutdown(&mut self) -> Result<(), Error> {
+        KernelSession::shutdown(self)
+    }
+}
@@ -0,0 +1,105 @@
+//! Recording of the packets exchanged during an evaluation.
+
+use std::convert::TryFrom;
+use std::time::{Duration, Instant, SystemTime};
+
+use wolfram_expr::Expr;
+
+use crate::{Link, PacketType};
+
+/// Record of the packets exchanged with a Wolfram Kernel during a single evaluation.
+///
+/// See [`WolframKernelProcess::set_tracing()`][super::WolframKernelProcess::set_tracing].
+#[derive(Debug, Clone)]
+pub struct EvaluationTrace {
+    /// Time at which the evaluation started.
+    pub started_at: SystemTime,
+    /// Packets exchanged during the evaluation, in the order they were sent or received.
+    pub packets: Vec<TracedPacket>,
+}
+
+/// A packet recorded in an [`EvaluationTrace`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TracedPacket {
+    /// Whether this packet was sent to or received from the kernel.
+    pub direction: PacketDirection,
+    /// The WSTP packet type, e.g. [`sys::RETURNPKT`][crate::sys::RETURNPKT].
+    ///
+    /// *WSTP C API Documentation:* [`WSNextPacket()`](https://reference.wolfram.com/language/ref/c/WSNextPacket.html)
+    pub packet_type: i32,
+    /// Time elapsed between the start of the evaluation and this packet being sent or
+    /// received.
+    pub elapsed: Duration,
+    /// The first element of the packet, if it could be read.
+    pub contents: Option<Expr>,
+}
+
+/// Direction of a [`TracedPacket`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PacketDirection {
+    /// The packet was sent to the kernel.
+    Sent,
+    /// The packet was received from the kernel.
+    Received,
+}
+
+impl TracedPacket {
+    /// Get the name of the packet head, e.g. `"ReturnPacket"`, if this is a standard
+    /// packet type.
+    pub fn name(&self) -> Option<&'static str> {
+        PacketType::try_from(self.packet_type)
+            .ok()
+            .map(PacketType::name)
+    }
+}
+
+/// Accumulates an [`EvaluationTrace`] while an evaluation is in progress.
+pub(super) struct Tracer {
+    start: Instant,
+    trace: EvaluationTrace,
+}
+
+impl Tracer {
+    pub(super) fn new() -> Self {
+        Tracer {
+            start: Instant::now(),
+            trace: EvaluationTrace {
+                started_at: SystemTime::now(),
+                packets: Vec::new(),
+            },
+        }
+    }
+
+    pub(super) fn sent(&mut self, packet_type: i32, contents: &Expr) {
+        self.record(PacketDirection::Sent, packet_type, Some(contents.clone()));
+    }
+
+    /// Record a packet received on `link`, whose head has just been read by
+    /// [`Link::raw_next_packet()`].
+    ///
+    /// The contents of the packet are read without consuming them.
+    pub(super) fn received(&mut self, packet_type: i32, link: &mut Link) {
+        let contents = link.peek(|link| link.get_expr().ok()).ok().flatten();
+
+        self.record(PacketDirection::Received, packet_type, contents);
+    }
+
+    fn record(
+        &mut self,
+        direction: PacketDirection,
+        packet_type: i32,
+        contents: Option<Expr>,
+    ) {
+        self.trace.packets.push(TracedPacket {
+            direction,
+            packet_type,
+            elapsed: self.start.elapsed(),
+            contents,
+        });
+    }
+
+    pub(super) fn finish(self) -> EvaluationTrace {
+        self.trace
+    }
+}
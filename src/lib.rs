@@ -77,11 +77,12 @@
 //! endpoints is established when one endpoint is created using [`Link::listen()`], and
 //! another endpoint is created using [`Link::connect()`].
 //!
-//! At a lower level, WSTP is actually three protocols:
+//! At a lower level, WSTP is actually several protocols:
 //!
 //! * [`IntraProcess`][Protocol::IntraProcess]
 //! * [`SharedMemory`][Protocol::SharedMemory]
 //! * [`TCPIP`][Protocol::TCPIP]
+//! * [`Pipes`][Protocol::Pipes] (macOS and Linux only)
 //!
 //! which are represented by the [`Protocol`] enum. Each lower-level protocol is optimized
 //! for usage within a particular domain. For example, `IntraProcess` is the best link
@@ -137,17 +138,57 @@
 #![warn(missing_docs)]
 
 
+mod bignum;
+mod builder;
+mod close;
+mod collections;
+mod connect;
+mod convert;
+mod data;
+mod dump;
 mod env;
 mod error;
+mod lifecycle;
 mod link_server;
+mod link_state;
+mod message;
+mod mux;
+mod packet;
+mod progress;
+mod retry;
+mod rpc;
+mod service;
+mod shared;
+mod socket;
+mod split;
+mod stream;
+mod strict;
+mod version;
+mod visit;
 mod wait;
+mod wait_set;
+mod watchdog;
+mod yield_function;
 
 mod get;
 mod put;
 
 mod strx;
 
+#[doc(hidden)]
+pub mod macros;
+
 pub mod kernel;
+pub mod mathlink;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "serde")]
+pub mod serde;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
 
 /// Ensure that doc tests in the README.md file get run.
 #[doc(hidden)]
@@ -160,6 +201,7 @@ use std::convert::TryFrom;
 use std::ffi::{CStr, CString};
 use std::fmt::{self, Display};
 use std::net;
+use std::path::Path;
 
 use wolfram_expr::{Expr, ExprKind, Number, Symbol};
 use wstp_sys::{WSErrorMessage, WSReady, WSReleaseErrorMessage, WSLINK};
@@ -175,16 +217,54 @@ use wstp_sys::{WSErrorMessage, WSReady, WSReleaseErrorMessage, WSLINK};
 pub use wstp_sys as sys;
 
 pub use crate::{
-    env::shutdown,
-    error::Error,
-    get::{Array, LinkStr, Token, TokenType},
-    link_server::LinkServer,
+    builder::ExprBuilder,
+    close::{set_close_error_hook, take_close_error_hook, CloseErrorHook, CloseMode},
+    connect::DEFAULT_CONNECTION_ATTEMPT_DELAY,
+    convert::{GetFromLink, PutOnLink},
+    data::{LinkDataReader, LinkDataWriter},
+    env::{initialize, is_initialized, shutdown, Environment, EnvironmentBuilder},
+    error::{Error, ErrorKind},
+    get::{
+        Array, ArrayElement, LinkStr, OwnedToken, StringChunks, Token, TokenType, Tokens,
+    },
+    link_server::{ConnectionInfo, Incoming, LinkServer, ShutdownHandle},
+    message::UrgentMessage,
+    mux::MuxLink,
+    packet::{Packet, PacketType},
+    progress::Progress,
+    retry::RetryPolicy,
+    rpc::{RequestId, RpcLink},
+    service::{Service, ServiceError},
+    shared::{SharedLink, SharedLinkGuard},
+    split::{ReadHalf, WriteHalf},
+    stream::{ByteArrayWriter, StringWriter},
     strx::{Ucs2Str, Utf16Str, Utf32Str, Utf8Str},
+    version::{library_version, LibraryVersion},
+    visit::ExprVisitor,
+    wait_set::LinkWaitSet,
+    watchdog::{StallReport, Watchdog},
+    yield_function::YieldParameters,
 };
 
+#[cfg(feature = "derive")]
+pub use wstp_derive::{GetFromLink, PutOnLink};
+
 // TODO: Make this function public from `wstp`?
 pub(crate) use env::stdenv;
 
+/// Maximum nesting depth of an expression read by [`Link::get_expr()`].
+///
+/// Reading an expression recurses once per level of nesting, so this bounds the stack
+/// used by a malicious or buggy peer. 1024 levels leave about 2 KiB of stack per level
+/// on a thread with the default 2 MiB stack size, and match the default value of
+/// [`$RecursionLimit`](https://reference.wolfram.com/language/ref/$RecursionLimit.html)
+/// in the Wolfram Language, so deeper expressions are rare in practice.
+const MAX_EXPR_DEPTH: usize = 1024;
+
+/// Maximum number of elements [`Link::get_expr()`] will preallocate space for, before
+/// the elements have actually been read.
+const MAX_PREALLOCATED_ARGS: usize = 1024;
+
 
 //======================================
 // Source
@@ -241,13 +321,14 @@ impl Link {
 /// threads at once (unless `WSEnableLinkLock()` has been called on the link). So [`Link`]
 /// satisfies [`Send`] but not [`Sync`].
 ///
-/// **TODO:**
-///   Add a wrapper type for [`Link`] which enforces that `WSEnableLinkLock()`
-///   has been called, and implements [`Sync`].
+/// Use [`SharedLink`] to share a link between threads.
 unsafe impl Send for Link {}
 
 /// Transport protocol used to communicate between two [`Link`] end points.
+///
+/// New protocols may be added in future versions of this crate.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Protocol {
     /// Protocol type optimized for communication between two [`Link`] end points
     /// from within the same OS process.
@@ -259,6 +340,11 @@ pub enum Protocol {
     /// Protocol type for communication between two [`Link`] end points reachable
     /// across a network connection.
     TCPIP,
+    /// Protocol type for communication between two [`Link`] end points from the same
+    /// machine using operating system pipes.
+    ///
+    /// This protocol is supported on macOS and Linux.
+    Pipes,
 }
 
 //======================================
@@ -271,20 +357,59 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSLoopbackOpen()`](https://reference.wolfram.com/language/ref/c/WSLoopbackOpen.html)
     pub fn new_loopback() -> Result<Self, Error> {
+        Link::new_loopback_in(&Environment::shared())
+    }
+
+    /// Create a new Loopback type link in the WSTP environment `env`.
+    ///
+    /// See [`EnvironmentBuilder::build()`].
+    ///
+    /// *WSTP C API Documentation:* [`WSLoopbackOpen()`](https://reference.wolfram.com/language/ref/c/WSLoopbackOpen.html)
+    pub fn new_loopback_in(env: &Environment) -> Result<Self, Error> {
         unsafe {
             let mut err: std::os::raw::c_int = sys::MLEOK;
-            let raw_link = sys::WSLoopbackOpen(stdenv()?.raw_env, &mut err);
+            let raw_env = env.lock()?.raw_env;
+            let raw_link = sys::WSLoopbackOpen(raw_env, &mut err);
 
             if raw_link.is_null() || err != sys::MLEOK {
-                return Err(Error::from_code(err));
+                return Err(Error::from_code_in(raw_env, err));
             }
 
             Ok(Link::unchecked_new(raw_link))
         }
     }
 
+    /// Create a new Loopback type link containing `expr`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::Expr;
+    ///
+    /// let mut link = Link::loopback_with_expr(&Expr::from(5)).unwrap();
+    ///
+    /// assert_eq!(link.get_i64().unwrap(), 5);
+    /// ```
+    pub fn loopback_with_expr(expr: &Expr) -> Result<Self, Error> {
+        let mut link = Link::new_loopback()?;
+        link.put_expr(expr)?;
+        Ok(link)
+    }
+
     /// Create a new named WSTP link using `protocol`.
     pub fn listen(protocol: Protocol, name: &str) -> Result<Self, Error> {
+        Link::listen_in(&Environment::shared(), protocol, name)
+    }
+
+    /// Create a new named WSTP link using `protocol`, in the WSTP environment `env`.
+    ///
+    /// See [`EnvironmentBuilder::build()`].
+    pub fn listen_in(
+        env: &Environment,
+        protocol: Protocol,
+        name: &str,
+    ) -> Result<Self, Error> {
         let protocol_string = protocol.to_string();
 
         let strings: &[&str] = &[
@@ -300,7 +425,7 @@ impl Link {
             "MLDontInteract",
         ];
 
-        Link::open_with_args(strings)
+        Link::open_with_args_in(env, strings)
     }
 
     /// Connect to an existing named WSTP link.
@@ -308,6 +433,17 @@ impl Link {
         Link::connect_with_options(protocol, name, &[])
     }
 
+    /// Connect to an existing named WSTP link, in the WSTP environment `env`.
+    ///
+    /// See [`EnvironmentBuilder::build()`].
+    pub fn connect_in(
+        env: &Environment,
+        protocol: Protocol,
+        name: &str,
+    ) -> Result<Self, Error> {
+        Link::connect_with_options_in(env, protocol, name, &[])
+    }
+
     /// Create a new WSTP [`TCPIP`][Protocol::TCPIP] link bound to `addr`.
     ///
     /// If `addr` yields multiple addresses, listening will be attempted with each of the
@@ -316,7 +452,10 @@ impl Link {
     /// (the last address) is returned.
     pub fn tcpip_listen<A: net::ToSocketAddrs>(addr: A) -> Result<Self, Error> {
         let addrs = addr.to_socket_addrs().map_err(|err| {
-            Error::custom(format!("error connecting to TCPIP Link address: {}", err))
+            Error::with_source(
+                format!("error connecting to TCPIP Link address: {}", err),
+                err,
+            )
         })?;
 
         // Try each address, returning the first one which binds for listening successfully.
@@ -333,7 +472,10 @@ impl Link {
     /// (the last address) is returned.
     pub fn tcpip_connect<A: net::ToSocketAddrs>(addr: A) -> Result<Self, Error> {
         let addrs = addr.to_socket_addrs().map_err(|err| {
-            Error::custom(format!("error connecting to TCPIP Link address: {}", err))
+            Error::with_source(
+                format!("error connecting to TCPIP Link address: {}", err),
+                err,
+            )
         })?;
 
         // Try each address, returning the first one which connects successfully.
@@ -352,7 +494,10 @@ impl Link {
         addrs: A,
     ) -> Result<Self, Error> {
         let addrs = addrs.to_socket_addrs().map_err(|err| {
-            Error::custom(format!("error connecting to LinkServer address: {}", err))
+            Error::with_source(
+                format!("error connecting to LinkServer address: {}", err),
+                err,
+            )
         })?;
 
         // Try each address, returning the first one which connects successfully.
@@ -368,15 +513,149 @@ impl Link {
             // TODO: Should we activate here, or let the caller do this?
             let () = link.activate()?;
 
-            return Ok(link);
+            Ok(link)
         })
     }
 
+    /// Launch a new [`WolframKernel`][WolframKernel] process, and return a [`Link`]
+    /// connected to it.
+    ///
+    /// This uses the `-linkmode launch` link mode, which starts `kernel` as a child
+    /// process and connects to it. The link is [activated][Link::activate] before it is
+    /// returned.
+    ///
+    /// Use `Link::launch_default()`, which requires the `wolfram-app-discovery` feature,
+    /// to launch the kernel of the default Wolfram Language installation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use wstp::Link;
+    ///
+    /// let kernel = Path::new(
+    ///     "/Applications/Mathematica.app/Contents/MacOS/WolframKernel"
+    /// );
+    ///
+    /// let mut link = Link::launch(kernel).unwrap();
+    /// ```
+    ///
+    /// [WolframKernel]: https://reference.wolfram.com/language/ref/program/WolframKernel.html
+    pub fn launch(kernel: &Path) -> Result<Self, Error> {
+        let kernel = kernel.to_str().ok_or_else(|| {
+            Error::custom(format!(
+                "unable to launch Wolfram Kernel: path is not valid UTF-8: {}",
+                kernel.display()
+            ))
+        })?;
+
+        // Note: The link name is parsed as a command line, so quote the kernel path in
+        //       case it contains spaces.
+        let command = format!("\"{}\" -wstp", kernel);
+
+        let mut link = Link::open_with_args(&[
+            "-wstp",
+            "-linkmode",
+            "launch",
+            "-linkname",
+            command.as_str(),
+        ])?;
+
+        let () = link.activate()?;
+
+        Ok(link)
+    }
+
+    /// Launch the [`WolframKernel`][WolframKernel] of the default Wolfram Language
+    /// installation, and return a [`Link`] connected to it.
+    ///
+    /// The installation is located using
+    /// [`WolframApp::try_default()`](https://docs.rs/wolfram-app-discovery/0.3.0/wolfram_app_discovery/struct.WolframApp.html#method.try_default)
+    /// from the [wolfram-app-discovery] crate.
+    ///
+    /// This method is only available when the `wolfram-app-discovery` feature is
+    /// enabled.
+    ///
+    /// See [`Link::launch()`].
+    ///
+    /// [WolframKernel]: https://reference.wolfram.com/language/ref/program/WolframKernel.html
+    /// [wolfram-app-discovery]: https://crates.io/crates/wolfram-app-discovery
+    #[cfg(feature = "wolfram-app-discovery")]
+    pub fn launch_default() -> Result<Self, Error> {
+        let app = wolfram_app_discovery::WolframApp::try_default().map_err(|err| {
+            Error::custom(format!(
+                "unable to launch Wolfram Kernel: no Wolfram Language installation \
+                 found: {}",
+                err
+            ))
+        })?;
+
+        let kernel = app.kernel_executable_path().map_err(|err| {
+            Error::custom(format!(
+                "unable to launch Wolfram Kernel: no kernel executable found: {}",
+                err
+            ))
+        })?;
+
+        Link::launch(&kernel)
+    }
+
+    /// Connect to the parent program using the link arguments in `args`.
+    ///
+    /// This is intended for use by programs started by the Wolfram Language using
+    /// [`Install[..]`](https://reference.wolfram.com/language/ref/Install.html) or
+    /// [`LinkLaunch[..]`](https://reference.wolfram.com/language/ref/LinkLaunch.html),
+    /// which pass the arguments needed to connect back to the parent on the command line
+    /// of the program. `args` is typically the command line of the current process.
+    ///
+    /// Arguments other than the link arguments (e.g. `-linkname` and `-linkprotocol`) are
+    /// ignored. If `args` does not specify a `-linkmode`, the `parentconnect` link mode
+    /// is used. The link is [activated][Link::activate] before it is returned.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::Link;
+    ///
+    /// let args: Vec<String> = std::env::args().collect();
+    ///
+    /// let mut link = Link::parent_connect(&args).unwrap();
+    /// ```
+    pub fn parent_connect<S: AsRef<str>>(args: &[S]) -> Result<Self, Error> {
+        let mut strings: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+
+        let has_link_mode = strings.iter().any(|arg| {
+            arg.eq_ignore_ascii_case("-linkmode")
+                || arg.eq_ignore_ascii_case("-linklaunch")
+                || arg.eq_ignore_ascii_case("-linkconnect")
+                || arg.eq_ignore_ascii_case("-linkcreate")
+        });
+
+        if !has_link_mode {
+            strings.extend(["-linkmode", "parentconnect"]);
+        }
+
+        let mut link = Link::open_with_args(&strings)?;
+
+        let () = link.activate()?;
+
+        Ok(link)
+    }
+
     #[allow(missing_docs)]
     pub fn connect_with_options(
         protocol: Protocol,
         name: &str,
         options: &[&str],
+    ) -> Result<Self, Error> {
+        Link::connect_with_options_in(&Environment::shared(), protocol, name, options)
+    }
+
+    fn connect_with_options_in(
+        env: &Environment,
+        protocol: Protocol,
+        name: &str,
+        options: &[&str],
     ) -> Result<Self, Error> {
         let protocol_string = protocol.to_string();
 
@@ -396,7 +675,7 @@ impl Link {
             strings.extend(options);
         }
 
-        Link::open_with_args(&strings)
+        Link::open_with_args_in(env, &strings)
     }
 
     /// *WSTP C API Documentation:* [`WSOpenArgcArgv()`](https://reference.wolfram.com/language/ref/c/WSOpenArgcArgv.html)
@@ -410,9 +689,18 @@ impl Link {
     /// * [`Link::tcpip_listen()`]
     /// * [`Link::tcpip_connect()`]
     /// * [`Link::connect_to_link_server()`]
-    // * [`Link::launch()`]
-    // * [`Link::parent_connect()`]
+    /// * [`Link::launch()`]
+    /// * [`Link::parent_connect()`]
     pub fn open_with_args(args: &[&str]) -> Result<Self, Error> {
+        Link::open_with_args_in(&Environment::shared(), args)
+    }
+
+    /// Create a [`Link`] from `args`, in the WSTP environment `env`.
+    ///
+    /// See [`Link::open_with_args()`] and [`EnvironmentBuilder::build()`].
+    ///
+    /// *WSTP C API Documentation:* [`WSOpenArgcArgv()`](https://reference.wolfram.com/language/ref/c/WSOpenArgcArgv.html)
+    pub fn open_with_args_in(env: &Environment, args: &[&str]) -> Result<Self, Error> {
         // NOTE: Before returning, we must convert these back into CString's to
         //       deallocate them.
         let mut c_strings: Vec<*mut i8> = args
@@ -426,9 +714,11 @@ impl Link {
 
         let mut err: std::os::raw::c_int = sys::MLEOK;
 
+        let raw_env = env.lock()?.raw_env;
+
         let raw_link = unsafe {
             sys::WSOpenArgcArgv(
-                stdenv()?.raw_env,
+                raw_env,
                 i32::try_from(c_strings.len()).unwrap(),
                 c_strings.as_mut_ptr(),
                 &mut err,
@@ -444,7 +734,7 @@ impl Link {
         }
 
         if raw_link.is_null() || err != sys::MLEOK {
-            return Err(Error::from_code(err));
+            return Err(Error::from_code_in(raw_env, err));
         }
 
         Ok(Link { raw_link })
@@ -463,15 +753,103 @@ impl Link {
             return Err(self.error_or_unknown());
         }
 
+        lifecycle::connected(self);
+
         Ok(())
     }
 
     /// Close this end of the link.
     ///
     /// *WSTP C API Documentation:* [`WSClose()`](https://reference.wolfram.com/language/ref/c/WSClose.html)
+    ///
+    /// Data still waiting to be sent is handled according to the
+    /// [`close_mode()`][Link::close_mode] of this link.
     pub fn close(self) {
         // Note: The link is closed when `self` is dropped.
     }
+
+    /// Flush this link, and then close it.
+    ///
+    /// Returns an error if the data written to this link could not be delivered. The link
+    /// is closed regardless of whether flushing succeeded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64(5).unwrap();
+    ///
+    /// link.close_flushed().unwrap();
+    /// ```
+    pub fn close_flushed(mut self) -> Result<(), Error> {
+        let result = self.flush();
+
+        unsafe { self.release() };
+
+        result
+    }
+
+    /// Close this link without flushing it, discarding any data still waiting to be
+    /// sent.
+    ///
+    /// Unlike [`Link::close()`], this ignores the [`close_mode()`][Link::close_mode] of
+    /// this link, and does not call the hook registered with
+    /// [`set_close_error_hook()`].
+    pub fn close_abrupt(self) {
+        unsafe { self.release() };
+    }
+
+    /// Replace the underlying connection of this link with `connection`, returning the
+    /// previous connection.
+    ///
+    /// The per-link configuration set on this link, such as its
+    /// [`close_mode()`][Link::close_mode], [strict mode][Link::set_strict_mode], and
+    /// [progress observer][Link::set_progress_observer], is moved to the new connection.
+    /// Any configuration previously set on `connection` is discarded.
+    ///
+    /// This is intended for reconnecting after a failure, so that code holding a
+    /// `&mut Link` can continue using it unchanged. The returned previous connection can
+    /// be inspected for the error that caused the failure, and is closed when dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{CloseMode, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.set_close_mode(CloseMode::Flush);
+    ///
+    /// let old = link.replace_connection(Link::new_loopback().unwrap());
+    ///
+    /// assert_eq!(link.close_mode(), CloseMode::Flush);
+    /// assert_eq!(old.close_mode(), CloseMode::Abrupt);
+    /// ```
+    pub fn replace_connection(&mut self, connection: Link) -> Link {
+        let new_raw_link = connection.raw_link;
+
+        // Note: `connection` is now owned by `self`, and must not be closed.
+        std::mem::forget(connection);
+
+        let old_raw_link = std::mem::replace(&mut self.raw_link, new_raw_link);
+
+        crate::close::transfer_state(old_raw_link, new_raw_link);
+
+        Link {
+            raw_link: old_raw_link,
+        }
+    }
+
+    /// Close this link without running its [`Drop`] impl.
+    unsafe fn release(self) {
+        let raw_link = self.raw_link;
+
+        std::mem::forget(self);
+
+        crate::close::release(raw_link);
+    }
 }
 
 /// # Link properties
@@ -484,7 +862,7 @@ impl Link {
 
         unsafe {
             let name: *const i8 = self::sys::WSName(raw_link as *mut _);
-            CStr::from_ptr(name).to_str().unwrap().to_owned()
+            CStr::from_ptr(name).to_string_lossy().into_owned()
         }
     }
 
@@ -521,7 +899,7 @@ impl Link {
 
         let string: String = unsafe {
             let cstr = CStr::from_ptr(message);
-            let string = cstr.to_str().unwrap().to_owned();
+            let string = cstr.to_string_lossy().into_owned();
 
             WSReleaseErrorMessage(raw_link, message);
             // TODO: Should this method clear the error? If it does, it should at least be
@@ -531,10 +909,13 @@ impl Link {
             string
         };
 
-        return Some(Error {
+        Some(Error {
             code: Some(code),
             message: string,
-        });
+            kind: ErrorKind::from_code(code),
+            source: None,
+            context: Vec::new(),
+        })
     }
 
     /// Returns a string describing the last error to occur on this link.
@@ -544,7 +925,7 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSErrorMessage()`](https://reference.wolfram.com/language/ref/c/WSErrorMessage.html)
     pub fn error_message(&self) -> Option<String> {
-        self.error().map(|Error { message, code: _ }| message)
+        self.error().map(|Error { message, .. }| message)
     }
 
     /// Helper to create an [`Error`] instance even if the underlying link does not have
@@ -554,6 +935,33 @@ impl Link {
             .unwrap_or_else(|| Error::custom("unknown error occurred on WSLINK".into()))
     }
 
+    /// Call `func` with this link, and then restore the read position of this link to
+    /// what it was before `func` was called.
+    ///
+    /// This is implemented using a WSTP mark, so the data read by `func` is not
+    /// consumed.
+    ///
+    /// *WSTP C API Documentation:* [`WSCreateMark()`](https://reference.wolfram.com/language/ref/c/WSCreateMark.html)
+    pub(crate) fn peek<T, F>(&mut self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> T,
+    {
+        let mark: sys::MLINKMark = unsafe { sys::WSCreateMark(self.raw_link) };
+
+        if mark.is_null() {
+            return Err(self.error_or_unknown());
+        }
+
+        // Note: The link is rewound and the mark is released by the guard, even if
+        //       `func` panics.
+        let _guard = MarkGuard {
+            raw_link: self.raw_link,
+            mark,
+        };
+
+        Ok(func(self))
+    }
+
     /// Clear errors on this link.
     ///
     /// *WSTP C API Documentation:* [`WSClearError()`](https://reference.wolfram.com/language/ref/c/WSClearError.html)
@@ -565,6 +973,48 @@ impl Link {
         }
     }
 
+    /// Attempt to recover from an error on this link.
+    ///
+    /// This clears the error state of this link, and then discards any remaining data
+    /// in the current packet, so that reading can resume at the start of the next
+    /// packet. Returns `true` if the link is usable again.
+    ///
+    /// Returns `false` if the error cannot be recovered from, e.g. because the link
+    /// has died or the other side closed the connection (see
+    /// [`Error::is_link_dead()`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_str("not an integer").unwrap();
+    ///
+    /// assert!(link.get_i64().is_err());
+    /// assert!(link.error().is_some());
+    ///
+    /// assert!(link.recover());
+    /// assert!(link.error().is_none());
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSClearError()`](https://reference.wolfram.com/language/ref/c/WSClearError.html),
+    /// [`WSNewPacket()`](https://reference.wolfram.com/language/ref/c/WSNewPacket.html)
+    pub fn recover(&mut self) -> bool {
+        let Link { raw_link } = *self;
+
+        if unsafe { sys::WSClearError(raw_link) } == 0 {
+            return false;
+        }
+
+        if unsafe { sys::WSNewPacket(raw_link) } == 0 {
+            return false;
+        }
+
+        self.error().is_none()
+    }
+
     /// *WSTP C API Documentation:* [`WSLINK`](https://reference.wolfram.com/language/ref/c/WSLINK.html)
     pub unsafe fn raw_link(&self) -> WSLINK {
         let Link { raw_link } = *self;
@@ -618,6 +1068,8 @@ impl Link {
         Ok(type_)
     }
 
+    /// See also [`Link::next_packet()`], which returns a typed [`Packet`].
+    ///
     /// *WSTP C API Documentation:* [`WSNextPacket()`](https://reference.wolfram.com/language/ref/c/WSNextPacket.html)
     pub fn raw_next_packet(&mut self) -> Result<i32, Error> {
         let type_ = unsafe { sys::WSNextPacket(self.raw_link) };
@@ -643,15 +1095,91 @@ impl Link {
         self.get_expr_with_resolver(&mut |_| None)
     }
 
+    /// Read an expression off of this link, if data is available.
+    ///
+    /// Returns `Ok(None)` without blocking if [`Link::is_ready()`] reports that no data
+    /// is available to be read. This is useful for polling a link from an event loop.
+    ///
+    /// Once data starts arriving, this reads the complete expression, and so may block
+    /// if the other side of the link has only sent part of an expression.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::Expr;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// assert_eq!(link.try_get_expr().unwrap(), None);
+    ///
+    /// link.put_i64(5).unwrap();
+    ///
+    /// assert_eq!(link.try_get_expr().unwrap(), Some(Expr::from(5)));
+    /// ```
+    pub fn try_get_expr(&mut self) -> Result<Option<Expr>, Error> {
+        if !self.is_ready() {
+            return Ok(None);
+        }
+
+        self.get_expr().map(Some)
+    }
+
+    /// Read every expression currently available on this link.
+    ///
+    /// Expressions are read using [`Link::try_get_expr()`] until no more data is
+    /// available. This is typically used to collect the contents of a loopback link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::Expr;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64(1).unwrap();
+    /// link.put_str("two").unwrap();
+    ///
+    /// assert_eq!(link.drain_exprs().unwrap(), vec![Expr::from(1), Expr::string("two")]);
+    /// assert_eq!(link.drain_exprs().unwrap(), vec![]);
+    /// ```
+    pub fn drain_exprs(&mut self) -> Result<Vec<Expr>, Error> {
+        let mut exprs = Vec::new();
+
+        while let Some(expr) = self.try_get_expr()? {
+            exprs.push(expr);
+        }
+
+        Ok(exprs)
+    }
+
     // TODO: This needs a bit more design work before being made public. For starters,
     //       you have to pass a closure to it using `get_expr_with_resolver(&mut |_| ...)`
     //       which looks out of place. Using `dyn FnMut()` is to avoid having to
     //       monomorphize different copies of `get_expr_with_resolver()`
     #[doc(hidden)]
     pub fn get_expr_with_resolver(
+        &mut self,
+        resolver: &mut dyn FnMut(&str) -> Option<Symbol>,
+    ) -> Result<Expr, Error> {
+        self.get_expr_at_depth(resolver, 0)
+    }
+
+    fn get_expr_at_depth(
         &mut self,
         mut resolver: &mut dyn FnMut(&str) -> Option<Symbol>,
+        depth: usize,
     ) -> Result<Expr, Error> {
+        // Guard against a peer exhausting the stack by sending a deeply nested
+        // expression.
+        if depth > MAX_EXPR_DEPTH {
+            return Err(Error::custom(format!(
+                "expression read from link exceeds the maximum nesting depth of {}",
+                MAX_EXPR_DEPTH
+            )));
+        }
+
         let value = self.get_token()?;
 
         let expr: Expr = match value {
@@ -660,11 +1188,10 @@ impl Link {
                 let real: wolfram_expr::F64 = match wolfram_expr::F64::new(value) {
                     Ok(real) => real,
                     // TODO: Try passing a NaN value or a BigReal value through WSLINK.
-                    Err(_is_nan) => {
-                        return Err(Error::custom(format!(
+                    Err(_is_nan) => return Err(Error::custom(
                         "NaN value passed on WSLINK cannot be used to construct an Expr"
-                    )))
-                    },
+                            .to_owned(),
+                    )),
                 };
                 Expr::number(Number::Real(real))
             },
@@ -691,11 +1218,34 @@ impl Link {
             Token::Function { length: arg_count } => {
                 drop(value);
 
-                let head = self.get_expr_with_resolver(&mut resolver)?;
+                let head = match self.get_expr_at_depth(&mut resolver, depth + 1) {
+                    Ok(head) => head,
+                    Err(err) => {
+                        return Err(err.with_context(|| {
+                            "while reading the head of a function expression".to_owned()
+                        }))
+                    },
+                };
 
-                let mut contents = Vec::with_capacity(arg_count);
-                for _ in 0..arg_count {
-                    contents.push(self.get_expr_with_resolver(&mut resolver)?);
+                // Note: `arg_count` is controlled by the peer, so don't trust it when
+                //       preallocating.
+                let capacity = arg_count.min(MAX_PREALLOCATED_ARGS);
+
+                let mut contents = Vec::with_capacity(capacity);
+                for index in 0..arg_count {
+                    match self.get_expr_at_depth(&mut resolver, depth + 1) {
+                        Ok(elem) => contents.push(elem),
+                        Err(err) => {
+                            return Err(err.with_context(|| {
+                                format!(
+                                    "while reading argument {} of {} of a function \
+                                     expression",
+                                    index + 1,
+                                    arg_count
+                                )
+                            }))
+                        },
+                    }
                 }
 
                 Expr::normal(head, contents)
@@ -758,6 +1308,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSTransferExpression()`](https://reference.wolfram.com/language/ref/c/WSTransferExpression.html)
     pub fn transfer_expr_to(&mut self, dest: &mut Link) -> Result<(), Error> {
+        crate::strict::on_put_expr(dest, "transfer_expr_to")?;
+
         let result = unsafe { sys::WSTransferExpression(dest.raw_link, self.raw_link) };
 
         if result == 0 {
@@ -767,8 +1319,125 @@ impl Link {
         Ok(())
     }
 
+    /// Read and discard the next expression on this link.
+    ///
+    /// The expression is discarded by WSTP without being converted into an [`Expr`], so
+    /// this is an efficient way to ignore data that the caller does not recognize.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put(&vec!["ignored"; 1000]).unwrap();
+    /// link.put_i64(5).unwrap();
+    ///
+    /// link.skip_expr().unwrap();
+    ///
+    /// assert_eq!(link.get_i64().unwrap(), 5);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSTransferExpression()`](https://reference.wolfram.com/language/ref/c/WSTransferExpression.html)
+    pub fn skip_expr(&mut self) -> Result<(), Error> {
+        // Passing a NULL destination link causes WSTransferExpression() to discard the
+        // expression.
+        let result =
+            unsafe { sys::WSTransferExpression(std::ptr::null_mut(), self.raw_link) };
+
+        if result == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
+    /// Transfer the next `count` expressions from this link to `dest`.
+    ///
+    /// See also [`Link::transfer_expr_to()`].
+    pub fn transfer_n_exprs_to(
+        &mut self,
+        dest: &mut Link,
+        count: usize,
+    ) -> Result<(), Error> {
+        for _ in 0..count {
+            self.transfer_expr_to(dest)?;
+        }
+
+        Ok(())
+    }
+
+    /// Transfer expressions from this link to `dest` until there is no more data ready
+    /// to be read from this link.
+    ///
+    /// Returns the number of expressions that were transferred.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut a = Link::new_loopback().unwrap();
+    /// let mut b = Link::new_loopback().unwrap();
+    ///
+    /// a.put_i64(1).unwrap();
+    /// a.put_i64(2).unwrap();
+    /// a.put_str("three").unwrap();
+    ///
+    /// assert_eq!(a.transfer_until_empty(&mut b).unwrap(), 3);
+    ///
+    /// assert_eq!(b.get_i64().unwrap(), 1);
+    /// assert_eq!(b.get_i64().unwrap(), 2);
+    /// assert_eq!(b.get_string().unwrap(), "three");
+    /// ```
+    ///
+    /// See also [`Link::transfer_expr_to()`].
+    pub fn transfer_until_empty(&mut self, dest: &mut Link) -> Result<usize, Error> {
+        let mut count = 0;
+
+        while self.is_ready() {
+            self.transfer_expr_to(dest)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
     /// Transfer the full contents of this loopback link to `dest`.
     ///
+    /// # Example
+    ///
+    /// Build up the arguments of a function in a loopback link, before the number of
+    /// arguments is known, and then write the complete function to another link:
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::{Expr, Symbol};
+    ///
+    /// let mut args = Link::new_loopback().unwrap();
+    /// let mut count = 0;
+    ///
+    /// for i in (1..=10).filter(|i| i % 3 == 0) {
+    ///     args.put_i64(i).unwrap();
+    ///     count += 1;
+    /// }
+    ///
+    /// let mut dest = Link::new_loopback().unwrap();
+    ///
+    /// dest.put_function("System`List", count).unwrap();
+    /// args.transfer_to_end_of_loopback_link(&mut dest).unwrap();
+    ///
+    /// assert_eq!(
+    ///     dest.get_expr().unwrap(),
+    ///     Expr::normal(Symbol::new("System`List"), vec![
+    ///         Expr::from(3),
+    ///         Expr::from(6),
+    ///         Expr::from(9)
+    ///     ])
+    /// );
+    /// ```
+    ///
     /// *WSTP C API Documentation:* [`WSTransferToEndOfLoopbackLink()`](https://reference.wolfram.com/language/ref/c/WSTransferToEndOfLoopbackLink.html)
     ///
     /// # Panics
@@ -782,6 +1451,14 @@ impl Link {
             panic!("transfer_to_end_of_loopback_link(): self must be a loopback link");
         }
 
+        // Note: The number of expressions in `self` is not known ahead of time, so in
+        //       strict mode they are transferred one at a time, letting
+        //       `transfer_expr_to()` account for each of them in the put state of `dest`.
+        if dest.is_strict_mode() {
+            self.transfer_until_empty(dest)?;
+            return Ok(());
+        }
+
         let result =
             unsafe { sys::WSTransferToEndOfLoopbackLink(dest.raw_link, self.raw_link) };
 
@@ -803,6 +1480,46 @@ impl Link {
 // Utilities
 //======================================
 
+/// Wrapper used to store non-[`Send`] values (typically raw pointers) in global statics
+/// or move them across threads.
+pub(crate) struct ForceSend<T>(pub T);
+
+unsafe impl<T> Send for ForceSend<T> {}
+
+impl<T> ForceSend<T> {
+    /// Unwrap the value.
+    ///
+    /// Inside a `move` closure, calling this method makes the closure capture the whole
+    /// `ForceSend`, whereas destructuring it would only capture the (non-[`Send`])
+    /// field.
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Rewinds a link to a mark, and releases the mark, when dropped.
+///
+/// Used by [`Link::peek()`].
+struct MarkGuard {
+    raw_link: WSLINK,
+    mark: sys::MLINKMark,
+}
+
+impl Drop for MarkGuard {
+    fn drop(&mut self) {
+        let MarkGuard { raw_link, mark } = *self;
+
+        unsafe {
+            // Note: Any error that occurred while reading ahead must be cleared before
+            //       the link can be rewound.
+            sys::WSClearError(raw_link);
+            sys::WSSeekToMark(raw_link, mark, 0);
+            sys::WSDestroyMark(raw_link, mark);
+        }
+    }
+}
+
 fn for_each_addr<T, F>(addrs: Vec<net::SocketAddr>, mut func: F) -> Result<T, Error>
 where
     F: FnMut(net::SocketAddr) -> Result<T, Error>,
@@ -817,7 +1534,7 @@ where
     }
 
     Err(last_error
-        .unwrap_or_else(|| Error::custom(format!("socket address list is empty"))))
+        .unwrap_or_else(|| Error::custom("socket address list is empty".to_owned())))
 }
 
 /// Construct an address string in the special syntax used by WSTP.
@@ -835,6 +1552,7 @@ impl Display for Protocol {
             Protocol::IntraProcess => "IntraProcess",
             Protocol::SharedMemory => "SharedMemory",
             Protocol::TCPIP => "TCPIP",
+            Protocol::Pipes => "Pipes",
         };
 
         write!(f, "{}", str)
@@ -849,8 +1567,10 @@ impl Drop for Link {
     fn drop(&mut self) {
         let Link { raw_link } = *self;
 
+        crate::close::on_drop(self);
+
         unsafe {
-            sys::WSClose(raw_link);
+            crate::close::release(raw_link);
         }
     }
 }
@@ -0,0 +1,269 @@
+//! Callbacks notified of changes to the connection state of a link.
+
+use std::sync::{Arc, RwLock};
+
+use crate::{link_state, Error, Link, LinkServer};
+
+type LinkHook = Arc<dyn Fn(&Link) + Send + Sync + 'static>;
+type ErrorHook = Arc<dyn Fn(&Link, &Error) + Send + Sync + 'static>;
+
+/// Callbacks registered using [`Link::on_connected()`], [`Link::on_peer_closed()`], and
+/// [`Link::on_error()`], or the equivalent [`LinkServer`] methods.
+#[derive(Clone, Default)]
+pub(crate) struct LifecycleHooks {
+    on_connected: Option<LinkHook>,
+    on_peer_closed: Option<LinkHook>,
+    on_error: Option<ErrorHook>,
+}
+
+/// Lifecycle callbacks of a link, and the events that have already been reported.
+pub(crate) struct Lifecycle {
+    hooks: LifecycleHooks,
+    connected: bool,
+    peer_closed: bool,
+}
+
+/// Lifecycle callbacks shared by a [`LinkServer`] with the links it accepts.
+pub(crate) type SharedHooks = Arc<RwLock<LifecycleHooks>>;
+
+/// # Lifecycle callbacks
+///
+/// These callbacks let supervisory code react to changes in the state of a connection
+/// without polling each link. They are called by the functions that wait on a link:
+///
+/// * [`Link::activate()`], once the connection has been established.
+/// * [`Link::wait()`], [`Link::wait_with_timeout()`], and [`Link::wait_with_callback()`],
+///   when waiting fails.
+/// * [`LinkWaitSet::wait()`][crate::LinkWaitSet::wait], for each link that became ready
+///   because its connection failed or was closed.
+///
+/// Callbacks are called on the thread that is waiting, and must not block for long.
+impl Link {
+    /// Register a function that is called once this link has connected to its peer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, Protocol};
+    ///
+    /// let mut listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+    /// let mut link = Link::connect(Protocol::IntraProcess, &listener.link_name()).unwrap();
+    ///
+    /// link.on_connected(|link: &Link| println!("connected: {}", link.link_name()));
+    ///
+    /// std::thread::spawn(move || listener.activate());
+    ///
+    /// link.activate().unwrap();
+    /// ```
+    pub fn on_connected<F>(&mut self, callback: F)
+    where
+        F: Fn(&Link) + Send + Sync + 'static,
+    {
+        let callback: LinkHook = Arc::new(callback);
+
+        self.update_lifecycle(|hooks| hooks.on_connected = Some(callback));
+    }
+
+    /// Register a function that is called once when waiting on this link fails because
+    /// the peer closed the connection.
+    pub fn on_peer_closed<F>(&mut self, callback: F)
+    where
+        F: Fn(&Link) + Send + Sync + 'static,
+    {
+        let callback: LinkHook = Arc::new(callback);
+
+        self.update_lifecycle(|hooks| hooks.on_peer_closed = Some(callback));
+    }
+
+    /// Register a function that is called when waiting on this link fails for a reason
+    /// other than the peer closing the connection.
+    pub fn on_error<F>(&mut self, callback: F)
+    where
+        F: Fn(&Link, &Error) + Send + Sync + 'static,
+    {
+        let callback: ErrorHook = Arc::new(callback);
+
+        self.update_lifecycle(|hooks| hooks.on_error = Some(callback));
+    }
+
+    /// Unregister the functions registered using [`Link::on_connected()`],
+    /// [`Link::on_peer_closed()`], and [`Link::on_error()`].
+    pub fn clear_lifecycle_callbacks(&mut self) {
+        let previous =
+            link_state::with_existing(self.raw_link, |state| state.lifecycle.take());
+
+        // Note: Drop the callbacks after the state table has been unlocked.
+        drop(previous);
+    }
+
+    fn update_lifecycle<F: FnOnce(&mut LifecycleHooks)>(&mut self, update: F) {
+        let previous = link_state::with(self.raw_link, |state| {
+            let lifecycle = state.lifecycle.get_or_insert_with(|| Lifecycle {
+                hooks: LifecycleHooks::default(),
+                connected: false,
+                peer_closed: false,
+            });
+
+            let previous = lifecycle.hooks.clone();
+
+            update(&mut lifecycle.hooks);
+
+            previous
+        });
+
+        drop(previous);
+    }
+}
+
+/// # Lifecycle callbacks
+///
+/// Callbacks registered on a link server apply to every link it accepts, whether using
+/// [`LinkServer::accept()`] or a callback passed to
+/// [`LinkServer::new_with_callback()`]. Links accepted before a callback is registered
+/// are not affected.
+impl LinkServer {
+    /// Register a function that is called with each link accepted by this link server,
+    /// before it is returned or passed to the server callback.
+    pub fn on_connected<F>(&mut self, callback: F)
+    where
+        F: Fn(&Link) + Send + Sync + 'static,
+    {
+        let previous = write_hooks(&self.hooks)
+            .on_connected
+            .replace(Arc::new(callback));
+
+        drop(previous);
+    }
+
+    /// Register a function that is called once for each link accepted by this link
+    /// server when the peer closes the connection. See [`Link::on_peer_closed()`].
+    pub fn on_peer_closed<F>(&mut self, callback: F)
+    where
+        F: Fn(&Link) + Send + Sync + 'static,
+    {
+        let previous = write_hooks(&self.hooks)
+            .on_peer_closed
+            .replace(Arc::new(callback));
+
+        drop(previous);
+    }
+
+    /// Register a function that is called when waiting on a link accepted by this link
+    /// server fails. See [`Link::on_error()`].
+    pub fn on_error<F>(&mut self, callback: F)
+    where
+        F: Fn(&Link, &Error) + Send + Sync + 'static,
+    {
+        let previous = write_hooks(&self.hooks)
+            .on_error
+            .replace(Arc::new(callback));
+
+        drop(previous);
+    }
+}
+
+/// Register the hooks of a link server on a link it accepted, and report that the link
+/// is connected.
+pub(crate) fn accepted(link: &Link, hooks: &SharedHooks) {
+    let hooks = read_hooks(hooks).clone();
+
+    if hooks.on_connected.is_none()
+        && hooks.on_peer_closed.is_none()
+        && hooks.on_error.is_none()
+    {
+        return;
+    }
+
+    link_state::with(link.raw_link, |state| {
+        state.lifecycle = Some(Lifecycle {
+            hooks,
+            connected: false,
+            peer_closed: false,
+        });
+    });
+
+    connected(link);
+}
+
+/// Report that `link` has connected to its peer.
+pub(crate) fn connected(link: &Link) {
+    let hook = link_state::with_existing(link.raw_link, |state| {
+        let lifecycle = state.lifecycle.as_mut()?;
+
+        if lifecycle.connected {
+            return None;
+        }
+
+        lifecycle.connected = true;
+
+        lifecycle.hooks.on_connected.clone()
+    })
+    .flatten();
+
+    if let Some(hook) = hook {
+        hook(link);
+    }
+}
+
+/// Report that waiting on `link` failed with `err`.
+pub(crate) fn wait_failed(link: &Link, err: &Error) {
+    let hook = link_state::with_existing(link.raw_link, |state| {
+        let lifecycle = state.lifecycle.as_mut()?;
+
+        if err.is_closed_by_peer() {
+            if lifecycle.peer_closed {
+                return None;
+            }
+
+            lifecycle.peer_closed = true;
+
+            let hook = lifecycle.hooks.on_peer_closed.clone()?;
+
+            Some(Hook::PeerClosed(hook))
+        } else {
+            let hook = lifecycle.hooks.on_error.clone()?;
+
+            Some(Hook::Error(hook))
+        }
+    })
+    .flatten();
+
+    // Note: Hooks are called after the state table has been unlocked, so that they may
+    //       register or clear callbacks.
+    match hook {
+        Some(Hook::PeerClosed(hook)) => hook(link),
+        Some(Hook::Error(hook)) => hook(link, err),
+        None => (),
+    }
+}
+
+/// Report the error on `link`, if any, after it became ready while being waited on.
+pub(crate) fn check_ready(link: &Link) {
+    let has_lifecycle =
+        link_state::with_existing(link.raw_link, |state| state.lifecycle.is_some());
+
+    if has_lifecycle != Some(true) {
+        return;
+    }
+
+    if let Some(err) = link.error() {
+        wait_failed(link, &err);
+    }
+}
+
+enum Hook {
+    PeerClosed(LinkHook),
+    Error(ErrorHook),
+}
+
+fn read_hooks(hooks: &SharedHooks) -> std::sync::RwLockReadGuard<LifecycleHooks> {
+    hooks
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write_hooks(hooks: &SharedHooks) -> std::sync::RwLockWriteGuard<LifecycleHooks> {
+    hooks
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -1,9 +1,18 @@
 use std::ffi::{CStr, CString};
 use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
 use std::os::raw::c_int;
 use std::str::FromStr;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, SystemTime};
 
-use crate::{sys, Error, Link};
+use crate::{
+    lifecycle::{self, SharedHooks},
+    sys, Error, Link, Protocol,
+};
 
 /// WSTP link server.
 ///
@@ -17,6 +26,65 @@ use crate::{sys, Error, Link};
 /// from this type (waiting and an async callback).
 pub struct LinkServer {
     raw_link_server: sys::WSLinkServer,
+    /// Environment the link server was created in, used to describe WSTP error codes.
+    raw_env: sys::WSENV,
+    /// Closure passed to [`LinkServer::new_with_callback()`], if any, which is freed
+    /// when this link server is dropped.
+    callback: Option<Callback>,
+    /// Set by [`LinkServer::shutdown()`].
+    is_shut_down: Arc<AtomicBool>,
+    /// Lifecycle callbacks registered on the links accepted by this server.
+    pub(crate) hooks: SharedHooks,
+}
+
+/// Maximum time [`ShutdownHandle::shutdown()`] waits to connect to the link server.
+const SHUTDOWN_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Handle used to shut down a [`LinkServer`] from another thread.
+///
+/// This `struct` is created by the [`LinkServer::shutdown_handle()`] method.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    is_shut_down: Arc<AtomicBool>,
+    /// Address used to connect to the link server to wake up a blocked
+    /// [`LinkServer::accept()`] call.
+    addr: SocketAddr,
+}
+
+/// Context pointer registered with a callback [`LinkServer`], pointing to a boxed
+/// `CallbackContext<F>`.
+struct CallbackContext<F> {
+    /// Shared with the [`LinkServer`], so that connections received after the link
+    /// server has been shut down are closed instead of passed to `callback`.
+    is_shut_down: Arc<AtomicBool>,
+    /// Shared with the [`LinkServer`], so that lifecycle callbacks registered after the
+    /// link server was created apply to the connections passed to `callback`.
+    hooks: SharedHooks,
+    callback: F,
+}
+
+/// Type-erased [`CallbackContext`] of a [`LinkServer`].
+struct Callback {
+    ptr: *mut std::ffi::c_void,
+    drop: unsafe fn(*mut std::ffi::c_void),
+}
+
+/// Information about a connection accepted by a [`LinkServer`].
+///
+/// Returned by [`LinkServer::accept_with_info()`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ConnectionInfo {
+    /// Address of the connecting client, if it could be determined.
+    pub peer_addr: Option<SocketAddr>,
+    /// Local address of the accepted connection, if it could be determined.
+    pub local_addr: Option<SocketAddr>,
+    /// Time at which the connection was accepted.
+    pub accepted_at: SystemTime,
+    /// Protocol used by the accepted link.
+    ///
+    /// This is currently always [`Protocol::TCPIP`].
+    pub protocol: Protocol,
 }
 
 /// An iterator that infinitely [`accept`]s connections on a [`LinkServer`].
@@ -40,9 +108,26 @@ impl LinkServer {
     ///
     /// let server = LinkServer::bind("127.0.0.1:8080").unwrap();
     /// ```
+    ///
+    /// Bind to an ephemeral port chosen by the operating system, and then use
+    /// [`LinkServer::port()`] and [`LinkServer::interface()`] to discover the actual
+    /// listening endpoint:
+    ///
+    /// ```
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use wstp::LinkServer;
+    ///
+    /// let server = LinkServer::bind("127.0.0.1:0").unwrap();
+    ///
+    /// assert_ne!(server.port(), 0);
+    /// assert_eq!(server.interface(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+    /// ```
     pub fn bind<A: std::net::ToSocketAddrs>(addrs: A) -> Result<Self, Error> {
         let addrs = addrs.to_socket_addrs().map_err(|err| {
-            Error::custom(format!("error binding LinkServer to address: {}", err))
+            Error::with_source(
+                format!("error binding LinkServer to address: {}", err),
+                err,
+            )
         })?;
 
         // Try each address, returning the first one which binds successfully.
@@ -52,9 +137,11 @@ impl LinkServer {
             let iface = CString::new(addr.ip().to_string())
                 .expect("failed to create CString from LinkServer interface");
 
+            let raw_env = crate::stdenv()?.raw_env;
+
             let raw_link_server: sys::WSLinkServer = unsafe {
                 sys::WSNewLinkServerWithPortAndInterface(
-                    crate::stdenv()?.raw_env,
+                    raw_env,
                     addr.port(),
                     iface.as_ptr(),
                     std::ptr::null_mut(),
@@ -63,10 +150,16 @@ impl LinkServer {
             };
 
             if raw_link_server.is_null() || err != sys::MLEOK {
-                return Err(Error::from_code(err));
+                return Err(Error::from_code_in(raw_env, err));
             }
 
-            return Ok(LinkServer { raw_link_server });
+            Ok(LinkServer {
+                raw_link_server,
+                raw_env,
+                callback: None,
+                is_shut_down: Arc::default(),
+                hooks: SharedHooks::default(),
+            })
         })
     }
 
@@ -80,24 +173,46 @@ impl LinkServer {
     pub fn new(port: u16) -> Result<Self, Error> {
         let mut err: std::os::raw::c_int = sys::MLEOK;
 
+        let raw_env = crate::stdenv()?.raw_env;
+
         let raw_server: sys::WSLinkServer = unsafe {
-            sys::WSNewLinkServerWithPort(
-                crate::stdenv()?.raw_env,
-                port,
-                std::ptr::null_mut(),
-                &mut err,
-            )
+            sys::WSNewLinkServerWithPort(raw_env, port, std::ptr::null_mut(), &mut err)
         };
 
         if raw_server.is_null() || err != sys::MLEOK {
-            return Err(Error::from_code(err));
+            return Err(Error::from_code_in(raw_env, err));
         }
 
         Ok(LinkServer {
             raw_link_server: raw_server,
+            raw_env,
+            callback: None,
+            is_shut_down: Arc::default(),
+            hooks: SharedHooks::default(),
         })
     }
 
+    /// Create a new link server which calls `callback` with each new [`Link`]
+    /// connection.
+    ///
+    /// `callback` is called on a background thread managed by WSTP. If `callback`
+    /// panics, the panic is caught, and the link server continues accepting
+    /// connections. `callback` is dropped when the returned link server is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::LinkServer;
+    ///
+    /// let server = LinkServer::new_with_callback(11235, |mut link| {
+    ///     link.put_str("hello").unwrap();
+    ///     link.flush().unwrap();
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// # `Send` bound
+    ///
     /// The callback is required to be [`Send`] so that it can be called from the link
     /// server's background thread, which accepts incoming connections.
     ///
@@ -132,17 +247,31 @@ impl LinkServer {
     {
         let mut err: std::os::raw::c_int = sys::MLEOK;
 
+        let raw_env = crate::stdenv()?.raw_env;
+
+        let is_shut_down = Arc::<AtomicBool>::default();
+        let hooks = SharedHooks::default();
+
+        let context = CallbackContext {
+            is_shut_down: Arc::clone(&is_shut_down),
+            hooks: Arc::clone(&hooks),
+            callback,
+        };
+
+        let callback = Callback {
+            ptr: Box::into_raw(Box::new(context)) as *mut std::ffi::c_void,
+            drop: drop_callback::<F>,
+        };
+
         let raw_server: sys::WSLinkServer = unsafe {
-            sys::WSNewLinkServerWithPort(
-                crate::stdenv()?.raw_env,
-                port,
-                Box::into_raw(Box::new(callback)) as *mut std::ffi::c_void,
-                &mut err,
-            )
+            sys::WSNewLinkServerWithPort(raw_env, port, callback.ptr, &mut err)
         };
 
         if raw_server.is_null() || err != sys::MLEOK {
-            return Err(Error::from_code(err));
+            // SAFETY: The link server was not created, so nothing else refers to the
+            //         callback closure.
+            unsafe { (callback.drop)(callback.ptr) };
+            return Err(Error::from_code_in(raw_env, err));
         }
 
         unsafe {
@@ -154,6 +283,10 @@ impl LinkServer {
 
         Ok(LinkServer {
             raw_link_server: raw_server,
+            raw_env,
+            callback: Some(callback),
+            is_shut_down,
+            hooks,
         })
     }
 
@@ -173,7 +306,7 @@ impl LinkServer {
             unsafe { sys::WSPortFromLinkServer(self.raw_link_server, &mut err) };
 
         if err != sys::MLEOK {
-            return Err(Error::from_code(err));
+            return Err(Error::from_code_in(self.raw_env, err));
         }
 
         Ok(port)
@@ -195,7 +328,7 @@ impl LinkServer {
             unsafe { sys::WSInterfaceFromLinkServer(self.raw_link_server, &mut err) };
 
         if iface_cstr.is_null() || err != sys::MLEOK {
-            return Err(Error::from_code(err));
+            return Err(Error::from_code_in(self.raw_env, err));
         }
 
         let iface: String = unsafe {
@@ -223,10 +356,13 @@ impl LinkServer {
 
         match std::net::IpAddr::from_str(iface.as_str()) {
             Ok(ip) => Ok(ip),
-            Err(err) => Err(Error::custom(format!(
-                "unable to parse LinkServer interface ({}) as IpAddr: {}",
-                iface, err
-            ))),
+            Err(err) => Err(Error::with_source(
+                format!(
+                    "unable to parse LinkServer interface ({}) as IpAddr: {}",
+                    iface, err
+                ),
+                err,
+            )),
         }
     }
 
@@ -240,16 +376,76 @@ impl LinkServer {
         // Note: The link server is closed when `self` is dropped.
     }
 
+    /// Stop accepting new connections on this link server.
+    ///
+    /// Any current or future call to [`LinkServer::accept()`] returns an error for which
+    /// [`Error::is_shutdown()`] returns `true`, and [`LinkServer::incoming()`] stops
+    /// returning new connections. A link server created using
+    /// [`LinkServer::new_with_callback()`] closes new connections without passing them
+    /// to its callback. The network port is released once this link server is dropped.
+    ///
+    /// Use [`LinkServer::shutdown_handle()`] to shut down a link server from a
+    /// different thread than the one accepting connections.
+    pub fn shutdown(&self) -> Result<(), Error> {
+        self.shutdown_handle()?.shutdown()
+    }
+
+    /// Returns `true` if [`LinkServer::shutdown()`] has been called.
+    pub fn is_shut_down(&self) -> bool {
+        self.is_shut_down.load(Ordering::SeqCst)
+    }
+
+    /// Get a [`ShutdownHandle`] that can be used to shut down this link server from
+    /// another thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::thread;
+    /// use wstp::LinkServer;
+    ///
+    /// let server = LinkServer::bind("127.0.0.1:0").unwrap();
+    /// let handle = server.shutdown_handle().unwrap();
+    ///
+    /// let shutdown_thread = thread::spawn(move || handle.shutdown().unwrap());
+    ///
+    /// // Blocks until the server is shut down.
+    /// let err = server.accept().unwrap_err();
+    /// assert!(err.is_shutdown());
+    ///
+    /// shutdown_thread.join().unwrap();
+    /// ```
+    pub fn shutdown_handle(&self) -> Result<ShutdownHandle, Error> {
+        let ip = match self.try_interface()? {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+
+        Ok(ShutdownHandle {
+            is_shut_down: Arc::clone(&self.is_shut_down),
+            addr: SocketAddr::new(ip, self.try_port()?),
+        })
+    }
+
     /// Accept a new incoming connection to this link server.
     ///
     /// This method blocks the current thread indefinitely until a connection is made to
-    /// the port this link server is bound to.
+    /// the port this link server is bound to, or until the link server is
+    /// [shut down][LinkServer::shutdown].
     ///
     /// Use [`LinkServer::new_with_callback()`] to create a link server which accepts
     /// connections asyncronously via a callback function.
     ///
+    /// Use [`LinkServer::accept_with_info()`] to also get the address of the connecting
+    /// client.
+    ///
     /// *WSTP C API Documentation:* [`WSWaitForNewLinkFromLinkServer`](https://reference.wolfram.com/language/ref/c/WSWaitForNewLinkFromLinkServer.html)
     pub fn accept(&self) -> Result<Link, Error> {
+        if self.is_shut_down() {
+            return Err(Error::link_server_shutdown());
+        }
+
         let mut err: c_int = sys::MLEOK;
 
         let raw_link = unsafe {
@@ -257,18 +453,81 @@ impl LinkServer {
         };
 
         if raw_link.is_null() || err != sys::MLEOK {
-            return Err(Error::from_code(err));
+            return Err(Error::from_code_in(self.raw_env, err));
         }
 
         let link = unsafe { Link::unchecked_new(raw_link) };
 
+        // The link server may have been shut down while we were waiting, in which case
+        // `link` may be the connection made to wake up this thread.
+        if self.is_shut_down() {
+            drop(link);
+            return Err(Error::link_server_shutdown());
+        }
+
+        lifecycle::accepted(&link, &self.hooks);
+
         Ok(link)
     }
 
+    /// Accept a new incoming connection to this link server, returning the new [`Link`]
+    /// along with information about the connection.
+    ///
+    /// See [`LinkServer::accept()`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::LinkServer;
+    ///
+    /// let server = LinkServer::bind("127.0.0.1:8080").unwrap();
+    ///
+    /// let (link, info) = server.accept_with_info().unwrap();
+    ///
+    /// println!("accepted connection from {:?}", info.peer_addr);
+    /// ```
+    #[doc(alias = "accept_with_addr")]
+    pub fn accept_with_info(&self) -> Result<(Link, ConnectionInfo), Error> {
+        let link = self.accept()?;
+
+        let info = ConnectionInfo {
+            peer_addr: link.peer_addr().ok(),
+            local_addr: link.local_addr().ok(),
+            accepted_at: SystemTime::now(),
+            protocol: Protocol::TCPIP,
+        };
+
+        Ok((link, info))
+    }
+
     /// Returns an iterator over the connections being received on this server.
     ///
-    /// The returned iterator will never return None. Iterating over it is equivalent to
-    /// calling [`LinkServer::accept`] in a loop.
+    /// Iterating over the returned iterator is equivalent to calling
+    /// [`LinkServer::accept`] in a loop. The iterator returns `None` once the link
+    /// server has been [shut down][LinkServer::shutdown].
+    ///
+    /// # Example
+    ///
+    /// Serve each client that connects, one at a time:
+    ///
+    /// ```no_run
+    /// use wstp::LinkServer;
+    ///
+    /// let server = LinkServer::bind("127.0.0.1:8080").unwrap();
+    ///
+    /// for link in server.incoming() {
+    ///     let mut link = match link {
+    ///         Ok(link) => link,
+    ///         Err(err) => {
+    ///             eprintln!("failed to accept connection: {}", err);
+    ///             continue;
+    ///         },
+    ///     };
+    ///
+    ///     link.put_str("hello").unwrap();
+    ///     link.flush().unwrap();
+    /// }
+    /// ```
     pub fn incoming(&self) -> Incoming {
         Incoming { server: self }
     }
@@ -286,32 +545,67 @@ extern "C" fn callback_trampoline<F: FnMut(Link) + Send + Sync + 'static>(
 ) {
     let mut err: std::os::raw::c_int = sys::MLEOK;
 
-    let user_closure: &mut F;
+    let context: &mut CallbackContext<F>;
     let link: Link;
 
     unsafe {
-        let raw_user_closure: *mut std::ffi::c_void =
+        let raw_context: *mut std::ffi::c_void =
             sys::WSContextFromLinkServer(raw_link_server, &mut err);
 
-        user_closure = &mut *(raw_user_closure as *mut F);
+        context = &mut *(raw_context as *mut CallbackContext<F>);
 
         // SAFETY: This is safe because `raw_link` is an entirely new link which we have
         //         ownership over.
         link = Link::unchecked_new(raw_link);
     }
 
-    // Call the closure provided by the user
-    // FIXME: Catch panic's in the user's code to prevent unwinding over C stack frames.
-    user_closure(link);
+    // Close connections received after the link server was shut down, including the
+    // connection made by `ShutdownHandle::shutdown()`.
+    if context.is_shut_down.load(Ordering::SeqCst) {
+        drop(link);
+        return;
+    }
+
+    let CallbackContext {
+        is_shut_down: _,
+        ref hooks,
+        callback: ref mut user_closure,
+    } = *context;
+
+    // Call the lifecycle callbacks and the closure provided by the user, catching any
+    // panics to prevent unwinding over C stack frames. The panic message has already
+    // been printed by the panic hook, so there is nothing more to do with the panic
+    // payload.
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        lifecycle::accepted(&link, hooks);
+
+        user_closure(link);
+    }));
+}
+
+unsafe fn drop_callback<F>(ptr: *mut std::ffi::c_void) {
+    drop(Box::from_raw(ptr as *mut CallbackContext<F>));
 }
 
 impl Drop for LinkServer {
     fn drop(&mut self) {
-        let LinkServer { raw_link_server } = *self;
+        let LinkServer {
+            raw_link_server,
+            raw_env: _,
+            ref mut callback,
+            is_shut_down: _,
+            hooks: _,
+        } = *self;
 
         unsafe {
             sys::WSShutdownLinkServer(raw_link_server);
         }
+
+        // Note: The callback must only be freed after the link server has been shut
+        //       down, so that it cannot be called again.
+        if let Some(Callback { ptr, drop }) = callback.take() {
+            unsafe { drop(ptr) };
+        }
     }
 }
 
@@ -330,6 +624,32 @@ impl<'a> Iterator for Incoming<'a> {
     type Item = Result<Link, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some(self.server.accept())
+        match self.server.accept() {
+            Err(err) if err.is_shutdown() => None,
+            result => Some(result),
+        }
+    }
+}
+
+impl ShutdownHandle {
+    /// Shut down the [`LinkServer`] this handle was created from.
+    ///
+    /// See [`LinkServer::shutdown()`].
+    pub fn shutdown(&self) -> Result<(), Error> {
+        if self.is_shut_down.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Wake up any thread blocked in `LinkServer::accept()` by opening a plain TCP
+        // connection to the link server. The accepting thread will see that the server
+        // has been shut down, and close the new link.
+        //
+        // Note: The connection is never activated as a WSTP link, so this cannot block
+        //       waiting for a handshake, even if no thread is currently accepting
+        //       connections. Failure to connect is not an error: it only means that no
+        //       thread could have been blocked waiting for a connection.
+        let _ = TcpStream::connect_timeout(&self.addr, SHUTDOWN_CONNECT_TIMEOUT);
+
+        Ok(())
     }
 }
@@ -0,0 +1,291 @@
+//! Per-link state stored by this crate.
+//!
+//! [`Link`][crate::Link] is a `#[repr(transparent)]` wrapper around a [`WSLINK`], so it
+//! cannot store any state itself. Instead, the state used to implement strict mode,
+//! close behavior, progress observers, message handlers, yield functions, wait
+//! callbacks, and lifecycle callbacks is stored in a single table keyed by the raw link.
+//! The state of a link is released when the link is closed, and moved when the
+//! connection of the link is replaced.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    close::CloseMode,
+    lifecycle::Lifecycle,
+    message::MessageHandler,
+    progress::ProgressObserver,
+    strict::PutState,
+    sys::{self, WSLINK},
+    yield_function::YieldFunction,
+    ForceSend,
+};
+
+/// State associated with a single link.
+#[derive(Default)]
+pub(crate) struct LinkState {
+    /// Put state of the link, if strict mode is enabled.
+    pub strict: Option<PutState>,
+    /// Close mode of the link, if it is not [`CloseMode::Abrupt`].
+    pub close_mode: Option<CloseMode>,
+    pub progress_observer: Option<Registered<(), ProgressObserver>>,
+    pub message_handler: Option<Registered<sys::WSMessageHandlerObject, MessageHandler>>,
+    pub yield_function: Option<Registered<sys::WSYieldFunctionObject, YieldFunction>>,
+    /// Closure passed to [`Link::wait_with_callback()`][crate::Link::wait_with_callback]
+    /// while it is waiting.
+    pub wait_callback: Option<*mut std::ffi::c_void>,
+    pub lifecycle: Option<Lifecycle>,
+}
+
+/// Callback object registered with WSTP on a link, and the closure it calls.
+///
+/// Closures that are only called by this crate, like progress observers, use `()` as
+/// the object.
+///
+/// The object remains registered until the link is closed. Replacing or clearing the
+/// closure does not change the registered object, so the object is never destroyed
+/// while WSTP may be calling it.
+pub(crate) struct Registered<O, F> {
+    pub object: O,
+    /// `None` if the closure was cleared, or is currently running.
+    closure: Option<F>,
+    /// Incremented each time `closure` is replaced or cleared.
+    generation: u64,
+}
+
+static LINK_STATES: Lazy<Mutex<ForceSend<HashMap<WSLINK, LinkState>>>> =
+    Lazy::new(|| Mutex::new(ForceSend(HashMap::new())));
+
+/// Number of entries in [`struct@LINK_STATES`].
+///
+/// Used to avoid acquiring the lock on [`struct@LINK_STATES`] for every put call when
+/// no links have any state.
+static LINK_STATE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Call `func` with the state of `raw_link`, creating empty state if there is none.
+///
+/// `func` is called while the table is locked, so it must not call user code, or drop
+/// user closures.
+pub(crate) fn with<T, F>(raw_link: WSLINK, func: F) -> T
+where
+    F: FnOnce(&mut LinkState) -> T,
+{
+    let mut lock = lock_states();
+    let states = &mut lock.0;
+
+    let state = states.entry(raw_link).or_insert_with(|| {
+        LINK_STATE_COUNT.fetch_add(1, Ordering::SeqCst);
+        LinkState::default()
+    });
+
+    let result = func(state);
+
+    if state.is_empty() {
+        states.remove(&raw_link);
+        LINK_STATE_COUNT.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    result
+}
+
+/// Call `func` with the state of `raw_link`, if there is any.
+///
+/// `func` is called while the table is locked, so it must not call user code, or drop
+/// user closures.
+pub(crate) fn with_existing<T, F>(raw_link: WSLINK, func: F) -> Option<T>
+where
+    F: FnOnce(&mut LinkState) -> T,
+{
+    if LINK_STATE_COUNT.load(Ordering::SeqCst) == 0 {
+        return None;
+    }
+
+    let mut lock = lock_states();
+    let states = &mut lock.0;
+
+    let state = states.get_mut(&raw_link)?;
+
+    let result = func(state);
+
+    if state.is_empty() {
+        states.remove(&raw_link);
+        LINK_STATE_COUNT.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    Some(result)
+}
+
+/// Release the state of `raw_link`, and close it.
+pub(crate) unsafe fn close(raw_link: WSLINK) {
+    let state = remove(raw_link);
+
+    sys::WSClose(raw_link);
+
+    // Note: Callback objects are only destroyed once the link is closed, so that WSTP
+    //       cannot call them any longer.
+    if let Some(state) = state {
+        state.destroy();
+    }
+}
+
+/// Move the state of `from` to `to`, when the connection `from` is replaced by `to`.
+///
+/// Any state previously stored for `to` is released.
+pub(crate) fn transfer(from: WSLINK, to: WSLINK) {
+    let discarded = remove(to);
+
+    let moved = remove(from).map(|mut state| {
+        // Strict mode stays enabled, but the put state is reset, because no data has
+        // been put on the new connection.
+        if let Some(ref mut strict) = state.strict {
+            *strict = PutState::default();
+        }
+
+        if let Some(ref handler) = state.message_handler {
+            unsafe {
+                sys::WSSetMessageHandler(from, None);
+                sys::WSSetMessageHandler(to, handler.object);
+            }
+        }
+
+        if let Some(ref function) = state.yield_function {
+            unsafe {
+                sys::WSSetYieldFunction(from, None);
+                sys::WSSetYieldFunction(to, function.object);
+            }
+        }
+
+        state
+    });
+
+    if let Some(state) = discarded {
+        unsafe {
+            state.unregister(to);
+            state.destroy();
+        }
+    }
+
+    if let Some(state) = moved {
+        let mut lock = lock_states();
+
+        lock.0.insert(to, state);
+        LINK_STATE_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn remove(raw_link: WSLINK) -> Option<LinkState> {
+    if LINK_STATE_COUNT.load(Ordering::SeqCst) == 0 {
+        return None;
+    }
+
+    let state = lock_states().0.remove(&raw_link);
+
+    if state.is_some() {
+        LINK_STATE_COUNT.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    state
+}
+
+impl LinkState {
+    fn is_empty(&self) -> bool {
+        let LinkState {
+            strict,
+            close_mode,
+            progress_observer,
+            message_handler,
+            yield_function,
+            wait_callback,
+            lifecycle,
+        } = self;
+
+        strict.is_none()
+            && close_mode.is_none()
+            && progress_observer.is_none()
+            && message_handler.is_none()
+            && yield_function.is_none()
+            && wait_callback.is_none()
+            && lifecycle.is_none()
+    }
+
+    /// Unregister the callback objects of this state from `raw_link`.
+    unsafe fn unregister(&self, raw_link: WSLINK) {
+        if self.message_handler.is_some() {
+            sys::WSSetMessageHandler(raw_link, None);
+        }
+
+        if self.yield_function.is_some() {
+            sys::WSSetYieldFunction(raw_link, None);
+        }
+    }
+
+    /// Destroy the callback objects of this state, which must no longer be registered
+    /// with any link.
+    unsafe fn destroy(self) {
+        if let Some(handler) = self.message_handler {
+            sys::WSDestroyMessageHandler(handler.object);
+        }
+
+        if let Some(function) = self.yield_function {
+            sys::WSDestroyYieldFunction(function.object);
+        }
+    }
+}
+
+impl<O, F> Registered<O, F> {
+    pub fn new(object: O, closure: F) -> Self {
+        Registered {
+            object,
+            closure: Some(closure),
+            generation: 0,
+        }
+    }
+
+    /// Replace the closure called by the registered object, returning the previous
+    /// closure.
+    ///
+    /// If the previous closure is currently running, it is dropped by the caller of
+    /// [`Registered::restore()`] instead.
+    pub fn replace(&mut self, closure: Option<F>) -> Option<F> {
+        self.generation += 1;
+
+        std::mem::replace(&mut self.closure, closure)
+    }
+
+    /// Take the closure out, so that it can be called without holding the lock on the
+    /// state table.
+    ///
+    /// Returns `None` if no closure is set, or if it is already running.
+    pub fn take(&mut self) -> Option<(F, u64)> {
+        let closure = self.closure.take()?;
+
+        Some((closure, self.generation))
+    }
+
+    /// Returns `true` if a closure is set and not currently running.
+    pub fn is_set(&self) -> bool {
+        self.closure.is_some()
+    }
+
+    /// Put back a closure returned by [`Registered::take()`], unless it was replaced or
+    /// cleared while it was running, in which case it is returned.
+    pub fn restore(&mut self, closure: F, generation: u64) -> Option<F> {
+        if self.generation != generation {
+            return Some(closure);
+        }
+
+        self.closure = Some(closure);
+
+        None
+    }
+}
+
+fn lock_states() -> MutexGuard<'static, ForceSend<HashMap<WSLINK, LinkState>>> {
+    // Note: Recover from poisoning; the table is still structurally valid.
+    LINK_STATES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,417 @@
+//! The [`wstp_put!`][crate::wstp_put] and [`wstp_service!`][crate::wstp_service]
+//! macros, and support code for the `#[derive(PutOnLink, GetFromLink)]` macros.
+//!
+//! This module is public only so that the macros can refer to its items. It is not part
+//! of the public API of this crate.
+
+use crate::{Error, GetFromLink, Link, PutOnLink, ServiceError};
+
+#[doc(hidden)]
+pub use wolfram_expr::Expr;
+
+/// Put an expression written in Wolfram Language-like syntax on a [`Link`].
+///
+/// The expression is written to the link directly using the token-level put methods
+/// (e.g. [`Link::put_function()`] and [`Link::put_i64()`]), without first constructing
+/// an [`Expr`][wolfram_expr::Expr].
+///
+/// The following syntax is supported:
+///
+/// * `Head[arg, ...]` — a function with a symbol head, put using
+///   [`Link::put_function()`].
+/// * ``"Context`Head"[arg, ...]`` — a function with a fully qualified symbol head.
+/// * `symbol` — a symbol, put using [`Link::put_symbol()`].
+/// * Integer, real, and string literals, e.g. `5`, `-2.5`, and `"string"`.
+/// * `(rust_expr)` — the value of a Rust expression of any type that implements
+///   [`PutOnLink`][crate::PutOnLink].
+///
+/// Bare identifiers are written as symbol names without a context, which the Wolfram
+/// Language resolves according to the current `$ContextPath`. Use a string literal
+/// head to specify a fully qualified symbol.
+///
+/// The macro evaluates to a `Result<(), wstp::Error>`.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{wstp_put, Link};
+/// use wolfram_expr::{Expr, Symbol};
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// let name = "three";
+///
+/// wstp_put!(&mut link, "System`List"[1, "two", (name), "Global`Foo"[-4]]).unwrap();
+///
+/// assert_eq!(
+///     link.get_expr().unwrap(),
+///     Expr::normal(Symbol::new("System`List"), vec![
+///         Expr::from(1),
+///         Expr::string("two"),
+///         Expr::string("three"),
+///         Expr::normal(Symbol::new("Global`Foo"), vec![Expr::from(-4)]),
+///     ])
+/// );
+/// ```
+#[macro_export]
+macro_rules! wstp_put {
+    ($link:expr, $($expr:tt)+) => {
+        $crate::macros::put_with($link, |link| {
+            $crate::__wstp_put_args!(link; $($expr)+);
+            ::std::result::Result::Ok(())
+        })
+    };
+}
+
+/// Put each of a comma-separated sequence of expressions on a link.
+///
+/// Each expression is a single token tree, optionally followed by a literal (for
+/// negative numbers) and a bracketed argument list. Matching the sequence using a
+/// repetition, instead of recursing once per expression, avoids reaching the
+/// `recursion_limit` for long argument lists.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __wstp_put_args {
+    ($link:ident; $($head:tt $($value:literal)? $([ $($args:tt)* ])?),* $(,)?) => {
+        $(
+            $crate::__wstp_put_expr!($link; $head $($value)? $([ $($args)* ])?);
+        )*
+    };
+}
+
+/// Put a single expression on a link.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __wstp_put_expr {
+    ($link:ident; $head:ident [ $($args:tt)* ]) => {
+        $link.put_function(
+            ::std::stringify!($head),
+            $crate::__wstp_count_args!($($args)*),
+        )?;
+        $crate::__wstp_put_args!($link; $($args)*);
+    };
+    ($link:ident; $head:literal [ $($args:tt)* ]) => {
+        $link.put_function($head, $crate::__wstp_count_args!($($args)*))?;
+        $crate::__wstp_put_args!($link; $($args)*);
+    };
+    ($link:ident; $symbol:ident) => {
+        $link.put_symbol(::std::stringify!($symbol))?;
+    };
+    // Note: The `literal` fragment also matches negative number literals, e.g. `-4`.
+    ($link:ident; $value:literal) => {
+        $crate::PutOnLink::put_on_link(&$value, $link)?;
+    };
+    ($link:ident; ( $value:expr )) => {
+        $crate::PutOnLink::put_on_link(&$value, $link)?;
+    };
+}
+
+/// Count the expressions in a comma-separated sequence of expressions.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __wstp_count_args {
+    ($($head:tt $($value:literal)? $([ $($args:tt)* ])?),* $(,)?) => {
+        <[&str]>::len(&[$(::std::stringify!($head)),*])
+    };
+}
+
+/// Call `func` with `link`.
+///
+/// Used by [`wstp_put!`][crate::wstp_put] to give the closure containing the put calls
+/// a concrete parameter type.
+#[doc(hidden)]
+pub fn put_with<F>(link: &mut Link, func: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut Link) -> Result<(), Error>,
+{
+    func(link)
+}
+
+//======================================
+// Derive support
+//======================================
+
+/// Check that the incoming expression is a function with head `head` and `count`
+/// arguments.
+#[doc(hidden)]
+pub fn expect_function(link: &mut Link, head: &str, count: usize) -> Result<(), Error> {
+    link.expect_normal_with_arg_count(head, count)
+}
+
+/// Read the head of a `key -> value` or `key :> value` rule, and its string key.
+#[doc(hidden)]
+pub fn get_rule_key(link: &mut Link) -> Result<String, Error> {
+    let length = link.expect_normal_with_head(&["System`Rule", "System`RuleDelayed"])?;
+
+    if length != 2 {
+        return Err(Error::custom(format!(
+            "expected rule with 2 arguments, got {} argument(s)",
+            length
+        )));
+    }
+
+    link.get_string()
+}
+
+#[doc(hidden)]
+pub fn unexpected_key(type_name: &str, key: &str) -> Error {
+    Error::custom(format!(
+        "unexpected or duplicate key \"{}\" in association for {}",
+        key, type_name
+    ))
+}
+
+#[doc(hidden)]
+pub fn missing_key(type_name: &str, key: &str) -> Error {
+    Error::custom(format!(
+        "missing key \"{}\" in association for {}",
+        key, type_name
+    ))
+}
+
+//======================================
+// Services
+//======================================
+
+/// Define a typed [`Service`][crate::Service], with a client type that calls it and a
+/// server type that dispatches requests to an implementation of it.
+///
+/// The macro takes a trait definition whose methods are written without a `self`
+/// parameter, followed by the names of the client and server types to generate:
+///
+/// * The trait is generated with a `&mut self` parameter added to each method, and each
+///   return type `T` changed to `Result<T, ServiceError>`.
+/// * The client type wraps an [`RpcLink`][crate::RpcLink], and has a method with the
+///   same signature as each trait method, returning `Result<T, wstp::Error>`.
+/// * The server type wraps an implementation of the trait, and implements
+///   [`Service`][crate::Service], so it can be passed to
+///   [`RpcLink::serve()`][crate::RpcLink::serve].
+///
+/// Argument types must implement [`PutOnLink`] and [`GetFromLink`], as must return
+/// types. See [`Service`][crate::Service] for the expressions requests and responses
+/// are written as.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{wstp_service, Link, Protocol, RpcLink, ServiceError};
+///
+/// wstp_service! {
+///     /// Arithmetic service.
+///     pub trait Calculator {
+///         fn add(a: i64, b: i64) -> i64;
+///         fn div(a: i64, b: i64) -> i64;
+///     }
+///
+///     /// Client of a [`Calculator`] service.
+///     pub struct CalculatorClient;
+///
+///     /// Server of a [`Calculator`] service.
+///     pub struct CalculatorServer;
+/// }
+///
+/// struct Calc;
+///
+/// impl Calculator for Calc {
+///     fn add(&mut self, a: i64, b: i64) -> Result<i64, ServiceError> {
+///         Ok(a + b)
+///     }
+///
+///     fn div(&mut self, a: i64, b: i64) -> Result<i64, ServiceError> {
+///         a.checked_div(b).ok_or_else(|| ServiceError::new("division by zero"))
+///     }
+/// }
+///
+/// let mut listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+/// let name = listener.link_name();
+///
+/// std::thread::spawn(move || {
+///     listener.activate()?;
+///     RpcLink::new(listener).serve(&mut CalculatorServer(Calc))
+/// });
+///
+/// let mut link = Link::connect(Protocol::IntraProcess, &name).unwrap();
+/// link.activate().unwrap();
+///
+/// let mut client = CalculatorClient::new(link);
+///
+/// assert_eq!(client.add(1, 2).unwrap(), 3);
+/// assert!(client.div(1, 0).is_err());
+/// ```
+#[macro_export]
+macro_rules! wstp_service {
+    (
+        $(#[$attr:meta])*
+        $vis:vis trait $name:ident {
+            $(
+                $(#[$method_attr:meta])*
+                fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> $ret:ty;
+            )*
+        }
+
+        $(#[$client_attr:meta])*
+        $client_vis:vis struct $client:ident;
+
+        $(#[$server_attr:meta])*
+        $server_vis:vis struct $server:ident;
+    ) => {
+        $(#[$attr])*
+        $vis trait $name {
+            $(
+                $(#[$method_attr])*
+                fn $method(
+                    &mut self,
+                    $($arg: $arg_ty),*
+                ) -> ::std::result::Result<$ret, $crate::ServiceError>;
+            )*
+        }
+
+        $(#[$client_attr])*
+        #[derive(Debug)]
+        $client_vis struct $client {
+            rpc: $crate::RpcLink,
+        }
+
+        impl $client {
+            /// Construct a client that calls the service served at the other end of
+            /// `link`.
+            pub fn new(link: $crate::Link) -> Self {
+                $client {
+                    rpc: $crate::RpcLink::new(link),
+                }
+            }
+
+            /// Convert this value into the underlying `RpcLink`.
+            pub fn into_inner(self) -> $crate::RpcLink {
+                self.rpc
+            }
+
+            $(
+                $(#[$method_attr])*
+                pub fn $method(
+                    &mut self,
+                    $($arg: $arg_ty),*
+                ) -> ::std::result::Result<$ret, $crate::Error> {
+                    let args = ::std::vec![$($crate::macros::to_expr(&$arg)?),*];
+
+                    let response =
+                        self.rpc.call_method(::std::stringify!($method), args)?;
+
+                    $crate::macros::from_expr(&response)
+                }
+            )*
+        }
+
+        $(#[$server_attr])*
+        $server_vis struct $server<S>(pub S);
+
+        impl<S: $name> $crate::Service for $server<S> {
+            fn call(
+                &mut self,
+                method: &str,
+                args: ::std::vec::Vec<$crate::macros::Expr>,
+            ) -> ::std::result::Result<$crate::macros::Expr, $crate::ServiceError> {
+                match method {
+                    $(
+                        ::std::stringify!($method) => {
+                            let mut args = $crate::macros::ServiceArgs::new(method, args);
+
+                            $(
+                                let $arg: $arg_ty = args.next_arg()?;
+                            )*
+
+                            args.finish()?;
+
+                            let value = self.0.$method($($arg),*)?;
+
+                            ::std::result::Result::Ok($crate::macros::to_expr(&value)?)
+                        },
+                    )*
+                    _ => ::std::result::Result::Err(
+                        $crate::macros::unknown_method(method),
+                    ),
+                }
+            }
+        }
+    };
+}
+
+/// Convert `value` to the expression it is written to a link as.
+#[doc(hidden)]
+pub fn to_expr<T: PutOnLink + ?Sized>(value: &T) -> Result<Expr, Error> {
+    let mut link = Link::new_loopback()?;
+
+    link.put(value)?;
+
+    link.get_expr()
+}
+
+/// Read a value of type `T` from `expr`.
+#[doc(hidden)]
+pub fn from_expr<T: GetFromLink>(expr: &Expr) -> Result<T, Error> {
+    let mut link = Link::new_loopback()?;
+
+    link.put_expr(expr)?;
+
+    link.get()
+}
+
+/// Arguments of a service request, converted to the parameter types of a method one at
+/// a time.
+#[doc(hidden)]
+pub struct ServiceArgs {
+    method: String,
+    args: std::vec::IntoIter<Expr>,
+    index: usize,
+}
+
+impl ServiceArgs {
+    #[doc(hidden)]
+    pub fn new(method: &str, args: Vec<Expr>) -> Self {
+        ServiceArgs {
+            method: method.to_owned(),
+            args: args.into_iter(),
+            index: 0,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn next_arg<T: GetFromLink>(&mut self) -> Result<T, ServiceError> {
+        let arg = self.args.next().ok_or_else(|| {
+            ServiceError::new(format!(
+                "method {} called with {} arguments; more are expected",
+                self.method, self.index
+            ))
+        })?;
+
+        self.index += 1;
+
+        from_expr(&arg).map_err(|err| {
+            ServiceError::new(format!(
+                "invalid argument {} for method {}: {}",
+                self.index, self.method, err
+            ))
+        })
+    }
+
+    #[doc(hidden)]
+    pub fn finish(self) -> Result<(), ServiceError> {
+        let extra = self.args.len();
+
+        if extra != 0 {
+            return Err(ServiceError::new(format!(
+                "method {} called with {} arguments; expected {}",
+                self.method,
+                self.index + extra,
+                self.index
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn unknown_method(method: &str) -> ServiceError {
+    ServiceError::new(format!("unknown method: {}", method))
+}
@@ -0,0 +1,335 @@
+//! MathLink-compatible names for the `wstp` API.
+//!
+//! WSTP is the successor to MathLink, and the WSTP C API is a renaming of the older
+//! MathLink C API (`MLPutInteger64()` became `WSPutInteger64()`, `MLINK` became
+//! `WSLINK`, etc.). This module provides the historical `ML*` names as thin aliases
+//! over the safe [`Link`] API, to make mechanical ports of legacy MathLink C code to
+//! Rust easier.
+//!
+//! New code should prefer calling the [`Link`] methods directly.
+//!
+//! # Example
+//!
+//! ```
+//! use wstp::mathlink::*;
+//!
+//! # fn example() -> Result<(), wstp::Error> {
+//! let mut link: MLINK = MLLoopbackOpen()?;
+//!
+//! MLPutFunction(&mut link, "System`List", 2)?;
+//! MLPutInteger64(&mut link, 1)?;
+//! MLPutInteger64(&mut link, 2)?;
+//!
+//! assert_eq!(MLTestHead(&mut link, "System`List")?, 2);
+//! assert_eq!(MLGetInteger64(&mut link)?, 1);
+//! assert_eq!(MLGetInteger64(&mut link)?, 2);
+//! # Ok(())
+//! # }
+//! # example().unwrap();
+//! ```
+//!
+//! # Differences from the C API
+//!
+//! * Functions return a [`Result`] instead of a `0`/non-zero success code and an error
+//!   code that must be retrieved separately using `MLError()`.
+//! * Getters return the value read instead of writing through an out-parameter.
+//! * Links are closed when the [`MLINK`] value is dropped; [`MLClose()`] is provided for
+//!   symmetry.
+
+#![allow(non_snake_case, non_camel_case_types)]
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link, LinkServer, LinkStr};
+
+#[doc(no_inline)]
+pub use crate::sys::{
+    MLEABORT, MLECLOSED, MLECONNECT, MLEDEAD, MLEGBAD, MLEGSEQ, MLEMEM, MLENAMETAKEN,
+    MLEOK, MLEOVFL, MLEPBIG, MLEPBTK, MLEPSEQ, MLEUNKNOWN,
+};
+
+//======================================
+// Types
+//======================================
+
+/// Alias for [`Link`].
+///
+/// *MathLink C API Documentation:* [`MLINK`](https://reference.wolfram.com/language/ref/c/MLINK.html)
+pub type MLINK = Link;
+
+/// Alias for [`LinkServer`].
+pub type MLLinkServer = LinkServer;
+
+//======================================
+// Token type constants
+//======================================
+
+/// Alias for [`WSTKFUNC`][crate::sys::WSTKFUNC].
+pub const MLTKFUNC: i32 = crate::sys::WSTKFUNC as i32;
+/// Alias for [`WSTKSTR`][crate::sys::WSTKSTR].
+pub const MLTKSTR: i32 = crate::sys::WSTKSTR as i32;
+/// Alias for [`WSTKSYM`][crate::sys::WSTKSYM].
+pub const MLTKSYM: i32 = crate::sys::WSTKSYM as i32;
+/// Alias for [`WSTKREAL`][crate::sys::WSTKREAL].
+pub const MLTKREAL: i32 = crate::sys::WSTKREAL as i32;
+/// Alias for [`WSTKINT`][crate::sys::WSTKINT].
+pub const MLTKINT: i32 = crate::sys::WSTKINT as i32;
+/// Alias for [`WSTKERR`][crate::sys::WSTKERR].
+pub const MLTKERR: i32 = crate::sys::WSTKERR;
+
+//======================================
+// Creating links
+//======================================
+
+/// Alias for [`Link::new_loopback()`].
+pub fn MLLoopbackOpen() -> Result<MLINK, Error> {
+    Link::new_loopback()
+}
+
+/// Alias for [`Link::open_with_args()`].
+pub fn MLOpenArgcArgv(args: &[&str]) -> Result<MLINK, Error> {
+    Link::open_with_args(args)
+}
+
+/// Alias for [`Link::activate()`].
+pub fn MLActivate(link: &mut MLINK) -> Result<(), Error> {
+    link.activate()
+}
+
+/// Alias for [`Link::close()`].
+pub fn MLClose(link: MLINK) {
+    link.close()
+}
+
+//======================================
+// Link properties
+//======================================
+
+/// Alias for [`Link::link_name()`].
+pub fn MLName(link: &MLINK) -> String {
+    link.link_name()
+}
+
+/// Alias for [`Link::is_ready()`].
+pub fn MLReady(link: &MLINK) -> bool {
+    link.is_ready()
+}
+
+/// Alias for [`Link::error()`].
+///
+/// Returns the error code of the last error to occur on `link`, or [`MLEOK`] if no
+/// error has occurred.
+pub fn MLError(link: &MLINK) -> i32 {
+    link.error().and_then(|err| err.code()).unwrap_or(MLEOK)
+}
+
+/// Alias for [`Link::error_message()`].
+pub fn MLErrorMessage(link: &MLINK) -> Option<String> {
+    link.error_message()
+}
+
+/// Alias for [`Link::clear_error()`].
+pub fn MLClearError(link: &mut MLINK) {
+    link.clear_error()
+}
+
+//======================================
+// Packets and flow control
+//======================================
+
+/// Alias for [`Link::flush()`].
+pub fn MLFlush(link: &mut MLINK) -> Result<(), Error> {
+    link.flush()
+}
+
+/// Alias for [`Link::wait()`].
+pub fn MLWaitForLinkActivity(link: &mut MLINK) -> Result<(), Error> {
+    link.wait()
+}
+
+/// Alias for [`Link::raw_next_packet()`].
+pub fn MLNextPacket(link: &mut MLINK) -> Result<i32, Error> {
+    link.raw_next_packet()
+}
+
+/// Alias for [`Link::new_packet()`].
+pub fn MLNewPacket(link: &mut MLINK) -> Result<(), Error> {
+    link.new_packet()
+}
+
+/// Alias for [`Link::end_packet()`].
+pub fn MLEndPacket(link: &mut MLINK) -> Result<(), Error> {
+    link.end_packet()
+}
+
+/// Alias for [`Link::transfer_expr_to()`].
+///
+/// Note that, as in the C API, the destination link is the first argument.
+pub fn MLTransferExpression(dest: &mut MLINK, src: &mut MLINK) -> Result<(), Error> {
+    src.transfer_expr_to(dest)
+}
+
+/// Alias for [`Link::transfer_to_end_of_loopback_link()`].
+///
+/// Note that, as in the C API, the destination link is the first argument.
+pub fn MLTransferToEndOfLoopbackLink(
+    dest: &mut MLINK,
+    src: &mut MLINK,
+) -> Result<(), Error> {
+    src.transfer_to_end_of_loopback_link(dest)
+}
+
+//======================================
+// Getting data
+//======================================
+
+/// Alias for [`Link::raw_get_next()`].
+pub fn MLGetNext(link: &mut MLINK) -> Result<i32, Error> {
+    link.raw_get_next()
+}
+
+/// Alias for [`Link::get_raw_type()`].
+pub fn MLGetType(link: &MLINK) -> Result<i32, Error> {
+    link.get_raw_type()
+}
+
+/// Alias for [`Link::get_arg_count()`].
+pub fn MLGetArgCount(link: &mut MLINK) -> Result<usize, Error> {
+    link.get_arg_count()
+}
+
+/// Alias for [`Link::test_head()`].
+pub fn MLTestHead(link: &mut MLINK, symbol: &str) -> Result<usize, Error> {
+    link.test_head(symbol)
+}
+
+/// Alias for [`Link::get_string_ref()`].
+pub fn MLGetUTF8String(link: &mut MLINK) -> Result<LinkStr, Error> {
+    link.get_string_ref()
+}
+
+/// Alias for [`Link::get_symbol_ref()`].
+pub fn MLGetUTF8Symbol(link: &mut MLINK) -> Result<LinkStr, Error> {
+    link.get_symbol_ref()
+}
+
+/// Alias for [`Link::get_i64()`].
+pub fn MLGetInteger64(link: &mut MLINK) -> Result<i64, Error> {
+    link.get_i64()
+}
+
+/// Alias for [`Link::get_i32()`].
+pub fn MLGetInteger32(link: &mut MLINK) -> Result<i32, Error> {
+    link.get_i32()
+}
+
+/// Alias for [`Link::get_i16()`].
+pub fn MLGetInteger16(link: &mut MLINK) -> Result<i16, Error> {
+    link.get_i16()
+}
+
+/// Alias for [`Link::get_u8()`].
+pub fn MLGetInteger8(link: &mut MLINK) -> Result<u8, Error> {
+    link.get_u8()
+}
+
+/// Alias for [`Link::get_f64()`].
+pub fn MLGetReal64(link: &mut MLINK) -> Result<f64, Error> {
+    link.get_f64()
+}
+
+/// Alias for [`Link::get_f32()`].
+pub fn MLGetReal32(link: &mut MLINK) -> Result<f32, Error> {
+    link.get_f32()
+}
+
+/// Alias for [`Link::get_expr()`].
+///
+/// This function has no direct MathLink C API equivalent.
+pub fn MLGetExpr(link: &mut MLINK) -> Result<Expr, Error> {
+    link.get_expr()
+}
+
+//======================================
+// Putting data
+//======================================
+
+/// Alias for [`Link::put_raw_type()`].
+pub fn MLPutType(link: &mut MLINK, type_: i32) -> Result<(), Error> {
+    link.put_raw_type(type_)
+}
+
+/// Alias for [`Link::put_function()`].
+pub fn MLPutFunction(link: &mut MLINK, head: &str, count: usize) -> Result<(), Error> {
+    link.put_function(head, count)
+}
+
+/// Alias for [`Link::put_arg_count()`].
+pub fn MLPutArgCount(link: &mut MLINK, count: usize) -> Result<(), Error> {
+    link.put_arg_count(count)
+}
+
+/// Alias for [`Link::put_str()`].
+pub fn MLPutUTF8String(link: &mut MLINK, string: &str) -> Result<(), Error> {
+    link.put_str(string)
+}
+
+/// Alias for [`Link::put_symbol()`].
+pub fn MLPutUTF8Symbol(link: &mut MLINK, symbol: &str) -> Result<(), Error> {
+    link.put_symbol(symbol)
+}
+
+/// Alias for [`Link::put_i64()`].
+pub fn MLPutInteger64(link: &mut MLINK, value: i64) -> Result<(), Error> {
+    link.put_i64(value)
+}
+
+/// Alias for [`Link::put_i32()`].
+pub fn MLPutInteger32(link: &mut MLINK, value: i32) -> Result<(), Error> {
+    link.put_i32(value)
+}
+
+/// Alias for [`Link::put_i16()`].
+pub fn MLPutInteger16(link: &mut MLINK, value: i16) -> Result<(), Error> {
+    link.put_i16(value)
+}
+
+/// Alias for [`Link::put_u8()`].
+pub fn MLPutInteger8(link: &mut MLINK, value: u8) -> Result<(), Error> {
+    link.put_u8(value)
+}
+
+/// Alias for [`Link::put_f64()`].
+pub fn MLPutReal64(link: &mut MLINK, value: f64) -> Result<(), Error> {
+    link.put_f64(value)
+}
+
+/// Alias for [`Link::put_f32()`].
+pub fn MLPutReal32(link: &mut MLINK, value: f32) -> Result<(), Error> {
+    link.put_f32(value)
+}
+
+/// Alias for [`Link::put_i64_array()`].
+pub fn MLPutInteger64Array(
+    link: &mut MLINK,
+    data: &[i64],
+    dimensions: &[usize],
+) -> Result<(), Error> {
+    link.put_i64_array(data, dimensions)
+}
+
+/// Alias for [`Link::put_f64_array()`].
+pub fn MLPutReal64Array(
+    link: &mut MLINK,
+    data: &[f64],
+    dimensions: &[usize],
+) -> Result<(), Error> {
+    link.put_f64_array(data, dimensions)
+}
+
+/// Alias for [`Link::put_expr()`].
+///
+/// This function has no direct MathLink C API equivalent.
+pub fn MLPutExpr(link: &mut MLINK, expr: &Expr) -> Result<(), Error> {
+    link.put_expr(expr)
+}
@@ -0,0 +1,229 @@
+//! Out-of-band urgent messages sent between link end points.
+
+use std::os::raw::{c_int, c_uint};
+
+use crate::{
+    link_state::{self, Registered},
+    stdenv,
+    sys::{self, WSLINK},
+    Error, Link,
+};
+
+pub(crate) type MessageHandler = Box<dyn FnMut(UrgentMessage) + Send + 'static>;
+
+/// Out-of-band message sent between link end points.
+///
+/// Urgent messages are delivered separately from the expressions sent on a link, and
+/// are typically used to interrupt or abort a computation in progress in the Wolfram
+/// Kernel.
+///
+/// *WSTP C API Documentation:* [`WSMessageHandlerObject`](https://reference.wolfram.com/language/ref/c/WSMessageHandlerObject.html)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UrgentMessage {
+    /// Request that the receiving program terminate.
+    Terminate,
+    /// Request that the current computation be interrupted.
+    Interrupt,
+    /// Request that the current computation be aborted.
+    Abort,
+    /// The link is about to be closed by the other side.
+    LinkClosing,
+    /// The other side of the link is about to exit.
+    ImDying,
+    /// Any other message, identified by its raw message code.
+    Other(i32),
+}
+
+impl UrgentMessage {
+    /// Construct an [`UrgentMessage`] from a raw WSTP message code, e.g.
+    /// [`sys::WSAbortMessage`].
+    pub fn from_raw(code: i32) -> Self {
+        match u32::try_from(code) {
+            Ok(sys::WSTerminateMessage) => UrgentMessage::Terminate,
+            Ok(sys::WSInterruptMessage) => UrgentMessage::Interrupt,
+            Ok(sys::WSAbortMessage) => UrgentMessage::Abort,
+            Ok(sys::WSLinkClosingMessage) => UrgentMessage::LinkClosing,
+            Ok(sys::WSImDyingMessage) => UrgentMessage::ImDying,
+            _ => UrgentMessage::Other(code),
+        }
+    }
+
+    /// Get the raw WSTP message code of this message.
+    pub fn as_raw(self) -> i32 {
+        let code: c_uint = match self {
+            UrgentMessage::Terminate => sys::WSTerminateMessage,
+            UrgentMessage::Interrupt => sys::WSInterruptMessage,
+            UrgentMessage::Abort => sys::WSAbortMessage,
+            UrgentMessage::LinkClosing => sys::WSLinkClosingMessage,
+            UrgentMessage::ImDying => sys::WSImDyingMessage,
+            UrgentMessage::Other(code) => return code,
+        };
+
+        code as i32
+    }
+}
+
+/// # Urgent messages
+impl Link {
+    /// Send an urgent message to the other side of this link.
+    ///
+    /// Urgent messages are delivered immediately, ahead of any expressions waiting to be
+    /// read by the other side.
+    ///
+    /// # Example
+    ///
+    /// Interrupt the evaluation in progress in a Wolfram Kernel:
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use wstp::{Link, UrgentMessage};
+    ///
+    /// let kernel = Path::new(
+    ///     "/Applications/Mathematica.app/Contents/MacOS/WolframKernel"
+    /// );
+    ///
+    /// let mut link = Link::launch(kernel).unwrap();
+    ///
+    /// link.put_message(UrgentMessage::Interrupt).unwrap();
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutMessage()`](https://reference.wolfram.com/language/ref/c/WSPutMessage.html)
+    pub fn put_message(&mut self, message: UrgentMessage) -> Result<(), Error> {
+        if unsafe { sys::WSPutMessage(self.raw_link, message.as_raw()) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
+    /// Register a function that is called when an urgent message arrives on this link.
+    ///
+    /// `handler` may be called from any thread, while a WSTP function is running on
+    /// this link. It should return quickly, and must not call WSTP functions on this
+    /// link. A typical handler records the message (e.g. by setting an atomic flag),
+    /// which is then acted on by the code that owns the link.
+    ///
+    /// `handler` is dropped when it is replaced, when it is unregistered using
+    /// [`Link::clear_message_handler()`], or when this link is closed. If `handler` is
+    /// running when it is replaced or unregistered, it is dropped once it returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+    /// use wstp::{Link, UrgentMessage};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let aborted = Arc::new(AtomicBool::new(false));
+    ///
+    /// link.set_message_handler({
+    ///     let aborted = Arc::clone(&aborted);
+    ///     move |message: UrgentMessage| {
+    ///         if message == UrgentMessage::Abort {
+    ///             aborted.store(true, Ordering::SeqCst);
+    ///         }
+    ///     }
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSSetMessageHandler()`](https://reference.wolfram.com/language/ref/c/WSSetMessageHandler.html)
+    pub fn set_message_handler<F>(&mut self, handler: F) -> Result<(), Error>
+    where
+        F: FnMut(UrgentMessage) + Send + 'static,
+    {
+        let raw_link = self.raw_link;
+        let handler: MessageHandler = Box::new(handler);
+
+        // Reuse the message handler object already registered with this link, if any.
+        let result = link_state::with(raw_link, |state| match state.message_handler {
+            Some(ref mut registered) => Ok(registered.replace(Some(handler))),
+            None => Err(handler),
+        });
+
+        let handler = match result {
+            Ok(previous) => {
+                drop(previous);
+                return Ok(());
+            },
+            Err(handler) => handler,
+        };
+
+        let object = unsafe {
+            sys::WSCreateMessageHandler(
+                stdenv()?.raw_env,
+                Some(message_handler_trampoline),
+                std::ptr::null_mut(),
+            )
+        };
+
+        if object.is_none() {
+            return Err(Error::custom(
+                "WSCreateMessageHandler() failed to create message handler".to_owned(),
+            ));
+        }
+
+        // Note: The handler is stored before the object is registered with WSTP, so
+        //       that the handler can be found as soon as WSTP calls the object.
+        link_state::with(raw_link, |state| {
+            state.message_handler = Some(Registered::new(object, handler));
+        });
+
+        if unsafe { sys::WSSetMessageHandler(raw_link, object) } == 0 {
+            let registered =
+                link_state::with(raw_link, |state| state.message_handler.take());
+            drop(registered);
+            unsafe { sys::WSDestroyMessageHandler(object) };
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
+    /// Unregister the function set by [`Link::set_message_handler()`].
+    ///
+    /// Returns `true` if a message handler was registered.
+    pub fn clear_message_handler(&mut self) -> bool {
+        let previous = link_state::with_existing(self.raw_link, |state| {
+            state.message_handler.as_mut()?.replace(None)
+        })
+        .flatten();
+
+        previous.is_some()
+    }
+}
+
+unsafe extern "C" fn message_handler_trampoline(
+    raw_link: WSLINK,
+    message: c_int,
+    _arg: c_int,
+) {
+    // Catch any panics which result from `handler()` to prevent unwinding over C stack
+    // frames.
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // Note: Take the handler out of the table while it runs, so that the table is
+        //       not locked while user code is running.
+        let (mut handler, generation) =
+            match link_state::with_existing(raw_link, |state| {
+                state.message_handler.as_mut()?.take()
+            }) {
+                Some(Some(taken)) => taken,
+                // No handler is set, or the handler was called re-entrantly.
+                _ => return,
+            };
+
+        handler(UrgentMessage::from_raw(message));
+
+        // Put the handler back, unless it was replaced or unregistered while it was
+        // running.
+        let replaced =
+            link_state::with_existing(raw_link, |state| match state.message_handler {
+                Some(ref mut registered) => registered.restore(handler, generation),
+                None => Some(handler),
+            });
+
+        drop(replaced);
+    }));
+}
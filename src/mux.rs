@@ -0,0 +1,201 @@
+//! Multiplexing several independent channels of expressions over a single [`Link`].
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+/// Link wrapper that multiplexes several independent channels of expressions over a
+/// single underlying [`Link`].
+///
+/// Each expression sent using [`MuxLink::send()`] is wrapped in a frame that identifies
+/// the channel it belongs to. Frames are written to the link as the expression:
+///
+/// ```text
+/// {channel, expr}
+/// ```
+///
+/// where `channel` is a non-negative integer. This makes it straightforward to
+/// implement the other end of a multiplexed link in the Wolfram Language.
+///
+/// When [`MuxLink::recv()`] reads a frame belonging to a different channel than the one
+/// requested, the frame is queued until that channel is read from. Queued frames are
+/// kept in the order they arrived in, and [`MuxLink::recv_any()`] returns them in that
+/// order, so no channel can be starved by another.
+///
+/// At most [`MuxLink::max_pending()`] frames are queued. Once the queue is full,
+/// [`MuxLink::recv()`] returns an error instead of reading further frames from the link,
+/// until queued frames are received using [`MuxLink::recv_any()`] or
+/// [`MuxLink::try_recv_pending()`]. Because unread frames stay on the link, this pushes
+/// back on the sender instead of buffering without limit.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, MuxLink};
+/// use wolfram_expr::Expr;
+///
+/// let mut link = MuxLink::new(Link::new_loopback().unwrap());
+///
+/// link.send(1, &Expr::from(1)).unwrap();
+/// link.send(2, &Expr::string("bulk data")).unwrap();
+/// link.send(1, &Expr::from(2)).unwrap();
+///
+/// // Read channel 1 first; the frame for channel 2 is queued.
+/// assert_eq!(link.recv(1).unwrap(), Expr::from(1));
+/// assert_eq!(link.recv(1).unwrap(), Expr::from(2));
+///
+/// assert_eq!(link.recv(2).unwrap(), Expr::string("bulk data"));
+/// ```
+#[derive(Debug)]
+pub struct MuxLink {
+    link: Link,
+    /// Frames that have been read from `link`, but not yet received by the caller, in
+    /// the order they were read.
+    pending: VecDeque<(u32, Expr)>,
+    max_pending: usize,
+}
+
+impl MuxLink {
+    /// Default value of [`MuxLink::max_pending()`].
+    pub const DEFAULT_MAX_PENDING: usize = 1024;
+
+    /// Construct a new [`MuxLink`] that sends and receives frames on `link`.
+    ///
+    /// At most [`MuxLink::DEFAULT_MAX_PENDING`] frames will be queued.
+    pub fn new(link: Link) -> Self {
+        MuxLink::with_max_pending(link, MuxLink::DEFAULT_MAX_PENDING)
+    }
+
+    /// Construct a new [`MuxLink`] that queues at most `max_pending` frames belonging to
+    /// channels other than the one being read from.
+    pub fn with_max_pending(link: Link, max_pending: usize) -> Self {
+        MuxLink {
+            link,
+            pending: VecDeque::new(),
+            max_pending,
+        }
+    }
+
+    /// Maximum number of frames that may be queued.
+    pub fn max_pending(&self) -> usize {
+        self.max_pending
+    }
+
+    /// Number of frames that have been read from the link but not yet received.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Get the underlying [`Link`].
+    pub fn link(&mut self) -> &mut Link {
+        &mut self.link
+    }
+
+    /// Convert this value into the underlying [`Link`].
+    ///
+    /// Any frames that have been read from the link but not yet received are
+    /// discarded.
+    pub fn into_inner(self) -> Link {
+        self.link
+    }
+
+    /// Send `expr` on `channel`.
+    ///
+    /// The link is flushed after the frame is written.
+    pub fn send(&mut self, channel: u32, expr: &Expr) -> Result<(), Error> {
+        let link = &mut self.link;
+
+        link.put_function("System`List", 2)?;
+        link.put_i64(i64::from(channel))?;
+        link.put_expr(expr)?;
+
+        link.flush()
+    }
+
+    /// Receive the next expression sent on `channel`.
+    ///
+    /// This will block until a frame for `channel` is available. Frames read from the
+    /// link that belong to other channels are queued.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error without reading from the link if a frame for another channel
+    /// would need to be read while [`MuxLink::max_pending()`] frames are already
+    /// queued.
+    pub fn recv(&mut self, channel: u32) -> Result<Expr, Error> {
+        if let Some(expr) = self.try_recv_pending(channel) {
+            return Ok(expr);
+        }
+
+        loop {
+            if self.pending.len() >= self.max_pending {
+                return Err(Error::custom(format!(
+                    "MuxLink: {} frames are queued for other channels; receive them \
+                     before reading from channel {}",
+                    self.pending.len(),
+                    channel
+                )));
+            }
+
+            let (frame_channel, expr) = self.read_frame()?;
+
+            if frame_channel == channel {
+                return Ok(expr);
+            }
+
+            self.pending.push_back((frame_channel, expr));
+        }
+    }
+
+    /// Receive the next expression, from any channel.
+    ///
+    /// Queued frames are returned in the order they were read from the link, before new
+    /// frames are read from the link.
+    pub fn recv_any(&mut self) -> Result<(u32, Expr), Error> {
+        if let Some(frame) = self.pending.pop_front() {
+            return Ok(frame);
+        }
+
+        self.read_frame()
+    }
+
+    /// Receive an expression sent on `channel` that has already been read from the link,
+    /// if there is one.
+    ///
+    /// This never blocks.
+    pub fn try_recv_pending(&mut self, channel: u32) -> Option<Expr> {
+        let index = self
+            .pending
+            .iter()
+            .position(|(frame_channel, _)| *frame_channel == channel)?;
+
+        let (_, expr) = self.pending.remove(index)?;
+
+        Some(expr)
+    }
+
+    fn read_frame(&mut self) -> Result<(u32, Expr), Error> {
+        let link = &mut self.link;
+
+        let length = link.test_head("System`List")?;
+
+        if length != 2 {
+            return Err(Error::custom(format!(
+                "MuxLink: expected frame with 2 elements, got {}",
+                length
+            )));
+        }
+
+        let channel = link.get_i64()?;
+        let channel = u32::try_from(channel).map_err(|_| {
+            Error::custom(format!("MuxLink: invalid channel id: {}", channel))
+        })?;
+
+        let expr = link.get_expr()?;
+
+        Ok((channel, expr))
+    }
+}
@@ -0,0 +1,226 @@
+//! Typed representation of the packets used to communicate with a Wolfram Kernel.
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+use wolfram_expr::Expr;
+
+use crate::{sys, Error, Link};
+
+/// Type of a WSTP packet.
+///
+/// See [WSTP Packets](https://reference.wolfram.com/language/guide/WSTPPackets.html)
+/// for a description of each packet type.
+///
+/// Use [`PacketType::try_from()`] to convert a raw packet type returned by
+/// [`Link::raw_next_packet()`] into a [`PacketType`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[allow(missing_docs)]
+pub enum PacketType {
+    Call,
+    Evaluate,
+    Return,
+    InputName,
+    EnterText,
+    EnterExpression,
+    OutputName,
+    ReturnText,
+    ReturnExpression,
+    Display,
+    DisplayEnd,
+    Message,
+    Text,
+    Input,
+    InputString,
+    Menu,
+    Syntax,
+    Suspend,
+    Resume,
+    BeginDialog,
+    EndDialog,
+}
+
+/// A complete packet read by [`Link::next_packet()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    packet_type: PacketType,
+    contents: Vec<Expr>,
+}
+
+impl PacketType {
+    /// Get the raw WSTP packet type, e.g. [`sys::RETURNPKT`].
+    pub fn as_raw(self) -> i32 {
+        match self {
+            PacketType::Call => sys::CALLPKT,
+            PacketType::Evaluate => sys::EVALUATEPKT,
+            PacketType::Return => sys::RETURNPKT,
+            PacketType::InputName => sys::INPUTNAMEPKT,
+            PacketType::EnterText => sys::ENTERTEXTPKT,
+            PacketType::EnterExpression => sys::ENTEREXPRPKT,
+            PacketType::OutputName => sys::OUTPUTNAMEPKT,
+            PacketType::ReturnText => sys::RETURNTEXTPKT,
+            PacketType::ReturnExpression => sys::RETURNEXPRPKT,
+            PacketType::Display => sys::DISPLAYPKT,
+            PacketType::DisplayEnd => sys::DISPLAYENDPKT,
+            PacketType::Message => sys::MESSAGEPKT,
+            PacketType::Text => sys::TEXTPKT,
+            PacketType::Input => sys::INPUTPKT,
+            PacketType::InputString => sys::INPUTSTRPKT,
+            PacketType::Menu => sys::MENUPKT,
+            PacketType::Syntax => sys::SYNTAXPKT,
+            PacketType::Suspend => sys::SUSPENDPKT,
+            PacketType::Resume => sys::RESUMEPKT,
+            PacketType::BeginDialog => sys::BEGINDLGPKT,
+            PacketType::EndDialog => sys::ENDDLGPKT,
+        }
+    }
+
+    /// Get the name of the packet head, e.g. `"ReturnPacket"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PacketType::Call => "CallPacket",
+            PacketType::Evaluate => "EvaluatePacket",
+            PacketType::Return => "ReturnPacket",
+            PacketType::InputName => "InputNamePacket",
+            PacketType::EnterText => "EnterTextPacket",
+            PacketType::EnterExpression => "EnterExpressionPacket",
+            PacketType::OutputName => "OutputNamePacket",
+            PacketType::ReturnText => "ReturnTextPacket",
+            PacketType::ReturnExpression => "ReturnExpressionPacket",
+            PacketType::Display => "DisplayPacket",
+            PacketType::DisplayEnd => "DisplayEndPacket",
+            PacketType::Message => "MessagePacket",
+            PacketType::Text => "TextPacket",
+            PacketType::Input => "InputPacket",
+            PacketType::InputString => "InputStringPacket",
+            PacketType::Menu => "MenuPacket",
+            PacketType::Syntax => "SyntaxPacket",
+            PacketType::Suspend => "SuspendPacket",
+            PacketType::Resume => "ResumePacket",
+            PacketType::BeginDialog => "BeginDialogPacket",
+            PacketType::EndDialog => "EndDialogPacket",
+        }
+    }
+
+    /// Number of elements in a packet of this type.
+    fn arity(self) -> usize {
+        match self {
+            // CallPacket[index, {args...}], MessagePacket[symbol, tag], and
+            // MenuPacket[code, title].
+            PacketType::Call | PacketType::Message | PacketType::Menu => 2,
+            PacketType::Suspend | PacketType::Resume => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl TryFrom<i32> for PacketType {
+    type Error = Error;
+
+    fn try_from(packet_type: i32) -> Result<PacketType, Error> {
+        let value = match packet_type {
+            sys::CALLPKT => PacketType::Call,
+            sys::EVALUATEPKT => PacketType::Evaluate,
+            sys::RETURNPKT => PacketType::Return,
+            sys::INPUTNAMEPKT => PacketType::InputName,
+            sys::ENTERTEXTPKT => PacketType::EnterText,
+            sys::ENTEREXPRPKT => PacketType::EnterExpression,
+            sys::OUTPUTNAMEPKT => PacketType::OutputName,
+            sys::RETURNTEXTPKT => PacketType::ReturnText,
+            sys::RETURNEXPRPKT => PacketType::ReturnExpression,
+            sys::DISPLAYPKT => PacketType::Display,
+            sys::DISPLAYENDPKT => PacketType::DisplayEnd,
+            sys::MESSAGEPKT => PacketType::Message,
+            sys::TEXTPKT => PacketType::Text,
+            sys::INPUTPKT => PacketType::Input,
+            sys::INPUTSTRPKT => PacketType::InputString,
+            sys::MENUPKT => PacketType::Menu,
+            sys::SYNTAXPKT => PacketType::Syntax,
+            sys::SUSPENDPKT => PacketType::Suspend,
+            sys::RESUMEPKT => PacketType::Resume,
+            sys::BEGINDLGPKT => PacketType::BeginDialog,
+            sys::ENDDLGPKT => PacketType::EndDialog,
+            _ => {
+                return Err(Error::custom(format!(
+                    "unknown WSTP packet type: {}",
+                    packet_type
+                )))
+            },
+        };
+
+        Ok(value)
+    }
+}
+
+impl Display for PacketType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Packet {
+    /// Get the type of this packet.
+    pub fn packet_type(&self) -> PacketType {
+        self.packet_type
+    }
+
+    /// Get the elements of this packet.
+    ///
+    /// For example, the contents of `ReturnPacket[expr]` is `[expr]`, and the contents
+    /// of `MessagePacket[symbol, tag]` is `[symbol, tag]`.
+    pub fn contents(&self) -> &[Expr] {
+        &self.contents
+    }
+
+    /// Convert this packet into its elements.
+    ///
+    /// See [`Packet::contents()`].
+    pub fn into_contents(self) -> Vec<Expr> {
+        self.contents
+    }
+}
+
+/// # Packets
+impl Link {
+    /// Read the next packet off of this link, including its contents.
+    ///
+    /// The link is left positioned at the end of the returned packet.
+    ///
+    /// Returns an error if the packet is not one of the standard [`PacketType`]s. Use
+    /// [`Link::raw_next_packet()`] to read packets of other types.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, PacketType};
+    /// use wolfram_expr::Expr;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("System`ReturnPacket", 1).unwrap();
+    /// link.put_i64(5).unwrap();
+    /// link.end_packet().unwrap();
+    ///
+    /// let packet = link.next_packet().unwrap();
+    ///
+    /// assert_eq!(packet.packet_type(), PacketType::Return);
+    /// assert_eq!(packet.contents(), &[Expr::from(5)]);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSNextPacket()`](https://reference.wolfram.com/language/ref/c/WSNextPacket.html)
+    pub fn next_packet(&mut self) -> Result<Packet, Error> {
+        let packet_type = PacketType::try_from(self.raw_next_packet()?)?;
+
+        let contents = (0..packet_type.arity())
+            .map(|_| self.get_expr())
+            .collect::<Result<Vec<Expr>, Error>>()?;
+
+        self.new_packet()?;
+
+        Ok(Packet {
+            packet_type,
+            contents,
+        })
+    }
+}
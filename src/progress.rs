@@ -0,0 +1,135 @@
+//! Progress reporting for long-running transfers.
+
+use crate::{
+    link_state::{self, Registered},
+    Error, Link,
+};
+
+pub(crate) type ProgressObserver = Box<dyn FnMut(&Progress) -> bool + Send + 'static>;
+
+/// Progress of a transfer, passed to the observer registered with
+/// [`Link::set_progress_observer()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of units (bytes or characters) transferred so far.
+    pub transferred: u64,
+    /// Total number of units in the transfer, if known.
+    pub total: Option<u64>,
+}
+
+/// # Progress reporting
+impl Link {
+    /// Register a function that is called periodically to report the progress of long
+    /// transfers on this link.
+    ///
+    /// If `observer` returns `false`, the transfer is cancelled, and the operation in
+    /// progress returns an error. Cancelling a transfer leaves a partially written or
+    /// read expression on the link.
+    ///
+    /// Progress is reported by:
+    ///
+    /// * [`StringWriter`][crate::StringWriter] and
+    ///   [`ByteArrayWriter`][crate::ByteArrayWriter]
+    /// * [`LinkDataWriter`][crate::LinkDataWriter] and
+    ///   [`LinkDataReader`][crate::LinkDataReader]
+    /// * [`Link::get_string_chunks()`]
+    /// * [`Link::put_file_as_byte_array()`]
+    /// * [`Link::get_byte_array_to_file()`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_progress_observer(Box::new(|progress: &wstp::Progress| {
+    ///     // Cancel the transfer after 2 bytes.
+    ///     progress.transferred < 2
+    /// }));
+    ///
+    /// let mut writer = link.begin_byte_array(4).unwrap();
+    ///
+    /// assert!(writer.write_chunk(&[1, 2]).is_err());
+    /// ```
+    pub fn set_progress_observer(
+        &mut self,
+        observer: Box<dyn FnMut(&Progress) -> bool + Send + 'static>,
+    ) {
+        let previous =
+            link_state::with(self.raw_link, |state| match state.progress_observer {
+                Some(ref mut registered) => registered.replace(Some(observer)),
+                None => {
+                    state.progress_observer = Some(Registered::new((), observer));
+                    None
+                },
+            });
+
+        drop(previous);
+    }
+
+    /// Unregister the observer set by [`Link::set_progress_observer()`], returning it.
+    ///
+    /// If this is called by the observer itself, the observer is dropped once it
+    /// returns, and `None` is returned.
+    pub fn take_progress_observer(
+        &mut self,
+    ) -> Option<Box<dyn FnMut(&Progress) -> bool + Send + 'static>> {
+        link_state::with_existing(self.raw_link, |state| {
+            let registered = state.progress_observer.as_mut()?;
+            let observer = registered.replace(None)?;
+
+            // The observer is not running, so there is nothing left to restore.
+            state.progress_observer = None;
+
+            Some(observer)
+        })
+        .flatten()
+    }
+}
+
+/// Report `progress` to the observer registered for `link`, if any.
+///
+/// Returns an error if the observer cancelled the transfer.
+pub(crate) fn report(link: &mut Link, progress: Progress) -> Result<(), Error> {
+    // Note: Take the observer out of the table while it runs, so that it may call
+    //       `set_progress_observer()` without deadlocking.
+    let taken = link_state::with_existing(link.raw_link, |state| {
+        state.progress_observer.as_mut()?.take()
+    });
+
+    let (mut observer, generation) = match taken.flatten() {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    let keep_going = observer(&progress);
+
+    // Put the observer back, unless it was replaced or unregistered while it was
+    // running.
+    let replaced = link_state::with_existing(link.raw_link, |state| {
+        let registered = state.progress_observer.as_mut()?;
+        let replaced = registered.restore(observer, generation);
+
+        if !registered.is_set() {
+            state.progress_observer = None;
+        }
+
+        replaced
+    });
+
+    drop(replaced);
+
+    if !keep_going {
+        return Err(Error::custom(format!(
+            "transfer cancelled by progress observer after {} of {} units",
+            progress.transferred,
+            progress
+                .total
+                .map(|total| total.to_string())
+                .unwrap_or_else(|| "unknown".to_owned())
+        )));
+    }
+
+    Ok(())
+}
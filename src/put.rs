@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::ffi::CString;
 
@@ -7,7 +8,7 @@ use crate::{
         WSPutInteger8, WSPutReal32, WSPutReal64, WSPutUTF16String, WSPutUTF32String,
         WSPutUTF8String, WSPutUTF8Symbol,
     },
-    Error, Link,
+    ArrayElement, Error, Link,
 };
 
 impl Link {
@@ -16,6 +17,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSPutType()`](https://reference.wolfram.com/language/ref/c/WSPutType.html)
     pub fn put_raw_type(&mut self, type_: i32) -> Result<(), Error> {
+        crate::strict::on_put_raw_type(self, type_)?;
+
         if unsafe { sys::WSPutType(self.raw_link, type_) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -25,6 +28,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSEndPacket()`](https://reference.wolfram.com/language/ref/c/WSEndPacket.html)
     pub fn end_packet(&mut self) -> Result<(), Error> {
+        crate::strict::on_end_packet(self)?;
+
         if unsafe { sys::WSEndPacket(self.raw_link) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -38,6 +43,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutUTF8String()`](https://reference.wolfram.com/language/ref/c/WSPutUTF8String.html)
     pub fn put_str(&mut self, string: &str) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_str")?;
+
         // TODO: Optimization:
         //     This intermediate CString allocation may not actually be necessary. Because
         //     WSPutUTF8String() takes a pointer + length pair, it's possible it doesn't
@@ -63,6 +70,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutUTF8Symbol()`](https://reference.wolfram.com/language/ref/c/WSPutUTF8Symbol.html)
     pub fn put_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_symbol")?;
+
         // FIXME:
         //     Is this extra allocation necessary?WSPutUTF8Symbol doesn't seem to require
         //     that the data contains a NULL terminator, so we should be able to just
@@ -88,6 +97,8 @@ impl Link {
     /// This function will return a WSTP error if `utf8` is not a valid UTF-8 encoded
     /// string.
     pub fn put_utf8_str(&mut self, utf8: &[u8]) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_utf8_str")?;
+
         let len = i32::try_from(utf8.len()).expect("usize overflows i32");
 
         if unsafe { WSPutUTF8String(self.raw_link, utf8.as_ptr(), len) } == 0 {
@@ -104,6 +115,8 @@ impl Link {
     /// *WSTP C API Documentation:* [`WSPutUTF16String()`](https://reference.wolfram.com/language/ref/c/WSPutUTF16String.html)
     ///
     pub fn put_utf16_str(&mut self, utf16: &[u16]) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_utf16_str")?;
+
         let len = i32::try_from(utf16.len()).expect("usize overflows i32");
 
         if unsafe { WSPutUTF16String(self.raw_link, utf16.as_ptr(), len) } == 0 {
@@ -112,6 +125,24 @@ impl Link {
         Ok(())
     }
 
+    /// Put a UCS-2 encoded string.
+    ///
+    /// Each element of `ucs2` is written as a single character. Characters outside the
+    /// Basic Multilingual Plane cannot be represented in UCS-2; use
+    /// [`Link::put_utf16_str()`] to write them.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutUCS2String()`](https://reference.wolfram.com/language/ref/c/WSPutUCS2String.html)
+    pub fn put_ucs2_str(&mut self, ucs2: &[u16]) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_ucs2_str")?;
+
+        let len = i32::try_from(ucs2.len()).expect("usize overflows i32");
+
+        if unsafe { sys::WSPutUCS2String(self.raw_link, ucs2.as_ptr(), len) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+        Ok(())
+    }
+
     /// Put a UTF-32 encoded string.
     ///
     /// This function will return a WSTP error if `utf32` is not a valid UTF-32 encoded
@@ -119,6 +150,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSPutUTF32String()`](https://reference.wolfram.com/language/ref/c/WSPutUTF32String.html)
     pub fn put_utf32_str(&mut self, utf32: &[u32]) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_utf32_str")?;
+
         let len = i32::try_from(utf32.len()).expect("usize overflows i32");
 
         if unsafe { WSPutUTF32String(self.raw_link, utf32.as_ptr(), len) } == 0 {
@@ -127,6 +160,24 @@ impl Link {
         Ok(())
     }
 
+    /// Put a string whose characters are the bytes in `bytes`.
+    ///
+    /// Each byte is written as a single character with the corresponding code point.
+    /// Unlike [`Link::put_utf8_str()`], `bytes` does not need to be valid UTF-8.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutByteString()`](https://reference.wolfram.com/language/ref/c/WSPutByteString.html)
+    pub fn put_byte_string(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_byte_string")?;
+
+        let len =
+            std::os::raw::c_long::try_from(bytes.len()).expect("usize overflows c_long");
+
+        if unsafe { sys::WSPutByteString(self.raw_link, bytes.as_ptr(), len) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+        Ok(())
+    }
+
     //==================================
     // Functions
     //==================================
@@ -187,11 +238,13 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutArgCount()`](https://reference.wolfram.com/language/ref/c/WSPutArgCount.html)
     pub fn put_arg_count(&mut self, count: usize) -> Result<(), Error> {
+        crate::strict::on_put_arg_count(self, count)?;
+
         let count: i32 = i32::try_from(count).map_err(|err| {
-            Error::custom(format!(
-                "put_arg_count: Error converting usize to i32: {}",
-                err
-            ))
+            Error::with_source(
+                format!("put_arg_count: Error converting usize to i32: {}", err),
+                err,
+            )
         })?;
 
         if unsafe { WSPutArgCount(self.raw_link, count) } == 0 {
@@ -207,6 +260,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutInteger64()`](https://reference.wolfram.com/language/ref/c/WSPutInteger64.html)
     pub fn put_i64(&mut self, value: i64) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_i64")?;
+
         if unsafe { WSPutInteger64(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -215,6 +270,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutInteger32()`](https://reference.wolfram.com/language/ref/c/WSPutInteger32.html)
     pub fn put_i32(&mut self, value: i32) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_i32")?;
+
         if unsafe { WSPutInteger32(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -223,6 +280,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutInteger16()`](https://reference.wolfram.com/language/ref/c/WSPutInteger16.html)
     pub fn put_i16(&mut self, value: i16) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_i16")?;
+
         // Note: This conversion is necessary due to the declaration of WSPutInteger16,
         //       which takes an int for legacy reasons.
         let value = i32::from(value);
@@ -235,6 +294,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutInteger8()`](https://reference.wolfram.com/language/ref/c/WSPutInteger8.html)
     pub fn put_u8(&mut self, value: u8) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_u8")?;
+
         if unsafe { WSPutInteger8(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -243,6 +304,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutReal64()`](https://reference.wolfram.com/language/ref/c/WSPutReal64.html)
     pub fn put_f64(&mut self, value: f64) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_f64")?;
+
         if unsafe { WSPutReal64(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -251,6 +314,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutReal32()`](https://reference.wolfram.com/language/ref/c/WSPutReal32.html)
     pub fn put_f32(&mut self, value: f32) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_f32")?;
+
         // Note: This conversion is necessary due to the declaration of WSPutReal32,
         //       which takes a double for legacy reasons.
         let value = f64::from(value);
@@ -261,22 +326,103 @@ impl Link {
         Ok(())
     }
 
+    //==================================
+    // Complex and rational numbers
+    //==================================
+
+    /// Put the complex number `Complex[re, im]`.
+    ///
+    /// See also [`Link::get_complex()`].
+    pub fn put_complex(&mut self, re: f64, im: f64) -> Result<(), Error> {
+        self.put_function("System`Complex", 2)?;
+        self.put_f64(re)?;
+        self.put_f64(im)?;
+
+        Ok(())
+    }
+
+    /// Put the rational number `Rational[numerator, denominator]`.
+    ///
+    /// The Wolfram Language expects `Rational` expressions to be in lowest terms, with
+    /// a positive denominator; this function does not normalize its arguments.
+    ///
+    /// See also [`Link::get_rational()`].
+    pub fn put_rational(
+        &mut self,
+        numerator: i64,
+        denominator: i64,
+    ) -> Result<(), Error> {
+        self.put_function("System`Rational", 2)?;
+        self.put_i64(numerator)?;
+        self.put_i64(denominator)?;
+
+        Ok(())
+    }
+
+    //==================================
+    // Numeric arrays
+    //==================================
+
+    /// Put a multidimensional array of any [`ArrayElement`] type.
+    ///
+    /// This is a generic alternative to the `put_*_array()` methods, e.g.
+    /// [`Link::put_i32_array()`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the product of `dimensions` is not equal to `data.len()`.
+    pub fn put_array<T: ArrayElement>(
+        &mut self,
+        data: &[T],
+        dimensions: &[usize],
+    ) -> Result<(), Error> {
+        T::put_array(self, data, dimensions)
+    }
+
     //==================================
     // Integer numeric arrays
     //==================================
 
     /// Put a multidimensional array of [`i64`].
     ///
+    /// `data` contains the elements of the array in row-major order, and `dimensions`
+    /// contains the length of each dimension of the array.
+    ///
     /// # Panics
     ///
     /// This function will panic if the product of `dimensions` is not equal to `data.len()`.
     ///
+    /// # Example
+    ///
+    /// Put the matrix `{{1, 2, 3}, {4, 5, 6}}`:
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::{Expr, Symbol};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64_array(&[1, 2, 3, 4, 5, 6], &[2, 3]).unwrap();
+    ///
+    /// let list = |elements| Expr::normal(Symbol::new("System`List"), elements);
+    ///
+    /// assert_eq!(
+    ///     link.get_expr().unwrap(),
+    ///     list(vec![
+    ///         list(vec![Expr::from(1), Expr::from(2), Expr::from(3)]),
+    ///         list(vec![Expr::from(4), Expr::from(5), Expr::from(6)]),
+    ///     ])
+    /// );
+    /// ```
+    ///
     /// *WSTP C API Documentation:* [`WSPutInteger64Array()`](https://reference.wolfram.com/language/ref/c/WSPutInteger64Array.html)
     pub fn put_i64_array(
         &mut self,
         data: &[i64],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_i64_array")?;
+
         assert_eq!(
             data.len(),
             dimensions.iter().product(),
@@ -314,6 +460,8 @@ impl Link {
         data: &[i32],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_i32_array")?;
+
         assert_eq!(
             data.len(),
             dimensions.iter().product(),
@@ -351,6 +499,8 @@ impl Link {
         data: &[i16],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_i16_array")?;
+
         assert_eq!(
             data.len(),
             dimensions.iter().product(),
@@ -382,6 +532,8 @@ impl Link {
         data: &[u8],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_u8_array")?;
+
         assert_eq!(
             data.len(),
             dimensions.iter().product(),
@@ -413,6 +565,9 @@ impl Link {
 
     /// Put a multidimensional array of [`f64`].
     ///
+    /// `data` contains the elements of the array in row-major order, and `dimensions`
+    /// contains the length of each dimension of the array.
+    ///
     /// # Panics
     ///
     /// This function will panic if the product of `dimensions` is not equal to `data.len()`.
@@ -423,6 +578,8 @@ impl Link {
         data: &[f64],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_f64_array")?;
+
         assert_eq!(
             data.len(),
             dimensions.iter().product(),
@@ -460,6 +617,8 @@ impl Link {
         data: &[f32],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        crate::strict::on_put_expr(self, "put_f32_array")?;
+
         assert_eq!(
             data.len(),
             dimensions.iter().product(),
@@ -484,6 +643,136 @@ impl Link {
 
         Ok(())
     }
+
+    /// Put a strided view of a multidimensional array of [`f64`].
+    ///
+    /// `strides[i]` is the distance, in elements of `data`, between consecutive indices
+    /// of dimension `i`. This allows putting a slice of a larger array (for example, a
+    /// column of a matrix, or a transposed matrix) without first copying it into
+    /// contiguous storage.
+    ///
+    /// If the view is contiguous in row-major order, `data` is passed to WSTP directly.
+    /// Otherwise, the elements are gathered into a reusable per-thread buffer before
+    /// being put.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `dimensions` and `strides` have different lengths, or
+    /// if the view addresses an element beyond the end of `data`.
+    ///
+    /// # Example
+    ///
+    /// Put the transpose of a 2x3 matrix:
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let matrix = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    ///
+    /// link.put_f64_array_view(&matrix, &[3, 2], &[1, 3]).unwrap();
+    ///
+    /// let array = link.get_f64_array().unwrap();
+    ///
+    /// assert_eq!(array.dimensions(), &[3, 2]);
+    /// assert_eq!(array.data(), &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutReal64Array()`](https://reference.wolfram.com/language/ref/c/WSPutReal64Array.html)
+    pub fn put_f64_array_view(
+        &mut self,
+        data: &[f64],
+        dimensions: &[usize],
+        strides: &[usize],
+    ) -> Result<(), Error> {
+        assert_eq!(
+            dimensions.len(),
+            strides.len(),
+            "dimensions and strides have different lengths"
+        );
+
+        let len: usize = dimensions.iter().product();
+
+        if len != 0 {
+            let last_offset: usize = dimensions
+                .iter()
+                .zip(strides)
+                .map(|(dim, stride)| (dim - 1) * stride)
+                .sum();
+
+            assert!(
+                last_offset < data.len(),
+                "strided array view addresses element {} of data with length {}",
+                last_offset,
+                data.len()
+            );
+        }
+
+        if is_row_major_contiguous(dimensions, strides) {
+            return self.put_f64_array(&data[..len], dimensions);
+        }
+
+        thread_local! {
+            static GATHER_BUFFER: RefCell<Vec<f64>> = const { RefCell::new(Vec::new()) };
+        }
+
+        // Note: Take the buffer out of the thread local instead of borrowing it, so that
+        //       it is not held across the call into WSTP.
+        let mut buffer =
+            GATHER_BUFFER.with(|cell| std::mem::take(&mut *cell.borrow_mut()));
+
+        buffer.clear();
+        buffer.reserve(len);
+
+        if len != 0 {
+            gather_strided(data, dimensions, strides, &mut buffer);
+        }
+
+        let result = self.put_f64_array(&buffer, dimensions);
+
+        GATHER_BUFFER.with(|cell| *cell.borrow_mut() = buffer);
+
+        result
+    }
+}
+
+/// Returns `true` if a view with the specified `dimensions` and `strides` addresses a
+/// contiguous, row-major range of elements starting at index 0.
+fn is_row_major_contiguous(dimensions: &[usize], strides: &[usize]) -> bool {
+    let mut expected_stride = 1;
+
+    for (dim, stride) in dimensions.iter().zip(strides).rev() {
+        // The stride of a dimension of length 1 is never used.
+        if *dim != 1 && *stride != expected_stride {
+            return false;
+        }
+
+        expected_stride *= dim;
+    }
+
+    true
+}
+
+/// Append the elements of the strided view of `data` to `out`, in row-major order.
+fn gather_strided<T: Copy>(
+    data: &[T],
+    dimensions: &[usize],
+    strides: &[usize],
+    out: &mut Vec<T>,
+) {
+    match (dimensions, strides) {
+        ([], []) => out.push(data[0]),
+        ([dim], [stride]) => {
+            out.extend((0..*dim).map(|index| data[index * stride]));
+        },
+        ([dim, dims_rest @ ..], [stride, strides_rest @ ..]) => {
+            for index in 0..*dim {
+                gather_strided(&data[index * stride..], dims_rest, strides_rest, out);
+            }
+        },
+        _ => unreachable!("dimensions and strides have different lengths"),
+    }
 }
 
 /// Convert `dimensions` to a `Vec<i32>`, which can further be converted to a
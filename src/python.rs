@@ -0,0 +1,406 @@
+//! Python bindings to the safe `wstp` API, built using [`pyo3`].
+//!
+//! This module is only available when the `python` feature is enabled.
+//!
+//! Python services that need to communicate over WSTP can reuse the safe wrappers
+//! provided by this crate, instead of calling into the WSTP C library via `ctypes`.
+//!
+//! # Example
+//!
+//! Include the `wstp` classes in a `pyo3` extension module:
+//!
+//! ```ignore
+//! use pyo3::prelude::*;
+//!
+//! #[pymodule]
+//! fn my_extension(py: Python, m: &PyModule) -> PyResult<()> {
+//!     wstp::python::register(py, m)
+//! }
+//! ```
+//!
+//! and then from Python:
+//!
+//! ```python
+//! from my_extension import Link
+//!
+//! link = Link.new_loopback()
+//! link.put_i64(5)
+//! assert link.get_i64() == 5
+//! ```
+//!
+//! Evaluate Wolfram Language code using a [`KernelSession`]. Input and results are
+//! passed as strings:
+//!
+//! ```python
+//! from my_extension import KernelSession
+//!
+//! session = KernelSession.launch("/path/to/WolframKernel")
+//! assert session.evaluate("2 + 2") == "4"
+//! ```
+
+use std::path::Path;
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+use wolfram_expr::{Expr, Symbol};
+
+use crate::{
+    kernel::{self, InterruptHandle, KernelSession},
+    Error, ForceSend, Link, LinkServer, Protocol,
+};
+
+create_exception!(wstp, WstpError, PyException, "WSTP link error.");
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        WstpError::new_err(err.to_string())
+    }
+}
+
+impl From<kernel::Error> for PyErr {
+    fn from(err: kernel::Error) -> PyErr {
+        WstpError::new_err(err.to_string())
+    }
+}
+
+/// Add the `wstp` Python classes and exception types to the module `m`.
+pub fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("WstpError", py.get_type::<WstpError>())?;
+    m.add_class::<PyLink>()?;
+    m.add_class::<PyLinkServer>()?;
+    m.add_class::<PyKernelSession>()?;
+    m.add_class::<PyInterruptHandle>()?;
+
+    Ok(())
+}
+
+//======================================
+// Link
+//======================================
+
+/// Python wrapper around [`Link`].
+#[pyclass(name = "Link")]
+pub struct PyLink {
+    link: Option<Link>,
+}
+
+impl PyLink {
+    /// Get the wrapped [`Link`], or raise an error if the link has been closed.
+    fn link(&mut self) -> PyResult<&mut Link> {
+        self.link
+            .as_mut()
+            .ok_or_else(|| WstpError::new_err("link has been closed"))
+    }
+
+    /// Convert this value into the wrapped [`Link`], if it has not been closed.
+    pub fn into_inner(self) -> Option<Link> {
+        self.link
+    }
+}
+
+impl From<Link> for PyLink {
+    fn from(link: Link) -> PyLink {
+        PyLink { link: Some(link) }
+    }
+}
+
+#[pymethods]
+impl PyLink {
+    /// See [`Link::new_loopback()`].
+    #[staticmethod]
+    fn new_loopback() -> PyResult<Self> {
+        Ok(PyLink::from(Link::new_loopback()?))
+    }
+
+    /// See [`Link::listen()`].
+    #[staticmethod]
+    fn listen(protocol: &str, name: &str) -> PyResult<Self> {
+        Ok(PyLink::from(Link::listen(parse_protocol(protocol)?, name)?))
+    }
+
+    /// See [`Link::connect()`].
+    #[staticmethod]
+    fn connect(protocol: &str, name: &str) -> PyResult<Self> {
+        Ok(PyLink::from(Link::connect(
+            parse_protocol(protocol)?,
+            name,
+        )?))
+    }
+
+    /// See [`Link::tcpip_connect()`].
+    #[staticmethod]
+    fn tcpip_connect(addr: &str) -> PyResult<Self> {
+        Ok(PyLink::from(Link::tcpip_connect(addr)?))
+    }
+
+    /// See [`Link::connect_to_link_server()`].
+    #[staticmethod]
+    fn connect_to_link_server(addr: &str) -> PyResult<Self> {
+        Ok(PyLink::from(Link::connect_to_link_server(addr)?))
+    }
+
+    /// See [`Link::close()`].
+    fn close(&mut self) {
+        // Note: The link is closed when it is dropped.
+        self.link = None;
+    }
+
+    /// See [`Link::activate()`].
+    fn activate(&mut self) -> PyResult<()> {
+        Ok(self.link()?.activate()?)
+    }
+
+    /// See [`Link::link_name()`].
+    fn link_name(&mut self) -> PyResult<String> {
+        Ok(self.link()?.link_name())
+    }
+
+    /// See [`Link::is_ready()`].
+    fn is_ready(&mut self) -> PyResult<bool> {
+        Ok(self.link()?.is_ready())
+    }
+
+    /// See [`Link::flush()`].
+    fn flush(&mut self) -> PyResult<()> {
+        Ok(self.link()?.flush()?)
+    }
+
+    /// See [`Link::end_packet()`].
+    fn end_packet(&mut self) -> PyResult<()> {
+        Ok(self.link()?.end_packet()?)
+    }
+
+    /// See [`Link::new_packet()`].
+    fn new_packet(&mut self) -> PyResult<()> {
+        Ok(self.link()?.new_packet()?)
+    }
+
+    /// See [`Link::raw_next_packet()`].
+    fn raw_next_packet(&mut self) -> PyResult<i32> {
+        Ok(self.link()?.raw_next_packet()?)
+    }
+
+    //==================================
+    // Put
+    //==================================
+
+    /// See [`Link::put_function()`].
+    fn put_function(&mut self, head: &str, count: usize) -> PyResult<()> {
+        Ok(self.link()?.put_function(head, count)?)
+    }
+
+    /// See [`Link::put_str()`].
+    fn put_str(&mut self, string: &str) -> PyResult<()> {
+        Ok(self.link()?.put_str(string)?)
+    }
+
+    /// See [`Link::put_symbol()`].
+    fn put_symbol(&mut self, symbol: &str) -> PyResult<()> {
+        Ok(self.link()?.put_symbol(symbol)?)
+    }
+
+    /// See [`Link::put_i64()`].
+    fn put_i64(&mut self, value: i64) -> PyResult<()> {
+        Ok(self.link()?.put_i64(value)?)
+    }
+
+    /// See [`Link::put_f64()`].
+    fn put_f64(&mut self, value: f64) -> PyResult<()> {
+        Ok(self.link()?.put_f64(value)?)
+    }
+
+    //==================================
+    // Get
+    //==================================
+
+    /// See [`Link::test_head()`].
+    fn test_head(&mut self, symbol: &str) -> PyResult<usize> {
+        Ok(self.link()?.test_head(symbol)?)
+    }
+
+    /// See [`Link::get_arg_count()`].
+    fn get_arg_count(&mut self) -> PyResult<usize> {
+        Ok(self.link()?.get_arg_count()?)
+    }
+
+    /// See [`Link::get_string()`].
+    fn get_string(&mut self) -> PyResult<String> {
+        Ok(self.link()?.get_string()?)
+    }
+
+    /// See [`Link::get_symbol_ref()`].
+    fn get_symbol(&mut self) -> PyResult<String> {
+        Ok(self.link()?.get_symbol_ref()?.as_str().to_owned())
+    }
+
+    /// See [`Link::get_i64()`].
+    fn get_i64(&mut self) -> PyResult<i64> {
+        Ok(self.link()?.get_i64()?)
+    }
+
+    /// See [`Link::get_f64()`].
+    fn get_f64(&mut self) -> PyResult<f64> {
+        Ok(self.link()?.get_f64()?)
+    }
+
+    /// Read the next expression off of the link, and return its `InputForm`-like
+    /// string representation.
+    ///
+    /// See [`Link::get_expr()`].
+    fn get_expr_string(&mut self) -> PyResult<String> {
+        Ok(self.link()?.get_expr()?.to_string())
+    }
+
+    fn __repr__(&self) -> String {
+        match self.link {
+            Some(ref link) => format!("Link({:?})", link.link_name()),
+            None => "Link(<closed>)".to_owned(),
+        }
+    }
+}
+
+//======================================
+// LinkServer
+//======================================
+
+/// Python wrapper around [`LinkServer`].
+// Note: `LinkServer` is not `Send`, so this class must only be used from the Python
+//       thread that created it.
+#[pyclass(name = "LinkServer", unsendable)]
+pub struct PyLinkServer {
+    server: LinkServer,
+}
+
+#[pymethods]
+impl PyLinkServer {
+    /// See [`LinkServer::bind()`].
+    #[staticmethod]
+    fn bind(addr: &str) -> PyResult<Self> {
+        Ok(PyLinkServer {
+            server: LinkServer::bind(addr)?,
+        })
+    }
+
+    /// See [`LinkServer::try_port()`].
+    fn port(&self) -> PyResult<u16> {
+        Ok(self.server.try_port()?)
+    }
+
+    /// See [`LinkServer::try_interface()`].
+    fn interface(&self) -> PyResult<String> {
+        Ok(self.server.try_interface()?.to_string())
+    }
+
+    /// See [`LinkServer::accept()`].
+    ///
+    /// The Python global interpreter lock is released while waiting for a new
+    /// connection.
+    fn accept(&self, py: Python) -> PyResult<PyLink> {
+        let server = ForceSend(&self.server);
+
+        let link = py.allow_threads(move || server.into_inner().accept())?;
+
+        Ok(PyLink::from(link))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.server)
+    }
+}
+
+//======================================
+// KernelSession
+//======================================
+
+/// Python wrapper around [`KernelSession`].
+///
+/// Wolfram Language input and results are passed as strings: the input is parsed
+/// using `ToExpression`, and the result is returned in an `InputForm`-like string
+/// representation.
+#[pyclass(name = "KernelSession")]
+pub struct PyKernelSession {
+    session: KernelSession,
+}
+
+#[pymethods]
+impl PyKernelSession {
+    /// See [`KernelSession::launch()`].
+    #[staticmethod]
+    fn launch(kernel: &str) -> PyResult<Self> {
+        Ok(PyKernelSession {
+            session: KernelSession::launch(Path::new(kernel))?,
+        })
+    }
+
+    /// Evaluate the Wolfram Language input `code`, and return the result as a string.
+    ///
+    /// The Python global interpreter lock is released while waiting for the result, so
+    /// another Python thread can abort the evaluation using an [`InterruptHandle`].
+    ///
+    /// See [`KernelSession::evaluate()`].
+    fn evaluate(&mut self, py: Python, code: &str) -> PyResult<String> {
+        let expr =
+            Expr::normal(Symbol::new("System`ToExpression"), vec![Expr::string(code)]);
+
+        let session = &mut self.session;
+
+        let result = py.allow_threads(move || session.evaluate(&expr))?;
+
+        Ok(result.to_string())
+    }
+
+    /// See [`KernelSession::interrupt_handle()`].
+    fn interrupt_handle(&self) -> PyInterruptHandle {
+        PyInterruptHandle {
+            handle: self.session.interrupt_handle(),
+        }
+    }
+
+    /// See [`KernelSession::shutdown()`].
+    fn shutdown(&mut self) -> PyResult<()> {
+        Ok(self.session.shutdown()?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.session)
+    }
+}
+
+/// Python wrapper around [`InterruptHandle`].
+#[pyclass(name = "InterruptHandle")]
+pub struct PyInterruptHandle {
+    handle: InterruptHandle,
+}
+
+#[pymethods]
+impl PyInterruptHandle {
+    /// See [`InterruptHandle::interrupt()`].
+    fn interrupt(&self) -> PyResult<()> {
+        Ok(self.handle.interrupt()?)
+    }
+
+    /// See [`InterruptHandle::abort()`].
+    fn abort(&self) -> PyResult<()> {
+        Ok(self.handle.abort()?)
+    }
+}
+
+//======================================
+// Utilities
+//======================================
+
+fn parse_protocol(protocol: &str) -> PyResult<Protocol> {
+    let protocol = match protocol {
+        "IntraProcess" => Protocol::IntraProcess,
+        "SharedMemory" => Protocol::SharedMemory,
+        "TCPIP" => Protocol::TCPIP,
+        "Pipes" => Protocol::Pipes,
+        _ => {
+            return Err(WstpError::new_err(format!(
+                "unknown WSTP link protocol: '{}'",
+                protocol
+            )))
+        },
+    };
+
+    Ok(protocol)
+}
@@ -0,0 +1,176 @@
+//! Retrying of transient failures when connecting [`TCPIP`][crate::Protocol::TCPIP]
+//! links.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::net;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Error, Link};
+
+/// Policy controlling how connection attempts are retried.
+///
+/// Used by [`Link::tcpip_connect_with_retry()`] and
+/// [`Link::connect_to_link_server_with_retry()`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use wstp::RetryPolicy;
+///
+/// let policy = RetryPolicy {
+///     max_attempts: 10,
+///     initial_backoff: Duration::from_millis(50),
+///     ..RetryPolicy::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of connection attempts, including the first.
+    pub max_attempts: u32,
+    /// Time to wait before the second attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound on the time to wait between attempts.
+    pub max_backoff: Duration,
+    /// Factor the wait time is multiplied by after each failed attempt.
+    pub multiplier: f64,
+    /// If `true`, each wait time is randomly reduced by up to half, to avoid many
+    /// clients retrying in lockstep.
+    pub jitter: bool,
+    /// Returns `true` if a connection attempt that failed with the specified error
+    /// should be retried.
+    pub is_retryable: fn(&Error) -> bool,
+}
+
+impl Default for RetryPolicy {
+    /// Make 5 attempts, waiting 100ms before the second attempt and doubling the wait
+    /// after each subsequent failure, with jitter. All errors are retried.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+            is_retryable: |_| true,
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let RetryPolicy {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+            multiplier,
+            jitter,
+            is_retryable: _,
+        } = self;
+
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", max_attempts)
+            .field("initial_backoff", initial_backoff)
+            .field("max_backoff", max_backoff)
+            .field("multiplier", multiplier)
+            .field("jitter", jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetryPolicy {
+    /// Call `func` until it succeeds, it fails with a non-retryable error, or the
+    /// maximum number of attempts has been made.
+    ///
+    /// The error from the last attempt is returned if every attempt fails.
+    pub fn retry<T, F>(&self, mut func: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Result<T, Error>,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+
+        loop {
+            let err = match func() {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+
+            if attempt >= self.max_attempts || !(self.is_retryable)(&err) {
+                return Err(err);
+            }
+
+            thread::sleep(self.jittered(backoff));
+
+            let next = backoff.as_secs_f64() * self.multiplier;
+            backoff = Duration::from_secs_f64(next.min(self.max_backoff.as_secs_f64()));
+            attempt += 1;
+        }
+    }
+
+    fn jittered(&self, backoff: Duration) -> Duration {
+        if !self.jitter {
+            return backoff;
+        }
+
+        // Note: Use the randomly seeded std hasher to avoid depending on a random number
+        //       generator crate.
+        let random = RandomState::new().build_hasher().finish();
+        let fraction = (random % 1024) as f64 / 1024.0;
+
+        backoff.mul_f64(1.0 - fraction / 2.0)
+    }
+}
+
+/// # Connecting with retries
+impl Link {
+    /// Connect to an existing WSTP [`TCPIP`][crate::Protocol::TCPIP] link listening at
+    /// `addr`, retrying failed attempts according to `policy`.
+    ///
+    /// Unlike [`Link::tcpip_connect()`], this function activates the link, so that a
+    /// failure to reach the peer is detected (and retried) before this function returns.
+    pub fn tcpip_connect_with_retry<A: net::ToSocketAddrs>(
+        addr: A,
+        policy: &RetryPolicy,
+    ) -> Result<Self, Error> {
+        let addrs: Vec<net::SocketAddr> = addr
+            .to_socket_addrs()
+            .map_err(|err| {
+                Error::with_source(
+                    format!("error connecting to TCPIP Link address: {}", err),
+                    err,
+                )
+            })?
+            .collect();
+
+        policy.retry(|| {
+            let mut link = Link::tcpip_connect(&addrs[..])?;
+            link.activate()?;
+            Ok(link)
+        })
+    }
+
+    /// Open a WSTP [`TCPIP`][crate::Protocol::TCPIP] connection to a
+    /// [`LinkServer`][crate::LinkServer], retrying failed attempts according to `policy`.
+    ///
+    /// See [`Link::connect_to_link_server()`].
+    pub fn connect_to_link_server_with_retry<A: net::ToSocketAddrs>(
+        addrs: A,
+        policy: &RetryPolicy,
+    ) -> Result<Self, Error> {
+        let addrs: Vec<net::SocketAddr> = addrs
+            .to_socket_addrs()
+            .map_err(|err| {
+                Error::with_source(
+                    format!("error connecting to LinkServer address: {}", err),
+                    err,
+                )
+            })?
+            .collect();
+
+        policy.retry(|| Link::connect_to_link_server(&addrs[..]))
+    }
+}
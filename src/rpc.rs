@@ -0,0 +1,114 @@
+//! Correlation of requests and responses sent over a single [`Link`].
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link, MuxLink};
+
+/// Identifier used to match a response to the request it answers.
+///
+/// Returned by [`RpcLink::send_request()`] and [`RpcLink::recv_request()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(u32);
+
+/// Link wrapper that tags each request with a [`RequestId`], and matches responses to
+/// the request they answer.
+///
+/// This allows several requests to be in flight at once, and their responses to be
+/// read in any order, instead of strictly alternating between writing a request and
+/// reading its response.
+///
+/// One end of the link acts as the client, using [`RpcLink::send_request()`] and
+/// [`RpcLink::wait_response()`]; the other end acts as the server, using
+/// [`RpcLink::recv_request()`] and [`RpcLink::send_response()`]. Requests and responses
+/// are written using the framing described in [`MuxLink`], with the request id used as
+/// the channel.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, RpcLink};
+/// use wolfram_expr::Expr;
+///
+/// // A loopback link echoes each request back as its response.
+/// let mut client = RpcLink::new(Link::new_loopback().unwrap());
+///
+/// let first = client.send_request(&Expr::from(1)).unwrap();
+/// let second = client.send_request(&Expr::from(2)).unwrap();
+///
+/// // Responses can be waited for in any order.
+/// assert_eq!(client.wait_response(second).unwrap(), Expr::from(2));
+/// assert_eq!(client.wait_response(first).unwrap(), Expr::from(1));
+/// ```
+#[derive(Debug)]
+pub struct RpcLink {
+    mux: MuxLink,
+    next_id: u32,
+}
+
+impl RpcLink {
+    /// Construct a new [`RpcLink`] that sends and receives messages on `link`.
+    pub fn new(link: Link) -> Self {
+        RpcLink {
+            mux: MuxLink::new(link),
+            next_id: 0,
+        }
+    }
+
+    /// Get the underlying [`Link`].
+    pub fn link(&mut self) -> &mut Link {
+        self.mux.link()
+    }
+
+    /// Convert this value into the underlying [`Link`].
+    ///
+    /// Any responses that have been read from the link but not yet waited for are
+    /// discarded.
+    pub fn into_inner(self) -> Link {
+        self.mux.into_inner()
+    }
+
+    //==================================
+    // Client
+    //==================================
+
+    /// Send `request`, returning the id that can be used to wait for its response.
+    pub fn send_request(&mut self, request: &Expr) -> Result<RequestId, Error> {
+        let id = RequestId(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1);
+
+        self.mux.send(id.0, request)?;
+
+        Ok(id)
+    }
+
+    /// Wait for the response to the request identified by `id`.
+    ///
+    /// Responses to other requests that are read while waiting are retained until they
+    /// are waited for.
+    pub fn wait_response(&mut self, id: RequestId) -> Result<Expr, Error> {
+        self.mux.recv(id.0)
+    }
+
+    /// Send `request`, and wait for its response.
+    pub fn call(&mut self, request: &Expr) -> Result<Expr, Error> {
+        let id = self.send_request(request)?;
+
+        self.wait_response(id)
+    }
+
+    //==================================
+    // Server
+    //==================================
+
+    /// Receive the next request.
+    pub fn recv_request(&mut self) -> Result<(RequestId, Expr), Error> {
+        let (id, request) = self.mux.recv_any()?;
+
+        Ok((RequestId(id), request))
+    }
+
+    /// Send `response` as the answer to the request identified by `id`.
+    pub fn send_response(&mut self, id: RequestId, response: &Expr) -> Result<(), Error> {
+        self.mux.send(id.0, response)
+    }
+}
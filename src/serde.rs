@@ -0,0 +1,1028 @@
+//! Serialization and deserialization of Rust values as Wolfram Language expressions
+//! using [serde](https://serde.rs).
+//!
+//! This module is only available when the `serde` feature is enabled.
+//!
+//! [`Serializer`] writes values to a link, and [`Deserializer`] reads them back, using
+//! the following representations:
+//!
+//! | serde data model              | Wolfram Language expression          |
+//! |-------------------------------|--------------------------------------|
+//! | `bool`                        | `True` or `False`                    |
+//! | integers                      | `Integer`                            |
+//! | `f32`, `f64`                  | `Real`                               |
+//! | `char`, string                | `String`                             |
+//! | byte array                    | `List[byte, ...]`                    |
+//! | `None`, unit, unit struct     | `Null`                               |
+//! | `Some(value)`, newtype struct | `value`                              |
+//! | sequence, tuple, tuple struct | `List[...]`                          |
+//! | map                           | `Association[key -> value, ...]`     |
+//! | struct                        | `Association["field" -> value, ...]` |
+//! | unit variant                  | `Variant`                            |
+//! | newtype variant               | `Variant[value]`                     |
+//! | tuple variant                 | `Variant[value, ...]`                |
+//! | struct variant                | `Variant[Association[...]]`          |
+//!
+//! The symbols used for enum variants are in the `` Global` `` context.
+//!
+//! Because WSTP requires the number of elements of an expression to be known before
+//! the elements are written, serializing sequences and maps of unknown length is not
+//! supported.
+//!
+//! When deserializing, the context of the symbol naming an enum variant is ignored, and
+//! `RuleDelayed` is accepted wherever `Rule` is. Integers are also accepted where a
+//! real number is expected.
+
+use std::fmt::Display;
+
+use ::serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use ::serde::ser::{self, Serialize};
+
+use crate::{Error, Link, TokenType, MAX_EXPR_DEPTH};
+
+/// Serializer that writes values to a [`Link`] as Wolfram Language expressions.
+///
+/// See the [module documentation][self] for how values are represented.
+///
+/// # Example
+///
+/// ```
+/// use serde::Serialize;
+/// use wstp::Link;
+///
+/// #[derive(Serialize)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// wstp::serde::to_link(&mut link, &vec![Point { x: 1.0, y: 2.0 }]).unwrap();
+///
+/// // {<| "x" -> 1.0, "y" -> 2.0 |>}
+/// let expr = link.get_expr().unwrap();
+/// ```
+pub struct Serializer<'link> {
+    link: &'link mut Link,
+}
+
+/// Write `value` to `link` as a Wolfram Language expression.
+pub fn to_link<T: Serialize + ?Sized>(link: &mut Link, value: &T) -> Result<(), Error> {
+    value.serialize(&mut Serializer::new(link))
+}
+
+impl<'link> Serializer<'link> {
+    /// Construct a serializer that writes to `link`.
+    pub fn new(link: &'link mut Link) -> Self {
+        Serializer { link }
+    }
+
+    fn put_variant_head(&mut self, variant: &str, len: usize) -> Result<(), Error> {
+        self.link
+            .put_function(format!("Global`{}", variant).as_str(), len)
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(message: T) -> Self {
+        Error::custom(message.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(message: T) -> Self {
+        Error::custom(message.to_string())
+    }
+}
+
+fn required_len(len: Option<usize>, kind: &str) -> Result<usize, Error> {
+    len.ok_or_else(|| {
+        Error::custom(format!(
+            "serializing a {} of unknown length to a WSTP link is not supported",
+            kind
+        ))
+    })
+}
+
+impl<'a, 'link> ser::Serializer for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        self.link
+            .put_symbol(if value { "System`True" } else { "System`False" })
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        self.link.put_i64(i64::from(value))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        self.link.put_i64(i64::from(value))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        self.link.put_i64(i64::from(value))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        self.link.put_i64(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.link.put_i64(value),
+            Err(_) => self.link.put_bigint_str(&value.to_string()),
+        }
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        self.link.put_i64(i64::from(value))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        self.link.put_i64(i64::from(value))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        self.link.put_i64(i64::from(value))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.link.put_i64(value),
+            Err(_) => self.link.put_bigint_str(&value.to_string()),
+        }
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.link.put_i64(value),
+            Err(_) => self.link.put_bigint_str(&value.to_string()),
+        }
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        self.link.put_f64(f64::from(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        self.link.put_f64(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        self.link.put_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        self.link.put_str(value)
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        self.link
+            .put_list(value, |link, byte| link.put_i64(i64::from(*byte)))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.link.put_symbol("System`Null")
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.link.put_symbol("System`Null")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.link.put_symbol(&format!("Global`{}", variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.put_variant_head(variant, 1)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+        let len = required_len(len, "sequence")?;
+        self.link.put_function("System`List", len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, Error> {
+        self.link.put_function("System`List", len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self, Error> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self, Error> {
+        self.put_variant_head(variant, len)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, Error> {
+        let len = required_len(len, "map")?;
+        self.link.put_function("System`Association", len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self, Error> {
+        self.link.put_function("System`Association", len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self, Error> {
+        self.put_variant_head(variant, 1)?;
+        self.link.put_function("System`Association", len)?;
+        Ok(self)
+    }
+}
+
+impl<'a, 'link> ser::SerializeSeq for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'link> ser::SerializeTuple for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'link> ser::SerializeTupleStruct for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'link> ser::SerializeTupleVariant for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'link> ser::SerializeMap for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.link.put_function("System`Rule", 2)?;
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'link> ser::SerializeStruct for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.link.put_function("System`Rule", 2)?;
+        self.link.put_str(key)?;
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'link> ser::SerializeStructVariant for &'a mut Serializer<'link> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.link.put_function("System`Rule", 2)?;
+        self.link.put_str(key)?;
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+//======================================
+// Deserializer
+//======================================
+
+/// Deserializer that reads values from a [`Link`].
+///
+/// See the [module documentation][self] for the expressions each type is read from.
+///
+/// If the expression on the link does not have the shape expected by the type being
+/// deserialized, the returned error describes both the expected shape and the
+/// expression that was found.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use wstp::Link;
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     Polygon(Vec<(f64, f64)>),
+/// }
+///
+/// let shapes = vec![
+///     Shape::Circle { radius: 1.0 },
+///     Shape::Polygon(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]),
+/// ];
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// wstp::serde::to_link(&mut link, &shapes).unwrap();
+///
+/// let read: Vec<Shape> = wstp::serde::from_link(&mut link).unwrap();
+///
+/// assert_eq!(read, shapes);
+/// ```
+pub struct Deserializer<'link> {
+    link: &'link mut Link,
+    /// Number of function expressions currently being read, used to limit the nesting
+    /// depth of the expression in the same way as [`Link::get_expr()`].
+    depth: usize,
+}
+
+/// Read a value of type `T` from the next expression on `link`.
+pub fn from_link<T: DeserializeOwned>(link: &mut Link) -> Result<T, Error> {
+    T::deserialize(&mut Deserializer::new(link))
+}
+
+impl<'link> Deserializer<'link> {
+    /// Construct a deserializer that reads from `link`.
+    pub fn new(link: &'link mut Link) -> Self {
+        Deserializer { link, depth: 0 }
+    }
+
+    /// Call `func` to read the arguments of a function expression, one level deeper
+    /// than the current expression.
+    fn nested<T, F>(&mut self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        if self.depth >= MAX_EXPR_DEPTH {
+            return Err(Error::custom(format!(
+                "expression read from link exceeds the maximum nesting depth of {}",
+                MAX_EXPR_DEPTH
+            )));
+        }
+
+        self.depth += 1;
+        let result = func(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Construct an error describing the next expression on the link, which does not
+    /// have the shape described by `expected`.
+    fn unexpected(&mut self, expected: &str) -> Error {
+        let found = match self.link.get_type() {
+            Ok(TokenType::Integer) => "Integer".to_owned(),
+            Ok(TokenType::Real) => "Real".to_owned(),
+            Ok(TokenType::String) => "String".to_owned(),
+            Ok(TokenType::Symbol) => self
+                .link
+                .peek(|link| match link.get_symbol_ref() {
+                    Ok(symbol) => format!("symbol {}", symbol.as_str()),
+                    Err(_) => "symbol".to_owned(),
+                })
+                .unwrap_or_else(|_| "symbol".to_owned()),
+            Ok(TokenType::Function) => self
+                .link
+                .peek(|link| {
+                    let length = link.get_arg_count()?;
+                    let head = match link.get_type()? {
+                        TokenType::Symbol => link.get_symbol_ref()?.as_str().to_owned(),
+                        _ => "function".to_owned(),
+                    };
+                    Ok::<_, Error>(format!(
+                        "{} expression with {} argument(s)",
+                        head, length
+                    ))
+                })
+                .and_then(|result| result)
+                .unwrap_or_else(|_| "function expression".to_owned()),
+            Err(error) => return error,
+        };
+
+        Error::custom(format!("expected {}, got {}", expected, found))
+    }
+
+    /// Returns `true` if the next expression on the link is the symbol `symbol`.
+    fn is_symbol(&mut self, symbol: &str) -> Result<bool, Error> {
+        if self.link.get_type()? != TokenType::Symbol {
+            return Ok(false);
+        }
+
+        self.link.peek(|link| match link.get_symbol_ref() {
+            Ok(name) => name.as_str() == symbol,
+            Err(_) => false,
+        })
+    }
+
+    /// Read the head of a function expression with one of `heads` as its head, and
+    /// return its number of arguments.
+    ///
+    /// Nothing is read from the link if the next expression does not have that shape.
+    fn expect_head(&mut self, heads: &[&str], expected: &str) -> Result<usize, Error> {
+        let matches = self.link.get_type()? == TokenType::Function
+            && self
+                .link
+                .peek(|link| link.expect_normal_with_head(heads).is_ok())?;
+
+        if !matches {
+            return Err(self.unexpected(expected));
+        }
+
+        self.link.expect_normal_with_head(heads)
+    }
+
+    fn expect_type(&mut self, type_: TokenType, expected: &str) -> Result<(), Error> {
+        if self.link.get_type()? != type_ {
+            return Err(self.unexpected(expected));
+        }
+
+        Ok(())
+    }
+
+    /// Read an integer, and convert it to `T`.
+    fn get_integer<T>(&mut self, type_name: &str) -> Result<T, Error>
+    where
+        T: std::str::FromStr,
+    {
+        self.expect_type(TokenType::Integer, "Integer")?;
+
+        let digits = self.link.get_bigint_string()?;
+
+        digits.parse().map_err(|_| {
+            Error::custom(format!(
+                "integer {} read from link does not fit in {}",
+                digits, type_name
+            ))
+        })
+    }
+
+    fn get_real(&mut self) -> Result<f64, Error> {
+        match self.link.get_type()? {
+            TokenType::Integer | TokenType::Real => self.link.get_f64(),
+            _ => Err(self.unexpected("Real")),
+        }
+    }
+
+    fn get_string(&mut self) -> Result<String, Error> {
+        self.expect_type(TokenType::String, "String")?;
+
+        self.link.get_string()
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($type:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let value: $type = self.get_integer(stringify!($type))?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, 'link> de::Deserializer<'de> for &'a mut Deserializer<'link> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.link.get_type()? {
+            TokenType::Integer => visitor.visit_i64(self.link.get_i64()?),
+            TokenType::Real => visitor.visit_f64(self.link.get_f64()?),
+            TokenType::String => visitor.visit_string(self.link.get_string()?),
+            TokenType::Symbol => {
+                let symbol = self.link.get_symbol()?;
+
+                match symbol.as_str() {
+                    "System`True" => visitor.visit_bool(true),
+                    "System`False" => visitor.visit_bool(false),
+                    "System`Null" => visitor.visit_unit(),
+                    other => visitor.visit_str(other),
+                }
+            },
+            TokenType::Function => {
+                if let Ok(length) = self.expect_head(&["System`List"], "List") {
+                    visit_seq(self, length, visitor)
+                } else if let Ok(length) =
+                    self.expect_head(&["System`Association"], "Association")
+                {
+                    visit_map(self, length, visitor)
+                } else {
+                    Err(self.unexpected("List or Association"))
+                }
+            },
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.is_symbol("System`True")? {
+            self.link.get_symbol()?;
+            visitor.visit_bool(true)
+        } else if self.is_symbol("System`False")? {
+            self.link.get_symbol()?;
+            visitor.visit_bool(false)
+        } else {
+            Err(self.unexpected("True or False"))
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f32(self.get_real()? as f32)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f64(self.get_real()?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let string = self.get_string()?;
+        let mut chars = string.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(char), None) => visitor.visit_char(char),
+            _ => Err(Error::custom(format!(
+                "expected String of length 1, got String {:?}",
+                string
+            ))),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.get_string()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.get_string()?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let length = self.expect_head(&["System`List"], "List of bytes")?;
+
+        let bytes = (0..length)
+            .map(|_| self.get_integer::<u8>("u8"))
+            .collect::<Result<Vec<u8>, Error>>()?;
+
+        visitor.visit_byte_buf(bytes)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.is_symbol("System`Null")? {
+            self.link.get_symbol()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if !self.is_symbol("System`Null")? {
+            return Err(self.unexpected("Null"));
+        }
+
+        self.link.get_symbol()?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let length = self.expect_head(&["System`List"], "List")?;
+
+        visit_seq(self, length, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let expected = format!("List of length {}", len);
+        let length = self.expect_head(&["System`List"], &expected)?;
+
+        if length != len {
+            return Err(Error::custom(format!(
+                "expected {}, got List of length {}",
+                expected, length
+            )));
+        }
+
+        visit_seq(self, length, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let length = self.expect_head(&["System`Association"], "Association")?;
+
+        visit_map(self, length, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let length = match self.link.get_type()? {
+            TokenType::Symbol => None,
+            TokenType::Function => {
+                let length = self.link.get_arg_count()?;
+                self.expect_type(TokenType::Symbol, "enum variant symbol")?;
+                Some(length)
+            },
+            _ => return Err(self.unexpected("enum variant")),
+        };
+
+        let symbol = self.link.get_symbol()?;
+
+        let variant = match symbol.as_str().rfind('`') {
+            Some(index) => &symbol.as_str()[index + 1..],
+            None => symbol.as_str(),
+        };
+
+        let variant = variant.to_owned();
+
+        if length.is_none() {
+            return visitor.visit_enum(Enum {
+                de: self,
+                variant,
+                length,
+            });
+        }
+
+        self.nested(|de| {
+            visitor.visit_enum(Enum {
+                de,
+                variant,
+                length,
+            })
+        })
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.link.skip_expr()?;
+        visitor.visit_unit()
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(
+    de: &mut Deserializer,
+    length: usize,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    de.nested(|de| {
+        let mut seq = Elements {
+            de,
+            remaining: length,
+        };
+
+        let value = visitor.visit_seq(&mut seq)?;
+
+        if seq.remaining != 0 {
+            return Err(Error::custom(format!(
+                "List of length {} has {} unexpected trailing element(s)",
+                length, seq.remaining
+            )));
+        }
+
+        Ok(value)
+    })
+}
+
+fn visit_map<'de, V: Visitor<'de>>(
+    de: &mut Deserializer,
+    length: usize,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    de.nested(|de| {
+        let mut map = Elements {
+            de,
+            remaining: length,
+        };
+
+        let value = visitor.visit_map(&mut map)?;
+
+        if map.remaining != 0 {
+            return Err(Error::custom(format!(
+                "Association of length {} has {} unexpected trailing rule(s)",
+                length, map.remaining
+            )));
+        }
+
+        Ok(value)
+    })
+}
+
+/// Access to the elements of a `List` or the rules of an `Association`.
+struct Elements<'a, 'link> {
+    de: &'a mut Deserializer<'link>,
+    remaining: usize,
+}
+
+impl<'de, 'a, 'link> de::SeqAccess<'de> for Elements<'a, 'link> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, 'a, 'link> de::MapAccess<'de> for Elements<'a, 'link> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+
+        let length = self
+            .de
+            .expect_head(&["System`Rule", "System`RuleDelayed"], "key -> value rule")?;
+
+        if length != 2 {
+            return Err(Error::custom(format!(
+                "expected key -> value rule, got rule with {} argument(s)",
+                length
+            )));
+        }
+
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// Access to an enum variant: either a symbol, or a function expression whose head is
+/// the variant symbol.
+struct Enum<'a, 'link> {
+    de: &'a mut Deserializer<'link>,
+    variant: String,
+    /// Number of arguments, or `None` if the variant is a symbol.
+    length: Option<usize>,
+}
+
+impl<'a, 'link> Enum<'a, 'link> {
+    fn expect_length(&self, expected: usize) -> Result<(), Error> {
+        let length = self.length.unwrap_or(0);
+
+        if length != expected {
+            return Err(Error::custom(format!(
+                "expected enum variant {} with {} argument(s), got {} argument(s)",
+                self.variant, expected, length
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'a, 'link> de::EnumAccess<'de> for Enum<'a, 'link> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), Error> {
+        let variant = seed.deserialize(self.variant.as_str().into_deserializer())?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a, 'link> de::VariantAccess<'de> for Enum<'a, 'link> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        self.expect_length(0)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Error> {
+        self.expect_length(1)?;
+
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.expect_length(len)?;
+
+        visitor.visit_seq(Elements {
+            de: self.de,
+            remaining: len,
+        })
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.expect_length(1)?;
+
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
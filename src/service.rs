@@ -0,0 +1,150 @@
+//! Typed request/response services defined on top of [`RpcLink`].
+
+use std::fmt::{self, Display};
+
+use wolfram_expr::{Expr, ExprKind, Symbol};
+
+use crate::{kernel::WolframFailure, Error, RpcLink};
+
+/// Handler for the requests received by [`RpcLink::serve()`].
+///
+/// A request to call `method` with arguments `args` is written as the expression
+/// `method[args...]`, where `method` is a string, e.g. `"add"[1, 2]`. The response is
+/// the value returned by the method, or a
+/// [`Failure["ServiceError", <|"MessageTemplate" -> message|>]`](https://reference.wolfram.com/language/ref/Failure.html)
+/// expression if the method returned an error. Requests and responses are framed as
+/// described in [`RpcLink`], so services can be implemented or called from the Wolfram
+/// Language as well as from Rust.
+///
+/// This trait is usually implemented by the server type generated by the
+/// [`wstp_service!`][crate::wstp_service] macro, which converts arguments and return
+/// values using [`GetFromLink`][crate::GetFromLink] and
+/// [`PutOnLink`][crate::PutOnLink]. It can also be implemented directly to handle
+/// requests as untyped expressions.
+pub trait Service {
+    /// Handle a call to `method` with arguments `args`, returning the response.
+    fn call(&mut self, method: &str, args: Vec<Expr>) -> Result<Expr, ServiceError>;
+}
+
+/// Error returned by a [`Service`] method, which is sent to the client as a `Failure`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceError {
+    message: String,
+}
+
+/// Failure tag used for responses to requests that failed.
+const SERVICE_ERROR_TAG: &str = "ServiceError";
+
+/// # Services
+impl RpcLink {
+    /// Handle requests received on this link using `service`, until the peer closes the
+    /// link.
+    ///
+    /// Returns `Ok(())` once the peer has closed the link, or the error that prevented a
+    /// request from being received or a response from being sent. Errors returned by
+    /// `service` are sent to the client, and do not stop this function.
+    pub fn serve<S: Service + ?Sized>(&mut self, service: &mut S) -> Result<(), Error> {
+        loop {
+            let (id, request) = match self.recv_request() {
+                Ok(request) => request,
+                Err(err) if err.is_closed_by_peer() => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            let response = match split_request(request) {
+                Ok((method, args)) => service.call(&method, args),
+                Err(err) => Err(err),
+            };
+
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => err.to_failure(),
+            };
+
+            self.send_response(id, &response)?;
+        }
+    }
+
+    /// Call `method` of the [`Service`] served by the peer, with arguments `args`, and
+    /// wait for the response.
+    ///
+    /// Returns an error if the method failed.
+    pub fn call_method(&mut self, method: &str, args: Vec<Expr>) -> Result<Expr, Error> {
+        let request = Expr::normal(Expr::string(method), args);
+
+        let response = self.call(&request)?;
+
+        match WolframFailure::from_expr(&response) {
+            Some(WolframFailure::Failure {
+                tag: Some(ref tag),
+                ref message_template,
+                expr: _,
+            }) if tag == SERVICE_ERROR_TAG => Err(Error::custom(format!(
+                "service method '{}' failed: {}",
+                method,
+                message_template.as_deref().unwrap_or("unknown error")
+            ))),
+            _ => Ok(response),
+        }
+    }
+}
+
+impl ServiceError {
+    /// Construct a new error with the given message.
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        ServiceError {
+            message: message.into(),
+        }
+    }
+
+    /// Get the message of this error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn to_failure(&self) -> Expr {
+        let message = Expr::normal(
+            Symbol::new("System`Rule"),
+            vec![
+                Expr::string("MessageTemplate"),
+                Expr::string(self.message.as_str()),
+            ],
+        );
+
+        Expr::normal(
+            Symbol::new("System`Failure"),
+            vec![
+                Expr::string(SERVICE_ERROR_TAG),
+                Expr::normal(Symbol::new("System`Association"), vec![message]),
+            ],
+        )
+    }
+}
+
+impl Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+impl From<Error> for ServiceError {
+    fn from(err: Error) -> Self {
+        ServiceError::new(err.to_string())
+    }
+}
+
+/// Split a `method[args...]` request into the method name and arguments.
+fn split_request(request: Expr) -> Result<(String, Vec<Expr>), ServiceError> {
+    if let ExprKind::Normal(normal) = request.kind() {
+        if let ExprKind::String(method) = normal.head().kind() {
+            return Ok((method.clone(), normal.elements().to_vec()));
+        }
+    }
+
+    Err(ServiceError::new(format!(
+        "malformed service request: expected method[args...], got: {}",
+        request
+    )))
+}
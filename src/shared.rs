@@ -0,0 +1,416 @@
+//! [`SharedLink`], a [`Link`] that can be used from multiple threads at once.
+
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::sync::{Mutex, MutexGuard};
+
+use wolfram_expr::{Expr, Symbol};
+
+use crate::{
+    sys, Array, Error, ExprVisitor, Link, LinkStr, Packet, StringChunks, Token, Tokens,
+    Ucs2Str, Utf16Str, Utf32Str, Utf8Str,
+};
+
+/// A [`Link`] that can be shared between threads.
+///
+/// [`SharedLink::new()`] calls [`WSEnableLinkLock()`][sys::WSEnableLinkLock] on the
+/// link, so that WSTP serializes the calls made on it from multiple threads. This makes
+/// [`SharedLink`] [`Sync`], and its methods take `&self`.
+///
+/// WSTP only serializes individual calls. Reading or writing a complete expression
+/// usually requires several calls, so [`SharedLink::get_expr()`] and
+/// [`SharedLink::put_expr()`] additionally hold a read or write lock for the duration
+/// of the operation, which prevents the tokens of concurrently read or written
+/// expressions from being interleaved. Use [`SharedLink::with_read()`] and
+/// [`SharedLink::with_write()`] to perform other multi-call operations while holding
+/// the same locks.
+///
+/// [`SharedLink`] never hands out a `&mut Link`, because the underlying link is owned
+/// by the [`SharedLink`] and must not be closed or replaced while other threads may be
+/// using it. Instead, [`SharedLink::with_read()`] and [`SharedLink::with_write()`] pass
+/// a [`SharedLinkGuard`], which provides the get and put methods of [`Link`].
+///
+/// Because calls on a locked link are serialized, a read that blocks waiting for data
+/// to arrive may delay a concurrent write. Use [`SharedLink::is_ready()`] to avoid
+/// blocking when the peer may be waiting on data written from another thread.
+///
+/// # Scoped threads
+///
+/// Because [`SharedLink`] is [`Sync`], a `&SharedLink` can be used by threads spawned
+/// with [`std::thread::scope()`], without wrapping it in an [`Arc`][std::sync::Arc].
+/// Each thread writes complete expressions using [`SharedLink::with_write()`] or
+/// [`SharedLink::put_expr()`], so expressions written by different threads are
+/// interleaved only as a whole:
+///
+/// ```
+/// use wstp::{Link, SharedLink};
+///
+/// let link = SharedLink::new(Link::new_loopback().unwrap());
+///
+/// std::thread::scope(|scope| {
+///     for worker in 0..4 {
+///         let link = &link;
+///
+///         scope.spawn(move || {
+///             link.with_write(|link| {
+///                 link.put_function("System`List", 2)?;
+///                 link.put_i64(worker)?;
+///                 link.put_i64(worker * 10)
+///             })
+///             .unwrap();
+///         });
+///     }
+/// });
+///
+/// for _ in 0..4 {
+///     link.with_read(|link| {
+///         assert_eq!(link.test_head("System`List").unwrap(), 2);
+///         let worker = link.get_i64().unwrap();
+///         assert_eq!(link.get_i64().unwrap(), worker * 10);
+///     });
+/// }
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use wstp::{Link, SharedLink};
+/// use wolfram_expr::Expr;
+///
+/// let link = Arc::new(SharedLink::new(Link::new_loopback().unwrap()));
+///
+/// let writer = {
+///     let link = Arc::clone(&link);
+///     std::thread::spawn(move || {
+///         link.put_expr(&Expr::from(5)).unwrap();
+///         link.flush().unwrap();
+///     })
+/// };
+///
+/// writer.join().unwrap();
+///
+/// assert_eq!(link.get_expr().unwrap(), Expr::from(5));
+/// ```
+///
+/// *WSTP C API Documentation:* [`WSEnableLinkLock()`](https://reference.wolfram.com/language/ref/c/WSEnableLinkLock.html)
+#[derive(Debug)]
+pub struct SharedLink {
+    link: Link,
+    read_lock: Mutex<()>,
+    write_lock: Mutex<()>,
+}
+
+// SAFETY: `WSEnableLinkLock()` is called on the link when the `SharedLink` is
+//         constructed, so WSTP serializes calls made on the link from multiple threads.
+unsafe impl Sync for SharedLink {}
+
+impl SharedLink {
+    /// Construct a new [`SharedLink`], enabling link locking on `link`.
+    ///
+    /// *WSTP C API Documentation:* [`WSEnableLinkLock()`](https://reference.wolfram.com/language/ref/c/WSEnableLinkLock.html)
+    pub fn new(link: Link) -> Self {
+        unsafe { sys::WSEnableLinkLock(link.raw_link) };
+
+        SharedLink {
+            link,
+            read_lock: Mutex::new(()),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Convert this value into the underlying [`Link`].
+    ///
+    /// Link locking remains enabled on the returned [`Link`].
+    pub fn into_inner(self) -> Link {
+        self.link
+    }
+
+    /// Get the underlying [`Link`].
+    pub fn link(&self) -> &Link {
+        &self.link
+    }
+
+    /// Call `func` with the underlying link while holding the read lock.
+    ///
+    /// Other calls to [`SharedLink::with_read()`] and [`SharedLink::get_expr()`] block
+    /// until `func` returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, SharedLink};
+    ///
+    /// let link = SharedLink::new(Link::new_loopback().unwrap());
+    ///
+    /// link.with_write(|link| {
+    ///     link.put_function("System`List", 2)?;
+    ///     link.put_i64(1)?;
+    ///     link.put_i64(2)
+    /// })
+    /// .unwrap();
+    ///
+    /// let sum = link
+    ///     .with_read(|link| {
+    ///         let len = link.test_head("System`List")?;
+    ///         (0..len).map(|_| link.get_i64()).sum::<Result<i64, _>>()
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(sum, 3);
+    /// ```
+    pub fn with_read<T, F>(&self, func: F) -> T
+    where
+        F: FnOnce(&mut SharedLinkGuard) -> T,
+    {
+        func(&mut self.guard(Some(lock(&self.read_lock)), None))
+    }
+
+    /// Call `func` with the underlying link while holding the write lock.
+    ///
+    /// Other calls to [`SharedLink::with_write()`] and [`SharedLink::put_expr()`] block
+    /// until `func` returns.
+    ///
+    /// See [`SharedLink::with_read()`] for an example.
+    pub fn with_write<T, F>(&self, func: F) -> T
+    where
+        F: FnOnce(&mut SharedLinkGuard) -> T,
+    {
+        func(&mut self.guard(None, Some(lock(&self.write_lock))))
+    }
+
+    /// Call `func` with the underlying link while holding both the read and write
+    /// locks.
+    ///
+    /// Use this for exchanges that write and then read, such as sending a request and
+    /// reading its response, so that no other thread can write or read in between.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, SharedLink};
+    ///
+    /// let link = SharedLink::new(Link::new_loopback().unwrap());
+    ///
+    /// let echoed = link
+    ///     .with(|link| {
+    ///         link.put_i64(42)?;
+    ///         link.get_i64()
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(echoed, 42);
+    /// ```
+    pub fn with<T, F>(&self, func: F) -> T
+    where
+        F: FnOnce(&mut SharedLinkGuard) -> T,
+    {
+        // Note: The read lock is always acquired before the write lock, so that
+        //       concurrent calls to this function cannot deadlock.
+        let read_lock = lock(&self.read_lock);
+        let write_lock = lock(&self.write_lock);
+
+        func(&mut self.guard(Some(read_lock), Some(write_lock)))
+    }
+
+    /// See [`Link::is_ready()`].
+    pub fn is_ready(&self) -> bool {
+        self.link.is_ready()
+    }
+
+    /// See [`Link::error()`].
+    pub fn error(&self) -> Option<Error> {
+        self.link.error()
+    }
+
+    /// See [`Link::link_name()`].
+    pub fn link_name(&self) -> String {
+        self.link.link_name()
+    }
+
+    /// See [`Link::wait()`].
+    pub fn wait(&self) -> Result<(), Error> {
+        self.guard(None, None).link.wait()
+    }
+
+    /// See [`Link::raw_next_packet()`].
+    pub fn raw_next_packet(&self) -> Result<i32, Error> {
+        self.with_read(|link| link.raw_next_packet())
+    }
+
+    /// See [`Link::new_packet()`].
+    pub fn new_packet(&self) -> Result<(), Error> {
+        self.with_read(|link| link.new_packet())
+    }
+
+    /// See [`Link::get_expr()`].
+    pub fn get_expr(&self) -> Result<Expr, Error> {
+        self.with_read(|link| link.get_expr())
+    }
+
+    /// See [`Link::put_expr()`].
+    pub fn put_expr(&self, expr: &Expr) -> Result<(), Error> {
+        self.with_write(|link| link.put_expr(expr))
+    }
+
+    /// See [`Link::end_packet()`].
+    pub fn end_packet(&self) -> Result<(), Error> {
+        self.with_write(|link| link.end_packet())
+    }
+
+    /// See [`Link::flush()`].
+    pub fn flush(&self) -> Result<(), Error> {
+        self.guard(None, None).link.flush()
+    }
+
+    fn guard<'a>(
+        &'a self,
+        read_lock: Option<MutexGuard<'a, ()>>,
+        write_lock: Option<MutexGuard<'a, ()>>,
+    ) -> SharedLinkGuard<'a> {
+        // Note: Each guard uses its own non-owning `Link` value, so that the `&mut Link`
+        //       references used by concurrent callers never alias each other.
+        let link = ManuallyDrop::new(unsafe { Link::unchecked_new(self.link.raw_link) });
+
+        SharedLinkGuard {
+            link,
+            _read_lock: read_lock,
+            _write_lock: write_lock,
+        }
+    }
+}
+
+/// Access to the link of a [`SharedLink`] while holding its read or write lock, or
+/// both.
+///
+/// See [`SharedLink::with_read()`], [`SharedLink::with_write()`], and
+/// [`SharedLink::with()`].
+///
+/// [`SharedLinkGuard`] dereferences to `&Link`, and provides the methods of [`Link`]
+/// that read and write expressions. It does not provide the methods that replace or
+/// close the link, nor the generic [`Link::get()`] and [`Link::put()`] methods, which
+/// pass a `&mut Link` to user-defined trait implementations.
+#[derive(Debug)]
+pub struct SharedLinkGuard<'a> {
+    /// Non-owning copy of the [`SharedLink`]'s link. It is never dropped, and is never
+    /// exposed as a `&mut Link`, so it cannot be closed or replaced.
+    link: ManuallyDrop<Link>,
+    _read_lock: Option<MutexGuard<'a, ()>>,
+    _write_lock: Option<MutexGuard<'a, ()>>,
+}
+
+impl Deref for SharedLinkGuard<'_> {
+    type Target = Link;
+
+    fn deref(&self) -> &Link {
+        &self.link
+    }
+}
+
+macro_rules! forward_to_link {
+    ($($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!("See [`Link::", stringify!($name), "()`].")]
+            pub fn $name(&mut self, $($arg: $ty),*) -> $ret {
+                self.link.$name($($arg),*)
+            }
+        )*
+    };
+}
+
+impl SharedLinkGuard<'_> {
+    forward_to_link! {
+        raw_get_next() -> Result<i32, Error>;
+        raw_next_packet() -> Result<i32, Error>;
+        next_packet() -> Result<Packet, Error>;
+        new_packet() -> Result<(), Error>;
+        end_packet() -> Result<(), Error>;
+        flush() -> Result<(), Error>;
+        wait() -> Result<(), Error>;
+
+        get_expr() -> Result<Expr, Error>;
+        try_get_expr() -> Result<Option<Expr>, Error>;
+        skip_expr() -> Result<(), Error>;
+        transfer_expr_to(dest: &mut Link) -> Result<(), Error>;
+        put_expr(expr: &Expr) -> Result<(), Error>;
+
+        get_token() -> Result<Token<'_>, Error>;
+        tokens() -> Tokens<'_>;
+        get_string_ref() -> Result<LinkStr<'_, str>, Error>;
+        get_string() -> Result<String, Error>;
+        get_symbol_ref() -> Result<LinkStr<'_, str>, Error>;
+        get_symbol() -> Result<Symbol, Error>;
+        get_utf8_str() -> Result<LinkStr<'_, Utf8Str>, Error>;
+        get_utf16_str() -> Result<LinkStr<'_, Utf16Str>, Error>;
+        get_ucs2_str() -> Result<LinkStr<'_, Ucs2Str>, Error>;
+        get_utf32_str() -> Result<LinkStr<'_, Utf32Str>, Error>;
+        get_byte_string(missing: u8) -> Result<Vec<u8>, Error>;
+        get_string_chunks(chunk_size: usize) -> Result<StringChunks<'_>, Error>;
+        test_head(symbol: &str) -> Result<usize, Error>;
+        get_arg_count() -> Result<usize, Error>;
+        get_i64() -> Result<i64, Error>;
+        get_i32() -> Result<i32, Error>;
+        get_i16() -> Result<i16, Error>;
+        get_u8() -> Result<u8, Error>;
+        get_f64() -> Result<f64, Error>;
+        get_f32() -> Result<f32, Error>;
+        get_i64_array() -> Result<Array<i64>, Error>;
+        get_i32_array() -> Result<Array<i32>, Error>;
+        get_i16_array() -> Result<Array<i16>, Error>;
+        get_u8_array() -> Result<Array<u8>, Error>;
+        get_f64_array() -> Result<Array<f64>, Error>;
+        get_f32_array() -> Result<Array<f32>, Error>;
+
+        put_str(string: &str) -> Result<(), Error>;
+        put_symbol(symbol: &str) -> Result<(), Error>;
+        put_utf8_str(utf8: &[u8]) -> Result<(), Error>;
+        put_utf16_str(utf16: &[u16]) -> Result<(), Error>;
+        put_ucs2_str(ucs2: &[u16]) -> Result<(), Error>;
+        put_utf32_str(utf32: &[u32]) -> Result<(), Error>;
+        put_byte_string(bytes: &[u8]) -> Result<(), Error>;
+        put_arg_count(count: usize) -> Result<(), Error>;
+        put_i64(value: i64) -> Result<(), Error>;
+        put_i32(value: i32) -> Result<(), Error>;
+        put_i16(value: i16) -> Result<(), Error>;
+        put_u8(value: u8) -> Result<(), Error>;
+        put_f64(value: f64) -> Result<(), Error>;
+        put_f32(value: f32) -> Result<(), Error>;
+        put_i64_array(data: &[i64], dimensions: &[usize]) -> Result<(), Error>;
+        put_i32_array(data: &[i32], dimensions: &[usize]) -> Result<(), Error>;
+        put_i16_array(data: &[i16], dimensions: &[usize]) -> Result<(), Error>;
+        put_u8_array(data: &[u8], dimensions: &[usize]) -> Result<(), Error>;
+        put_f64_array(data: &[f64], dimensions: &[usize]) -> Result<(), Error>;
+        put_f32_array(data: &[f32], dimensions: &[usize]) -> Result<(), Error>;
+    }
+
+    /// See [`Link::put_function()`].
+    pub fn put_function<'h, H: Into<Option<&'h str>>>(
+        &mut self,
+        head: H,
+        count: usize,
+    ) -> Result<(), Error> {
+        self.link.put_function(head, count)
+    }
+
+    /// See [`Link::parse_expr()`].
+    pub fn parse_expr<V: ExprVisitor + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> Result<(), Error> {
+        self.link.parse_expr(visitor)
+    }
+}
+
+impl From<Link> for SharedLink {
+    fn from(link: Link) -> Self {
+        SharedLink::new(link)
+    }
+}
+
+/// Acquire `mutex`, ignoring poisoning. The lock guards no data of its own.
+fn lock(mutex: &Mutex<()>) -> MutexGuard<()> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
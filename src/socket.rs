@@ -0,0 +1,202 @@
+//! Access to the socket underlying a [`TCPIP`][crate::Protocol::TCPIP] link.
+
+use std::mem::ManuallyDrop;
+use std::net::{SocketAddr, TcpStream};
+use std::os::raw::c_long;
+
+use socket2::SockRef;
+
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{FromRawSocket, RawSocket};
+
+use crate::{sys, Error, Link};
+
+/// # Socket options
+impl Link {
+    /// Get the file descriptor of the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    ///
+    /// The returned file descriptor is owned by the link, and must not be closed. It can
+    /// be used to configure socket options that are not exposed by this crate (for
+    /// example, using the [`socket2`](https://crates.io/crates/socket2) crate to set
+    /// `SO_LINGER`).
+    ///
+    /// *WSTP C API Documentation:* [`WSDeviceInformation()`](https://reference.wolfram.com/language/ref/c/WSDeviceInformation.html)
+    #[cfg(unix)]
+    pub fn socket_fd(&self) -> Result<RawFd, Error> {
+        let mut fd: std::os::raw::c_int = -1;
+
+        unsafe { self.device_information(sys::SOCKET_FD, &mut fd)? };
+
+        if fd < 0 {
+            return Err(Error::custom(format!(
+                "TCPIP link has invalid socket file descriptor: {}",
+                fd
+            )));
+        }
+
+        Ok(fd)
+    }
+
+    /// Get the socket underlying this [`TCPIP`][crate::Protocol::TCPIP] link.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    ///
+    /// The returned socket is owned by the link, and must not be closed. It can be used
+    /// to configure socket options that are not exposed by this crate (for example,
+    /// using the [`socket2`](https://crates.io/crates/socket2) crate to set
+    /// `SO_LINGER`).
+    ///
+    /// *WSTP C API Documentation:* [`WSDeviceInformation()`](https://reference.wolfram.com/language/ref/c/WSDeviceInformation.html)
+    #[cfg(windows)]
+    pub fn socket(&self) -> Result<RawSocket, Error> {
+        let mut socket: usize = !0;
+
+        unsafe { self.device_information(sys::SOCKET_FD, &mut socket)? };
+
+        // `INVALID_SOCKET`
+        if socket == !0 {
+            return Err(Error::custom(
+                "TCPIP link has invalid socket handle".to_owned(),
+            ));
+        }
+
+        Ok(socket as RawSocket)
+    }
+
+    /// Enable or disable Nagle's algorithm on the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link, by setting the `TCP_NODELAY` option.
+    ///
+    /// Disabling Nagle's algorithm (`nodelay = true`) reduces the latency of sending
+    /// small expressions, which is typically desirable for request/response protocols.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    pub fn set_tcp_nodelay(&mut self, nodelay: bool) -> Result<(), Error> {
+        self.with_tcp_stream(|stream| stream.set_nodelay(nodelay))
+    }
+
+    /// Returns the value of the `TCP_NODELAY` option on the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link.
+    ///
+    /// See [`Link::set_tcp_nodelay()`].
+    pub fn tcp_nodelay(&self) -> Result<bool, Error> {
+        self.with_tcp_stream(|stream| stream.nodelay())
+    }
+
+    /// Enable or disable TCP keepalive probes on the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link, by setting the `SO_KEEPALIVE` option.
+    ///
+    /// Keepalive probes allow a connection to a peer that has disappeared without
+    /// closing the connection to eventually be detected as dead.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    pub fn set_tcp_keepalive(&mut self, keepalive: bool) -> Result<(), Error> {
+        self.with_tcp_stream(|stream| SockRef::from(stream).set_keepalive(keepalive))
+    }
+
+    /// Returns the value of the `SO_KEEPALIVE` option on the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link.
+    ///
+    /// See [`Link::set_tcp_keepalive()`].
+    pub fn tcp_keepalive(&self) -> Result<bool, Error> {
+        self.with_tcp_stream(|stream| SockRef::from(stream).keepalive())
+    }
+
+    /// Set the size of the send buffer of the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link, by setting the `SO_SNDBUF` option.
+    ///
+    /// The operating system may adjust the requested size; use
+    /// [`Link::send_buffer_size()`] to get the size actually used.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    pub fn set_send_buffer_size(&mut self, size: usize) -> Result<(), Error> {
+        self.with_tcp_stream(|stream| SockRef::from(stream).set_send_buffer_size(size))
+    }
+
+    /// Returns the value of the `SO_SNDBUF` option on the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link.
+    pub fn send_buffer_size(&self) -> Result<usize, Error> {
+        self.with_tcp_stream(|stream| SockRef::from(stream).send_buffer_size())
+    }
+
+    /// Set the size of the receive buffer of the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link, by setting the `SO_RCVBUF` option.
+    ///
+    /// The operating system may adjust the requested size; use
+    /// [`Link::recv_buffer_size()`] to get the size actually used.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    pub fn set_recv_buffer_size(&mut self, size: usize) -> Result<(), Error> {
+        self.with_tcp_stream(|stream| SockRef::from(stream).set_recv_buffer_size(size))
+    }
+
+    /// Returns the value of the `SO_RCVBUF` option on the socket underlying this
+    /// [`TCPIP`][crate::Protocol::TCPIP] link.
+    pub fn recv_buffer_size(&self) -> Result<usize, Error> {
+        self.with_tcp_stream(|stream| SockRef::from(stream).recv_buffer_size())
+    }
+
+    /// Returns the address of the remote peer of this [`TCPIP`][crate::Protocol::TCPIP]
+    /// link.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    pub fn peer_addr(&self) -> Result<SocketAddr, Error> {
+        self.with_tcp_stream(|stream| stream.peer_addr())
+    }
+
+    /// Returns the local address of this [`TCPIP`][crate::Protocol::TCPIP] link.
+    ///
+    /// Returns an error if this is not a `TCPIP` link.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.with_tcp_stream(|stream| stream.local_addr())
+    }
+
+    /// Call `func` with a [`TcpStream`] that borrows the socket underlying this link.
+    fn with_tcp_stream<T, F>(&self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&TcpStream) -> std::io::Result<T>,
+    {
+        // Note: The stream must not be dropped, because the socket is owned by the link.
+        #[cfg(unix)]
+        let stream =
+            ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(self.socket_fd()?) });
+        #[cfg(windows)]
+        let stream =
+            ManuallyDrop::new(unsafe { TcpStream::from_raw_socket(self.socket()?) });
+
+        func(&stream).map_err(|err| {
+            Error::with_source(format!("error accessing TCPIP link socket: {}", err), err)
+        })
+    }
+
+    /// *WSTP C API Documentation:* [`WSDeviceInformation()`](https://reference.wolfram.com/language/ref/c/WSDeviceInformation.html)
+    pub(crate) unsafe fn device_information<T>(
+        &self,
+        selector: i64,
+        value: &mut T,
+    ) -> Result<(), Error> {
+        let mut len = std::mem::size_of::<T>() as c_long;
+
+        let result = sys::WSDeviceInformation(
+            self.raw_link,
+            selector as sys::devinfo_selector,
+            value as *mut T as *mut std::ffi::c_void,
+            &mut len,
+        );
+
+        if result == 0 {
+            return Err(self.error().unwrap_or_else(|| {
+                Error::custom(format!(
+                    "unable to get device information {:#x}; is this a TCPIP link?",
+                    selector
+                ))
+            }));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+//! Splitting a [`Link`] into a [`ReadHalf`] and a [`WriteHalf`] that can be used from
+//! different threads.
+
+use std::mem::ManuallyDrop;
+use std::sync::Arc;
+
+use wolfram_expr::Expr;
+
+use crate::{sys, Error, Link};
+
+/// Owner of the underlying link, shared by a [`ReadHalf`] and [`WriteHalf`].
+///
+/// The link is closed when both halves have been dropped.
+#[derive(Debug)]
+struct Shared {
+    link: Link,
+}
+
+// SAFETY: `WSEnableLinkLock()` is called on the link before it is shared, so WSTP
+//         serializes calls made on the link from multiple threads.
+unsafe impl Sync for Shared {}
+
+/// Reading half of a [`Link`], created by [`Link::split()`].
+#[derive(Debug)]
+pub struct ReadHalf {
+    link: ManuallyDrop<Link>,
+    shared: Arc<Shared>,
+}
+
+/// Writing half of a [`Link`], created by [`Link::split()`].
+#[derive(Debug)]
+pub struct WriteHalf {
+    link: ManuallyDrop<Link>,
+    shared: Arc<Shared>,
+}
+
+/// # Splitting
+impl Link {
+    /// Split this link into a [`ReadHalf`] and a [`WriteHalf`], which can be used
+    /// concurrently from different threads.
+    ///
+    /// This calls [`WSEnableLinkLock()`][sys::WSEnableLinkLock] on the link, so that
+    /// WSTP serializes the calls made through each half.
+    ///
+    /// Because calls on a locked link are serialized, a read that blocks waiting for
+    /// data to arrive may delay a concurrent write. Use [`ReadHalf::is_ready()`] to
+    /// avoid blocking when the peer may be waiting on data from the [`WriteHalf`].
+    ///
+    /// The link is closed when both halves have been dropped. Use
+    /// [`ReadHalf::reunite()`] to recover the original [`Link`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    /// use wolfram_expr::Expr;
+    ///
+    /// let (mut read, mut write) = Link::new_loopback().unwrap().split();
+    ///
+    /// let writer = std::thread::spawn(move || {
+    ///     write.put_expr(&Expr::from(5)).unwrap();
+    ///     write
+    /// });
+    ///
+    /// let write = writer.join().unwrap();
+    ///
+    /// assert_eq!(read.get_expr().unwrap(), Expr::from(5));
+    ///
+    /// let _link: Link = read.reunite(write).unwrap();
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSEnableLinkLock()`](https://reference.wolfram.com/language/ref/c/WSEnableLinkLock.html)
+    pub fn split(self) -> (ReadHalf, WriteHalf) {
+        let raw_link = self.raw_link;
+
+        unsafe { sys::WSEnableLinkLock(raw_link) };
+
+        let shared = Arc::new(Shared { link: self });
+
+        // Note: Each half holds its own non-owning `Link` value, so that `&mut Link`
+        //       references handed out by the two halves never alias each other.
+        let read = ReadHalf {
+            link: ManuallyDrop::new(unsafe { Link::unchecked_new(raw_link) }),
+            shared: Arc::clone(&shared),
+        };
+
+        let write = WriteHalf {
+            link: ManuallyDrop::new(unsafe { Link::unchecked_new(raw_link) }),
+            shared,
+        };
+
+        (read, write)
+    }
+}
+
+impl ReadHalf {
+    /// Rejoin this [`ReadHalf`] with the [`WriteHalf`] it was split from.
+    ///
+    /// If `write` was not created by the same call to [`Link::split()`] as this value,
+    /// both halves are returned unchanged in the `Err` variant.
+    ///
+    /// Link locking remains enabled on the returned [`Link`].
+    pub fn reunite(self, write: WriteHalf) -> Result<Link, (ReadHalf, WriteHalf)> {
+        if !Arc::ptr_eq(&self.shared, &write.shared) {
+            return Err((self, write));
+        }
+
+        let ReadHalf { link: _, shared } = self;
+        drop(write);
+
+        match Arc::try_unwrap(shared) {
+            Ok(Shared { link }) => Ok(link),
+            Err(_) => unreachable!("ReadHalf::reunite(): link is still shared"),
+        }
+    }
+
+    /// See [`Link::is_ready()`].
+    pub fn is_ready(&self) -> bool {
+        self.link.is_ready()
+    }
+
+    /// See [`Link::error()`].
+    pub fn error(&self) -> Option<Error> {
+        self.link.error()
+    }
+
+    /// See [`Link::raw_get_next()`].
+    pub fn raw_get_next(&mut self) -> Result<i32, Error> {
+        self.link.raw_get_next()
+    }
+
+    /// See [`Link::raw_next_packet()`].
+    pub fn raw_next_packet(&mut self) -> Result<i32, Error> {
+        self.link.raw_next_packet()
+    }
+
+    /// See [`Link::new_packet()`].
+    pub fn new_packet(&mut self) -> Result<(), Error> {
+        self.link.new_packet()
+    }
+
+    /// See [`Link::get_expr()`].
+    pub fn get_expr(&mut self) -> Result<Expr, Error> {
+        self.link.get_expr()
+    }
+}
+
+impl WriteHalf {
+    /// See [`Link::error()`].
+    pub fn error(&self) -> Option<Error> {
+        self.link.error()
+    }
+
+    /// See [`Link::put_expr()`].
+    pub fn put_expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        self.link.put_expr(expr)
+    }
+
+    /// See [`Link::end_packet()`].
+    pub fn end_packet(&mut self) -> Result<(), Error> {
+        self.link.end_packet()
+    }
+
+    /// See [`Link::flush()`].
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.link.flush()
+    }
+}
@@ -0,0 +1,673 @@
+//! Incremental writing of large strings and byte arrays.
+
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    progress::{self, Progress},
+    sys, Error, Link, TokenType,
+};
+
+/// Number of encoded characters read by [`Link::get_byte_array_to_file()`] in each
+/// piece.
+const READ_CHUNK_LEN: usize = 64 * 1024;
+
+/// Writer used to put a string on a [`Link`] in pieces.
+///
+/// Returned by [`Link::begin_string()`].
+///
+/// [`StringWriter::finish()`] must be called after all of the characters of the string
+/// have been written. If a `StringWriter` is dropped before the string is complete, the
+/// data written to the link will be malformed.
+pub struct StringWriter<'link> {
+    link: &'link mut Link,
+    /// Total number of characters in the string.
+    total: usize,
+    /// Number of characters that have not yet been written.
+    remaining: usize,
+    buffer: Vec<u32>,
+}
+
+/// Writer used to put a `ByteArray` on a [`Link`] in pieces.
+///
+/// Returned by [`Link::begin_byte_array()`].
+///
+/// The array is written as `ByteArray["..."]`, where the string argument is the
+/// [base64](https://en.wikipedia.org/wiki/Base64) encoding of the bytes. This is the
+/// same form as the `InputForm` of a `ByteArray`, and the string is written in pieces as
+/// the bytes are encoded.
+///
+/// [`ByteArrayWriter::finish()`] must be called after all of the bytes of the array have
+/// been written. If a `ByteArrayWriter` is dropped before the array is complete, the data
+/// written to the link will be malformed.
+///
+/// [`ByteArrayWriter`] implements [`io::Write`], so [`io::copy()`] can be used to write
+/// the contents of an [`io::Read`] source to a link.
+pub struct ByteArrayWriter<'link> {
+    link: &'link mut Link,
+    /// Total number of bytes in the array.
+    total: usize,
+    /// Number of bytes that have not yet been written.
+    remaining: usize,
+    /// Bytes that have been written, but not yet encoded, because they do not fill a
+    /// complete 3-byte base64 group.
+    pending: Vec<u8>,
+    /// Number of encoded characters that have not yet been put on the link.
+    chars_remaining: usize,
+    buffer: Vec<u8>,
+}
+
+/// # Incremental writing
+impl Link {
+    /// Begin putting a string containing exactly `total_chars` characters, whose
+    /// contents will be written in pieces using the returned [`StringWriter`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let mut writer = link.begin_string(11).unwrap();
+    /// writer.write_chunk("hello").unwrap();
+    /// writer.write_chunk(" world").unwrap();
+    /// writer.finish().unwrap();
+    ///
+    /// assert_eq!(link.get_string().unwrap(), "hello world");
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutUTF32Characters()`](https://reference.wolfram.com/language/ref/c/WSPutUTF32Characters.html)
+    pub fn begin_string(&mut self, total_chars: usize) -> Result<StringWriter, Error> {
+        crate::strict::on_begin_data(self, "begin_string", total_chars)?;
+
+        let mut writer = StringWriter {
+            link: self,
+            total: total_chars,
+            remaining: total_chars,
+            buffer: Vec::new(),
+        };
+
+        // Note: WSTP only begins the string when WSPutUTF32Characters() is first called,
+        //       so an empty string must be put explicitly.
+        if total_chars == 0 {
+            writer.put_characters(0)?;
+        }
+
+        Ok(writer)
+    }
+
+    /// Begin putting a `ByteArray` expression containing exactly `total_len` bytes,
+    /// whose contents will be written in pieces using the returned
+    /// [`ByteArrayWriter`].
+    ///
+    /// # Example
+    ///
+    /// Write the contents of an [`io::Read`] source to a link:
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let data: &[u8] = &[1, 2, 3, 4, 5];
+    ///
+    /// let mut writer = link.begin_byte_array(data.len()).unwrap();
+    /// std::io::copy(&mut { data }, &mut writer).unwrap();
+    /// writer.finish().unwrap();
+    ///
+    /// assert_eq!(link.test_head("System`ByteArray").unwrap(), 1);
+    /// assert_eq!(link.get_string().unwrap(), "AQIDBAU=");
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutUTF8Characters()`](https://reference.wolfram.com/language/ref/c/WSPutUTF8Characters.html)
+    pub fn begin_byte_array(
+        &mut self,
+        total_len: usize,
+    ) -> Result<ByteArrayWriter, Error> {
+        self.put_function("System`ByteArray", 1)?;
+        crate::strict::on_begin_data(self, "begin_byte_array", total_len)?;
+
+        let mut writer = ByteArrayWriter {
+            link: self,
+            total: total_len,
+            remaining: total_len,
+            pending: Vec::with_capacity(3),
+            chars_remaining: base64_len(total_len),
+            buffer: Vec::new(),
+        };
+
+        // Note: As in `begin_string()`, an empty string must be put explicitly.
+        if total_len == 0 {
+            writer.put_characters()?;
+        }
+
+        Ok(writer)
+    }
+}
+
+/// # Files
+impl Link {
+    /// Put the contents of the file at `path` on this link as a `ByteArray` expression.
+    ///
+    /// The file is read and written to the link in bounded pieces, so the complete file
+    /// contents are never held in memory.
+    ///
+    /// See also [`Link::begin_byte_array()`].
+    pub fn put_file_as_byte_array<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        let file = File::open(path).map_err(|err| file_error(path, err))?;
+
+        let len = file.metadata().map_err(|err| file_error(path, err))?.len();
+        let len = usize::try_from(len).map_err(|_| {
+            Error::custom(format!(
+                "file is too large to put on a link: {}",
+                path.display()
+            ))
+        })?;
+
+        let mut writer = self.begin_byte_array(len)?;
+
+        io::copy(&mut BufReader::new(file).take(len as u64), &mut writer)
+            .map_err(|err| file_error(path, err))?;
+
+        writer.finish()
+    }
+
+    /// Read a `ByteArray` expression from this link, and write its contents to the file
+    /// at `path`.
+    ///
+    /// The contents are first written to a temporary file in the same directory, which
+    /// replaces the file at `path` only once the complete array has been read. If an
+    /// error occurs, any existing file at `path` is left unchanged.
+    ///
+    /// A `ByteArray["..."]` expression, as written by [`Link::begin_byte_array()`], is
+    /// read and decoded in bounded pieces, so the complete array is never held in
+    /// memory. A `ByteArray[{...}]` expression containing a list of bytes is also
+    /// accepted, and is read using a single array get.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_file_as_byte_array("input.bin").unwrap();
+    /// link.get_byte_array_to_file("output.bin").unwrap();
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSGetUTF8Characters()`](https://reference.wolfram.com/language/ref/c/WSGetUTF8Characters.html)
+    pub fn get_byte_array_to_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        let count = self.test_head("System`ByteArray")?;
+
+        if count != 1 {
+            return Err(Error::custom(format!(
+                "expected ByteArray with 1 argument, got {} arguments",
+                count
+            )));
+        }
+
+        let temp_path = temporary_path(path)?;
+
+        let result = self.get_byte_array_contents(&temp_path).and_then(|()| {
+            fs::rename(&temp_path, path).map_err(|err| file_error(path, err))
+        });
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+
+        result
+    }
+
+    /// Read the argument of a `ByteArray` expression, and write the bytes to the file at
+    /// `path`.
+    fn get_byte_array_contents(&mut self, path: &Path) -> Result<(), Error> {
+        let file = File::create(path).map_err(|err| file_error(path, err))?;
+        let mut writer = BufWriter::new(file);
+
+        match self.get_type()? {
+            TokenType::String => {
+                self.raw_get_next()?;
+
+                let mut decoder = Base64Decoder::default();
+                let mut buffer = vec![0u8; READ_CHUNK_LEN];
+                let mut bytes = Vec::new();
+                let mut transferred: u64 = 0;
+
+                loop {
+                    let mut chars_left: i32 = 0;
+                    let mut got: i32 = 0;
+
+                    let result = unsafe {
+                        sys::WSGetUTF8Characters(
+                            self.raw_link,
+                            &mut chars_left,
+                            buffer.as_mut_ptr(),
+                            abi_len(buffer.len())?,
+                            &mut got,
+                        )
+                    };
+
+                    if result == 0 {
+                        return Err(self.error_or_unknown());
+                    }
+
+                    let got = usize::try_from(got).map_err(|_| {
+                        Error::custom(format!(
+                            "invalid WSGetUTF8Characters() length returned by WSTP: {}",
+                            got
+                        ))
+                    })?;
+
+                    bytes.clear();
+                    decoder.decode(&buffer[..got], &mut bytes)?;
+
+                    writer
+                        .write_all(&bytes)
+                        .map_err(|err| file_error(path, err))?;
+
+                    transferred += bytes.len() as u64;
+
+                    progress::report(
+                        self,
+                        Progress {
+                            transferred,
+                            total: None,
+                        },
+                    )?;
+
+                    if chars_left == 0 {
+                        break;
+                    }
+                }
+
+                decoder.finish()?;
+            },
+            TokenType::Function => {
+                let array = self.get_u8_array()?;
+
+                if array.dimensions().len() != 1 {
+                    return Err(Error::custom(format!(
+                        "expected ByteArray argument to be a list of bytes, got array \
+                         with dimensions {:?}",
+                        array.dimensions()
+                    )));
+                }
+
+                writer
+                    .write_all(array.data())
+                    .map_err(|err| file_error(path, err))?;
+
+                let len = array.data().len();
+                drop(array);
+
+                progress::report(self, progress_of(len, 0))?;
+            },
+            other => {
+                return Err(Error::custom(format!(
+                    "expected ByteArray argument to be a string or list, got {:?}",
+                    other
+                )))
+            },
+        }
+
+        writer.flush().map_err(|err| file_error(path, err))?;
+
+        Ok(())
+    }
+}
+
+impl<'link> StringWriter<'link> {
+    /// Write the next piece of the string.
+    ///
+    /// Returns an error if `chunk` contains more characters than remain to be written.
+    pub fn write_chunk(&mut self, chunk: &str) -> Result<(), Error> {
+        self.buffer.clear();
+        self.buffer.extend(chunk.chars().map(u32::from));
+
+        if self.buffer.len() > self.remaining {
+            return Err(Error::custom(format!(
+                "StringWriter: chunk of {} characters exceeds the {} characters \
+                 remaining in the string",
+                self.buffer.len(),
+                self.remaining
+            )));
+        }
+
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.put_characters(self.remaining - self.buffer.len())
+    }
+
+    /// Returns the number of characters that have not yet been written.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Finish writing the string.
+    ///
+    /// Returns an error if fewer characters were written than were promised to
+    /// [`Link::begin_string()`].
+    pub fn finish(self) -> Result<(), Error> {
+        if self.remaining != 0 {
+            return Err(Error::custom(format!(
+                "StringWriter: string is incomplete; {} more characters expected",
+                self.remaining
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Put the characters in `self.buffer`, followed by `chars_left` more characters.
+    fn put_characters(&mut self, chars_left: usize) -> Result<(), Error> {
+        let chars_left = abi_len(chars_left)?;
+        let len = abi_len(self.buffer.len())?;
+
+        let result = unsafe {
+            sys::WSPutUTF32Characters(
+                self.link.raw_link,
+                chars_left,
+                self.buffer.as_ptr(),
+                len,
+            )
+        };
+
+        if result == 0 {
+            return Err(self.link.error_or_unknown());
+        }
+
+        self.remaining -= self.buffer.len();
+
+        if self.remaining == 0 {
+            crate::strict::on_data_complete(self.link);
+        }
+
+        progress::report(self.link, progress_of(self.total, self.remaining))
+    }
+}
+
+impl<'link> ByteArrayWriter<'link> {
+    /// Write the next piece of the byte array.
+    ///
+    /// Returns an error if `chunk` contains more bytes than remain to be written.
+    pub fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        if chunk.len() > self.remaining {
+            return Err(Error::custom(format!(
+                "ByteArrayWriter: chunk of {} bytes exceeds the {} bytes remaining in \
+                 the array",
+                chunk.len(),
+                self.remaining
+            )));
+        }
+
+        self.pending.extend_from_slice(chunk);
+        self.remaining -= chunk.len();
+
+        // Encode every complete 3-byte group. The final group may be incomplete, and is
+        // padded once the last byte has been written.
+        let encode_len = if self.remaining == 0 {
+            self.pending.len()
+        } else {
+            self.pending.len() - self.pending.len() % 3
+        };
+
+        if encode_len > 0 {
+            self.buffer.clear();
+            base64_encode(&self.pending[..encode_len], &mut self.buffer);
+            self.pending.drain(..encode_len);
+
+            self.put_characters()?;
+        }
+
+        progress::report(self.link, progress_of(self.total, self.remaining))
+    }
+
+    /// Returns the number of bytes that have not yet been written.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Finish writing the byte array.
+    ///
+    /// Returns an error if fewer bytes were written than were promised to
+    /// [`Link::begin_byte_array()`].
+    pub fn finish(self) -> Result<(), Error> {
+        if self.remaining != 0 {
+            return Err(Error::custom(format!(
+                "ByteArrayWriter: byte array is incomplete; {} more bytes expected",
+                self.remaining
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'link> ByteArrayWriter<'link> {
+    /// Put the encoded characters in `self.buffer`.
+    fn put_characters(&mut self) -> Result<(), Error> {
+        self.chars_remaining -= self.buffer.len();
+
+        let chars_left = abi_len(self.chars_remaining)?;
+        let len = abi_len(self.buffer.len())?;
+
+        let result = unsafe {
+            sys::WSPutUTF8Characters(
+                self.link.raw_link,
+                chars_left,
+                self.buffer.as_ptr(),
+                len,
+            )
+        };
+
+        if result == 0 {
+            return Err(self.link.error_or_unknown());
+        }
+
+        if self.chars_remaining == 0 {
+            crate::strict::on_data_complete(self.link);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'link> io::Write for ByteArrayWriter<'link> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = std::cmp::min(buf.len(), self.remaining);
+
+        if len == 0 && !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "ByteArrayWriter: byte array is already complete",
+            ));
+        }
+
+        self.write_chunk(&buf[..len]).map_err(io::Error::other)?;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn progress_of(total: usize, remaining: usize) -> Progress {
+    Progress {
+        transferred: (total - remaining) as u64,
+        total: Some(total as u64),
+    }
+}
+
+fn file_error(path: &Path, err: io::Error) -> Error {
+    Error::with_source(
+        format!("I/O error for file {}: {}", path.display(), err),
+        err,
+    )
+}
+
+//======================================
+// Base64
+//======================================
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Number of characters in the base64 encoding of `len` bytes, including padding.
+fn base64_len(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+/// Append the base64 encoding of `bytes` to `out`, padding the final group if `bytes`
+/// is not a multiple of 3 bytes long.
+fn base64_encode(bytes: &[u8], out: &mut Vec<u8>) {
+    for group in bytes.chunks(3) {
+        let b0 = group[0];
+        let b1 = group.get(1).copied().unwrap_or(0);
+        let b2 = group.get(2).copied().unwrap_or(0);
+
+        let sextets = [
+            b0 >> 2,
+            ((b0 & 0x03) << 4) | (b1 >> 4),
+            ((b1 & 0x0f) << 2) | (b2 >> 6),
+            b2 & 0x3f,
+        ];
+
+        for (index, sextet) in sextets.iter().enumerate() {
+            if index <= group.len() {
+                out.push(BASE64_ALPHABET[usize::from(*sextet)]);
+            } else {
+                out.push(b'=');
+            }
+        }
+    }
+}
+
+/// Incremental decoder for base64 data that may be split at any point.
+#[derive(Debug, Default)]
+struct Base64Decoder {
+    /// Characters of the current, incomplete 4-character group.
+    group: [u8; 4],
+    group_len: usize,
+    /// Set once a padded group has been decoded. Only whitespace may follow.
+    finished: bool,
+}
+
+impl Base64Decoder {
+    /// Decode `input`, appending the decoded bytes to `out`.
+    fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+        for &c in input {
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+
+            if self.finished {
+                return Err(base64_error("data after padding"));
+            }
+
+            self.group[self.group_len] = c;
+            self.group_len += 1;
+
+            if self.group_len == 4 {
+                self.group_len = 0;
+                self.decode_group(out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the input ended at the end of a complete group.
+    fn finish(&self) -> Result<(), Error> {
+        if self.group_len != 0 {
+            return Err(base64_error("incomplete final group"));
+        }
+
+        Ok(())
+    }
+
+    fn decode_group(&mut self, out: &mut Vec<u8>) -> Result<(), Error> {
+        let padding = self.group.iter().rev().take_while(|&&c| c == b'=').count();
+
+        if padding > 2 {
+            return Err(base64_error("invalid padding"));
+        }
+
+        let mut sextets = [0u8; 4];
+
+        for (sextet, &c) in sextets.iter_mut().zip(&self.group[..4 - padding]) {
+            *sextet = base64_value(c).ok_or_else(|| {
+                base64_error(&format!("invalid character {:?}", c as char))
+            })?;
+        }
+
+        let bytes = [
+            (sextets[0] << 2) | (sextets[1] >> 4),
+            (sextets[1] << 4) | (sextets[2] >> 2),
+            (sextets[2] << 6) | sextets[3],
+        ];
+
+        out.extend_from_slice(&bytes[..3 - padding]);
+
+        if padding > 0 {
+            self.finished = true;
+        }
+
+        Ok(())
+    }
+}
+
+fn base64_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+fn base64_error(message: &str) -> Error {
+    Error::custom(format!("malformed base64 data in ByteArray: {}", message))
+}
+
+/// Path of the temporary file that [`Link::get_byte_array_to_file()`] writes to before
+/// renaming it to `path`.
+fn temporary_path(path: &Path) -> Result<PathBuf, Error> {
+    let file_name = path.file_name().ok_or_else(|| {
+        Error::custom(format!("path does not name a file: {}", path.display()))
+    })?;
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+
+    Ok(path.with_file_name(temp_name))
+}
+
+fn abi_len(len: usize) -> Result<i32, Error> {
+    i32::try_from(len).map_err(|_| {
+        Error::custom(format!(
+            "string length overflows i32: {}; during WSTP put",
+            len
+        ))
+    })
+}
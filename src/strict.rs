@@ -0,0 +1,241 @@
+//! Opt-in validation of the sequence of calls used to put expressions on a link.
+//!
+//! WSTP does not always detect a malformed sequence of put calls at the point where the
+//! mistake is made. For example, calling [`Link::end_packet()`] before all of the
+//! elements promised by [`Link::put_function()`] have been written may only surface as
+//! a sequence error on the *reading* side of the link, long after the buggy call was
+//! made. Strict mode tracks the expected put state in Rust, and returns a descriptive
+//! error from the exact call that broke the protocol.
+//!
+//! Only put calls are validated. Get calls are not tracked.
+
+use crate::{link_state, sys, Error, Link};
+
+/// Put state of a link that has strict mode enabled.
+#[derive(Debug, Default)]
+pub(crate) struct PutState {
+    /// Number of expressions (including the head) still expected for each incomplete
+    /// function, from outermost to innermost.
+    remaining: Vec<usize>,
+
+    /// Set after `put_raw_type(WSTKFUNC)`, which must be followed by `put_arg_count()`.
+    expecting_arg_count: bool,
+
+    /// Name of the operation that began writing the data of an atomic expression in
+    /// pieces, until all of the promised data has been written.
+    incomplete_data: Option<&'static str>,
+}
+
+/// # Strict mode
+impl Link {
+    /// Enable or disable strict validation of the put calls made on this link.
+    ///
+    /// When strict mode is enabled, put calls that would leave the outgoing expression
+    /// data malformed return an error describing the mistake. This includes put calls
+    /// made before all of the data promised to [`Link::begin_string()`],
+    /// [`Link::begin_byte_array()`], or [`Link::begin_data()`] has been written.
+    ///
+    /// Get calls are not validated.
+    ///
+    /// Strict mode adds overhead to every put call, and is intended to be used while
+    /// debugging.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.set_strict_mode(true);
+    ///
+    /// // Promise 2 elements, but only put 1.
+    /// link.put_function("System`List", 2).unwrap();
+    /// link.put_i64(1).unwrap();
+    ///
+    /// let err = link.end_packet().unwrap_err();
+    ///
+    /// assert!(err.to_string().contains("1 more expression"));
+    /// ```
+    pub fn set_strict_mode(&mut self, enabled: bool) {
+        link_state::with(self.raw_link, |state| {
+            state.strict = if enabled {
+                Some(PutState::default())
+            } else {
+                None
+            };
+        })
+    }
+
+    /// Returns `true` if strict mode is enabled for this link.
+    ///
+    /// See [`Link::set_strict_mode()`].
+    pub fn is_strict_mode(&self) -> bool {
+        link_state::with_existing(self.raw_link, |state| state.strict.is_some())
+            .unwrap_or(false)
+    }
+}
+
+//======================================
+// Hooks called by the put methods
+//======================================
+
+/// Called before an atomic expression (or a complete array) is put on `link`.
+pub(crate) fn on_put_expr(link: &Link, operation: &str) -> Result<(), Error> {
+    with_state(link, |state| {
+        state.check_data_complete(operation)?;
+
+        if state.expecting_arg_count {
+            return Err(strict_error(format!(
+                "{}() called after put_raw_type(WSTKFUNC); expected put_arg_count()",
+                operation
+            )));
+        }
+
+        state.consume();
+
+        Ok(())
+    })
+}
+
+/// Called before `put_raw_type()`.
+pub(crate) fn on_put_raw_type(link: &Link, type_: i32) -> Result<(), Error> {
+    with_state(link, |state| {
+        state.check_data_complete("put_raw_type")?;
+
+        if state.expecting_arg_count {
+            return Err(strict_error(format!(
+                "put_raw_type({}) called after put_raw_type(WSTKFUNC); expected \
+                 put_arg_count()",
+                type_
+            )));
+        }
+
+        if type_ == i32::from(sys::WSTKFUNC) {
+            state.expecting_arg_count = true;
+        }
+
+        Ok(())
+    })
+}
+
+/// Called before `put_arg_count()`.
+pub(crate) fn on_put_arg_count(link: &Link, count: usize) -> Result<(), Error> {
+    with_state(link, |state| {
+        state.check_data_complete("put_arg_count")?;
+
+        if !state.expecting_arg_count {
+            return Err(strict_error(format!(
+                "put_arg_count({}) must be preceded by put_raw_type(WSTKFUNC)",
+                count
+            )));
+        }
+
+        state.expecting_arg_count = false;
+
+        // The function expression itself fills a slot in its parent.
+        state.consume();
+        // The head, followed by `count` elements.
+        state.remaining.push(count + 1);
+
+        Ok(())
+    })
+}
+
+/// Called before `end_packet()`.
+pub(crate) fn on_end_packet(link: &Link) -> Result<(), Error> {
+    with_state(link, |state| {
+        state.check_data_complete("end_packet")?;
+
+        if state.expecting_arg_count {
+            return Err(strict_error(
+                "end_packet() called after put_raw_type(WSTKFUNC); expected \
+                 put_arg_count()"
+                    .to_owned(),
+            ));
+        }
+
+        if let Some(innermost) = state.remaining.last() {
+            return Err(strict_error(format!(
+                "end_packet() called before the current expression was complete: \
+                 {} more expression(s) expected in the innermost of {} incomplete \
+                 function(s)",
+                innermost,
+                state.remaining.len()
+            )));
+        }
+
+        Ok(())
+    })
+}
+
+/// Called before an atomic expression whose data is `len` units long is begun by
+/// `operation`, and then written in pieces.
+///
+/// [`on_data_complete()`] must be called once all of the data has been written.
+pub(crate) fn on_begin_data(
+    link: &Link,
+    operation: &'static str,
+    len: usize,
+) -> Result<(), Error> {
+    on_put_expr(link, operation)?;
+
+    with_state(link, |state| {
+        // Note: Empty data is written by `operation` itself.
+        if len > 0 {
+            state.incomplete_data = Some(operation);
+        }
+
+        Ok(())
+    })
+}
+
+/// Called once all of the data begun by [`on_begin_data()`] has been written.
+pub(crate) fn on_data_complete(link: &Link) {
+    // Note: The closure never returns an error.
+    let _ = with_state(link, |state| {
+        state.incomplete_data = None;
+        Ok(())
+    });
+}
+
+//======================================
+// Utilities
+//======================================
+
+impl PutState {
+    fn check_data_complete(&self, operation: &str) -> Result<(), Error> {
+        match self.incomplete_data {
+            Some(begun_by) => Err(strict_error(format!(
+                "{}() called before the data started by {}() was complete",
+                operation, begun_by
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn consume(&mut self) {
+        if let Some(innermost) = self.remaining.last_mut() {
+            *innermost -= 1;
+        }
+
+        // Pop any functions which are now complete.
+        while self.remaining.last() == Some(&0) {
+            self.remaining.pop();
+        }
+    }
+}
+
+fn with_state<F>(link: &Link, func: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut PutState) -> Result<(), Error>,
+{
+    link_state::with_existing(link.raw_link, |state| match state.strict {
+        Some(ref mut strict) => func(strict),
+        None => Ok(()),
+    })
+    .unwrap_or(Ok(()))
+}
+
+fn strict_error(message: String) -> Error {
+    Error::custom(format!("strict mode: {}", message))
+}
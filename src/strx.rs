@@ -143,6 +143,30 @@ impl Utf32Str {
     }
 }
 
+//--------------------------------------
+// Ucs2
+//--------------------------------------
+
+impl Ucs2Str {
+    /// Convert a slice of [`u16`] to a UCS-2 string slice.
+    ///
+    /// Every `u16` value is a valid UCS-2 code unit, so this conversion cannot fail.
+    pub fn from_ucs2(ucs2: &[u16]) -> &Ucs2Str {
+        const _: () = assert!(mem::size_of::<&Ucs2Str>() == mem::size_of::<&[u16]>());
+        const _: () = assert!(mem::align_of::<&Ucs2Str>() == mem::align_of::<&[u16]>());
+
+        // SAFETY: Relies on representation of references to unsized data being the same
+        //         between types.
+        unsafe { std::mem::transmute::<&[u16], &Ucs2Str>(ucs2) }
+    }
+
+    /// Access the elements of this UCS-2 string as a slice of `u16` elements.
+    pub fn as_slice(&self) -> &[u16] {
+        let Ucs2Str(slice) = self;
+        slice
+    }
+}
+
 //======================================
 // Display Impls
 //======================================
@@ -160,7 +184,10 @@ impl Display for Utf16Str {
         for char in char::decode_utf16(slice.into_iter().copied()) {
             let char: char = match char {
                 Ok(char) => char,
-                Err(err) => panic!("Utf16Str could not be decoded: {err}"),
+                // Note: Invalid data can only be present if the `unsafe`
+                //       `from_utf16_unchecked()` invariants were violated. Don't panic
+                //       while formatting regardless.
+                Err(_) => char::REPLACEMENT_CHARACTER,
             };
             let () = Display::fmt(&char, f)?;
         }
@@ -176,7 +203,24 @@ impl Display for Utf32Str {
         for char_u32 in slice.into_iter().copied() {
             let char: char = match char::from_u32(char_u32) {
                 Some(char) => char,
-                None => panic!("Utf32Str code point is not a valid `char`: {char_u32}"),
+                None => char::REPLACEMENT_CHARACTER,
+            };
+            let () = Display::fmt(&char, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Ucs2Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Ucs2Str(slice) = self;
+
+        for code_unit in slice.into_iter().copied() {
+            // Note: Surrogate code units do not represent characters in UCS-2.
+            let char: char = match char::from_u32(u32::from(code_unit)) {
+                Some(char) => char,
+                None => char::REPLACEMENT_CHARACTER,
             };
             let () = Display::fmt(&char, f)?;
         }
@@ -216,3 +260,11 @@ fn test_utf32_str_display() {
 
     assert_eq!(format!("{}", utf32), String::from("hello 👋"));
 }
+
+#[test]
+fn test_ucs2_str_display() {
+    let ucs2: Vec<u16> = "hello é".encode_utf16().collect();
+    let ucs2: &Ucs2Str = Ucs2Str::from_ucs2(&ucs2);
+
+    assert_eq!(format!("{}", ucs2), String::from("hello é"));
+}
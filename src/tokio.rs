@@ -0,0 +1,651 @@
+//! Asynchronous [`Link`] API for use with the [Tokio](https://tokio.rs) runtime.
+//!
+//! This module is only available when the `tokio` feature is enabled.
+//!
+//! The WSTP C library only provides blocking functions. [`AsyncLink`] runs each
+//! operation on Tokio's blocking thread pool using
+//! [`spawn_blocking()`](::tokio::task::spawn_blocking), so that it does not block the
+//! async executor.
+//!
+//! Waiting for data to arrive does not occupy a blocking thread. Instead, a single
+//! background thread per WSTP [`Environment`][crate::Environment] waits for data on
+//! every link that an [`AsyncLink`] is waiting on, using `WSReadyParallel()`, and wakes
+//! the corresponding tasks. This works the same way on every platform, including
+//! Windows, so services with many mostly-idle connections do not need a thread per
+//! connection.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+use ::tokio::task::{JoinError, JoinHandle};
+use once_cell::sync::Lazy;
+use wolfram_expr::Expr;
+
+use crate::{
+    sys::{self, WSLINK},
+    Error, ForceSend, Link, LinkWaitSet,
+};
+
+/// Asynchronous wrapper around a [`Link`].
+///
+/// # Example
+///
+/// ```
+/// use wstp::{tokio::AsyncLink, Link};
+/// use wolfram_expr::Expr;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// runtime.block_on(async {
+///     let mut link = AsyncLink::new(Link::new_loopback().unwrap());
+///
+///     link.put_expr(&Expr::from(5)).await.unwrap();
+///     link.flush().await.unwrap();
+///
+///     assert_eq!(link.get_expr().await.unwrap(), Expr::from(5));
+/// });
+/// ```
+///
+/// # Cancellation
+///
+/// Each operation is performed by a blocking task that takes temporary ownership of
+/// the link. If the future returned by an operation is dropped before it completes,
+/// the blocking task still runs to completion, but the link is closed when it finishes
+/// instead of being returned to the [`AsyncLink`]. Subsequent operations return an
+/// error.
+///
+/// [`AsyncLink::wait()`] is the exception: while it is waiting, the link is owned by the
+/// background thread described in the [module documentation](self). If the future
+/// returned by [`AsyncLink::wait()`] (or [`AsyncLink::get_expr()`]) is dropped, the
+/// link is returned to the [`AsyncLink`] by the next operation, so waiting can be
+/// safely cancelled, e.g. by a timeout.
+///
+/// # Backpressure
+///
+/// [`AsyncLink::send()`] writes and flushes an expression in the background, and
+/// returns as soon as the write has started. At most one such write is in progress at a
+/// time: the next call to [`AsyncLink::send()`] (or [`AsyncLink::poll_ready()`]) waits
+/// for the previous write to be flushed. Flushing blocks while the peer is not reading,
+/// so a producer that sends faster than the peer reads is suspended, instead of
+/// expressions queuing up in memory.
+///
+/// ```
+/// use wstp::{tokio::AsyncLink, Link};
+/// use wolfram_expr::Expr;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// runtime.block_on(async {
+///     let mut link = AsyncLink::new(Link::new_loopback().unwrap());
+///
+///     for i in 0..3 {
+///         link.send(&Expr::from(i)).await.unwrap();
+///     }
+///
+///     for i in 0..3 {
+///         assert_eq!(link.get_expr().await.unwrap(), Expr::from(i));
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct AsyncLink {
+    /// `None` if the link was lost because an operation was cancelled or panicked, or
+    /// while `pending_write` owns it.
+    link: Option<Link>,
+    /// Write started by [`AsyncLink::start_send()`] that has not yet been completed by
+    /// [`AsyncLink::poll_ready()`].
+    pending_write: Option<JoinHandle<(Link, Result<(), Error>)>>,
+    /// Registration with the reactor that owns the link while [`AsyncLink::wait()`] is
+    /// waiting, or after such a wait was cancelled.
+    parked: Option<Parked>,
+}
+
+impl AsyncLink {
+    /// Construct a new [`AsyncLink`] that performs operations on `link`.
+    pub fn new(link: Link) -> Self {
+        AsyncLink {
+            link: Some(link),
+            pending_write: None,
+            parked: None,
+        }
+    }
+
+    /// Get the underlying [`Link`].
+    ///
+    /// Returns `None` if the link was lost because a previous operation was cancelled,
+    /// if a write started by [`AsyncLink::send()`] has not yet been completed, or if the
+    /// link is still being waited on after [`AsyncLink::wait()`] was cancelled.
+    pub fn link(&mut self) -> Option<&mut Link> {
+        self.try_unpark();
+
+        self.link.as_mut()
+    }
+
+    /// Convert this value into the underlying [`Link`].
+    ///
+    /// Returns `None` if the link was lost because a previous operation was cancelled,
+    /// if a write started by [`AsyncLink::send()`] has not yet been completed, or if the
+    /// link is still being waited on after [`AsyncLink::wait()`] was cancelled.
+    pub fn into_inner(mut self) -> Option<Link> {
+        self.try_unpark();
+
+        self.link.take()
+    }
+
+    /// Write `expr` to this link and flush it in the background, once the previous
+    /// write has been flushed.
+    ///
+    /// This returns once the write of `expr` has started. Errors writing `expr` are
+    /// returned by the next operation on this link. See [Backpressure](#backpressure).
+    pub async fn send(&mut self, expr: &Expr) -> Result<(), Error> {
+        self.finish_write().await?;
+
+        self.start_send(expr.clone())
+    }
+
+    /// Poll until this link is ready for [`AsyncLink::start_send()`], i.e. until the
+    /// write started by the previous call to [`AsyncLink::start_send()`], if any, has
+    /// been flushed, and the link is no longer being waited on by a cancelled call to
+    /// [`AsyncLink::wait()`].
+    ///
+    /// Returns the result of the previous write.
+    pub fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        if let Some(ref parked) = self.parked {
+            let (link, _) = match parked.reactor.poll_reclaim(parked.id, false, cx) {
+                Poll::Ready(reclaimed) => reclaimed,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            self.parked = None;
+            self.link = Some(link);
+        }
+
+        let pending_write = match self.pending_write {
+            Some(ref mut pending_write) => pending_write,
+            None => return Poll::Ready(Ok(())),
+        };
+
+        let result = match Pin::new(pending_write).poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.pending_write = None;
+
+        let (link, result) = result.map_err(join_error)?;
+
+        self.link = Some(link);
+
+        Poll::Ready(result)
+    }
+
+    /// Start writing `expr` to this link and flushing it, on Tokio's blocking thread
+    /// pool.
+    ///
+    /// [`AsyncLink::poll_ready()`] must have returned [`Poll::Ready`] since the previous
+    /// call to this function, otherwise an error is returned.
+    pub fn start_send(&mut self, expr: Expr) -> Result<(), Error> {
+        if self.pending_write.is_some() {
+            return Err(Error::custom(
+                "AsyncLink::start_send(): previous write has not completed; call \
+                 poll_ready() first"
+                    .to_owned(),
+            ));
+        }
+
+        let mut link = self.link.take().ok_or_else(lost_link_error)?;
+
+        self.pending_write = Some(::tokio::task::spawn_blocking(move || {
+            let result = link.put_expr(&expr).and_then(|()| link.flush());
+            (link, result)
+        }));
+
+        Ok(())
+    }
+
+    /// Wait for the write started by [`AsyncLink::start_send()`], if any, to complete.
+    async fn finish_write(&mut self) -> Result<(), Error> {
+        std::future::poll_fn(|cx| self.poll_ready(cx)).await
+    }
+
+    /// Read an expression off of this link.
+    ///
+    /// This waits for data to become available without occupying a blocking thread. See
+    /// [`Link::get_expr()`].
+    pub async fn get_expr(&mut self) -> Result<Expr, Error> {
+        self.wait().await?;
+
+        self.with_link(|link| link.get_expr()).await
+    }
+
+    /// Write an expression to this link.
+    ///
+    /// See [`Link::put_expr()`].
+    pub async fn put_expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        let expr = expr.clone();
+
+        self.with_link(move |link| link.put_expr(&expr)).await
+    }
+
+    /// Flush out any buffers containing data waiting to be sent on this link.
+    ///
+    /// See [`Link::flush()`].
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        self.with_link(|link| link.flush()).await
+    }
+
+    /// Wait until there is data ready to be read from this link.
+    ///
+    /// Unlike the other operations, this does not use Tokio's blocking thread pool. See
+    /// the [module documentation](self).
+    ///
+    /// See [`Link::wait()`].
+    pub async fn wait(&mut self) -> Result<(), Error> {
+        self.finish_write().await?;
+
+        let link = self.link.take().ok_or_else(lost_link_error)?;
+
+        if link.is_ready() {
+            self.link = Some(link);
+            return Ok(());
+        }
+
+        if let Some(err) = link.error() {
+            self.link = Some(link);
+            return Err(err);
+        }
+
+        let reactor = Reactor::for_link(&link);
+        let id = reactor.register(link);
+
+        self.parked = Some(Parked { reactor, id });
+
+        std::future::poll_fn(|cx| self.poll_wait(cx)).await
+    }
+
+    /// Poll for the link registered by [`AsyncLink::wait()`] to become ready.
+    fn poll_wait(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        let parked = match self.parked {
+            Some(ref parked) => parked,
+            None => return Poll::Ready(Err(lost_link_error())),
+        };
+
+        let (link, result) = match parked.reactor.poll_reclaim(parked.id, true, cx) {
+            Poll::Ready(reclaimed) => reclaimed,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.parked = None;
+        self.link = Some(link);
+
+        Poll::Ready(result.unwrap_or(Ok(())))
+    }
+
+    /// Take the link back from the reactor after a cancelled call to
+    /// [`AsyncLink::wait()`], if it is not being used by the current wait pass.
+    fn try_unpark(&mut self) {
+        let parked = match self.parked {
+            Some(ref parked) => parked,
+            None => return,
+        };
+
+        if let Some(link) = parked.reactor.try_reclaim(parked.id) {
+            self.parked = None;
+            self.link = Some(link);
+        }
+    }
+
+    /// Call `func` with the underlying [`Link`] on Tokio's blocking thread pool.
+    ///
+    /// This can be used to perform operations that do not have an async equivalent.
+    pub async fn with_link<T, F>(&mut self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.finish_write().await?;
+
+        let mut link = self.link.take().ok_or_else(lost_link_error)?;
+
+        let (link, result) = ::tokio::task::spawn_blocking(move || {
+            let result = func(&mut link);
+            (link, result)
+        })
+        .await
+        .map_err(join_error)?;
+
+        self.link = Some(link);
+
+        result
+    }
+}
+
+impl Drop for AsyncLink {
+    fn drop(&mut self) {
+        if let Some(parked) = self.parked.take() {
+            parked.reactor.abandon(parked.id);
+        }
+    }
+}
+
+fn join_error(err: JoinError) -> Error {
+    Error::with_source(
+        format!("AsyncLink: blocking link operation failed: {}", err),
+        err,
+    )
+}
+
+fn lost_link_error() -> Error {
+    Error::custom(
+        "AsyncLink: link was lost because a previous operation was cancelled".to_owned(),
+    )
+}
+
+//======================================
+// Readiness
+//======================================
+
+/// Longest time a single `WSReadyParallel()` call made by a [`Reactor`] waits for,
+/// before links registered in the meantime are included.
+const REACTOR_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Reactors, keyed by the address of the WSTP environment whose links they wait on.
+static REACTORS: Lazy<Mutex<HashMap<usize, Arc<Reactor>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Background thread that waits for data on the links of a single WSTP environment, and
+/// wakes the tasks waiting on them.
+///
+/// A link is owned by the reactor while it is registered, so the reactor thread is the
+/// only thread that calls WSTP functions on it. The link is returned to its
+/// [`AsyncLink`] once it is ready, or when the [`AsyncLink`] needs it for another
+/// operation and the link is not in use by the current wait pass.
+struct Reactor {
+    state: Mutex<ReactorState>,
+    /// Notified when a link is registered.
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct ReactorState {
+    waiters: HashMap<u64, Waiter>,
+    next_id: u64,
+    /// Ids of the waiters whose links are used by the wait pass in progress.
+    in_pass: HashSet<u64>,
+}
+
+struct Waiter {
+    link: Link,
+    /// Result recorded by the reactor once the link is ready, or once waiting on it has
+    /// failed. Links with a result are not included in further wait passes.
+    result: Option<Result<(), Error>>,
+    /// Task to wake once `result` is recorded, or, if `reclaiming` is set, once the link
+    /// is no longer used by the current wait pass.
+    waker: Option<Waker>,
+    /// Set if the link is wanted back before it is ready. Links being reclaimed are not
+    /// included in further wait passes.
+    reclaiming: bool,
+    /// Set if the [`AsyncLink`] was dropped while the link was used by a wait pass. The
+    /// link is closed once the pass finishes.
+    abandoned: bool,
+}
+
+/// Registration of the link of an [`AsyncLink`] with a [`Reactor`].
+struct Parked {
+    reactor: Arc<Reactor>,
+    id: u64,
+}
+
+impl Reactor {
+    /// Get the reactor for the environment of `link`, starting it if necessary.
+    fn for_link(link: &Link) -> Arc<Reactor> {
+        let env = unsafe { sys::WSLinkEnvironment(link.raw_link) } as usize;
+
+        let mut reactors = lock(&REACTORS);
+
+        let reactor = reactors.entry(env).or_insert_with(|| {
+            let reactor = Arc::new(Reactor {
+                state: Mutex::new(ReactorState::default()),
+                changed: Condvar::new(),
+            });
+
+            let thread_reactor = Arc::clone(&reactor);
+
+            thread::Builder::new()
+                .name("wstp-tokio-reactor".to_owned())
+                .spawn(move || thread_reactor.run())
+                .expect("failed to spawn AsyncLink reactor thread");
+
+            reactor
+        });
+
+        Arc::clone(reactor)
+    }
+
+    fn run(&self) {
+        loop {
+            let waiting: Vec<(u64, ForceSend<WSLINK>)> = {
+                let mut state = lock(&self.state);
+
+                loop {
+                    let waiting: Vec<_> = state
+                        .waiters
+                        .iter()
+                        .filter(|(_, waiter)| {
+                            waiter.result.is_none() && !waiter.reclaiming
+                        })
+                        .map(|(&id, waiter)| (id, ForceSend(waiter.link.raw_link)))
+                        .collect();
+
+                    if !waiting.is_empty() {
+                        state.in_pass = waiting.iter().map(|&(id, _)| id).collect();
+                        break waiting;
+                    }
+
+                    state = self
+                        .changed
+                        .wait(state)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+            };
+
+            // Note: The links are owned by `Waiter`s. They are not removed from the
+            //       state while they are part of a pass, so they remain valid, and no
+            //       other thread uses them.
+            let links: Vec<ManuallyDrop<Link>> = waiting
+                .iter()
+                .map(|(_, raw_link)| {
+                    ManuallyDrop::new(unsafe { Link::unchecked_new(raw_link.0) })
+                })
+                .collect();
+
+            let mut set = LinkWaitSet::new();
+
+            for link in &links {
+                set.add(link);
+            }
+
+            let result = set.wait(Some(REACTOR_POLL_INTERVAL));
+
+            // If waiting failed, check every link, so that an error on any one of them
+            // is reported to its task.
+            let checked: Vec<usize> = match result {
+                Ok(ref ready) => ready.clone(),
+                Err(_) => (0..waiting.len()).collect(),
+            };
+
+            let mut results: Vec<Option<Result<(), Error>>> =
+                (0..waiting.len()).map(|_| None).collect();
+
+            for index in checked {
+                let link = &links[index];
+
+                results[index] = if link.is_ready() {
+                    Some(Ok(()))
+                } else {
+                    link.error().map(Err)
+                };
+            }
+
+            let (wakers, closed): (Vec<Waker>, Vec<Link>) = {
+                let mut state = lock(&self.state);
+
+                let mut wakers = Vec::new();
+                let mut closed = Vec::new();
+
+                for (&(id, _), result) in waiting.iter().zip(results) {
+                    let waiter = match state.waiters.get_mut(&id) {
+                        Some(waiter) => waiter,
+                        None => continue,
+                    };
+
+                    if result.is_some() {
+                        waiter.result = result;
+                    }
+
+                    if waiter.abandoned {
+                        if let Some(waiter) = state.waiters.remove(&id) {
+                            closed.push(waiter.link);
+                        }
+                    } else if waiter.result.is_some() || waiter.reclaiming {
+                        wakers.extend(waiter.waker.take());
+                    }
+                }
+
+                state.in_pass.clear();
+
+                (wakers, closed)
+            };
+
+            // Note: Close abandoned links and wake tasks after the state is unlocked.
+            drop(closed);
+
+            for waker in wakers {
+                waker.wake();
+            }
+
+            if result.is_err() {
+                thread::sleep(REACTOR_POLL_INTERVAL);
+            }
+        }
+    }
+
+    /// Take ownership of `link`, and wait for it to become ready.
+    fn register(&self, link: Link) -> u64 {
+        let mut state = lock(&self.state);
+
+        let id = state.next_id;
+        state.next_id += 1;
+
+        state.waiters.insert(
+            id,
+            Waiter {
+                link,
+                result: None,
+                waker: None,
+                reclaiming: false,
+                abandoned: false,
+            },
+        );
+
+        drop(state);
+
+        self.changed.notify_all();
+
+        id
+    }
+
+    /// Poll for the link registered as `id` to be returned.
+    ///
+    /// If `until_ready` is `true`, the link is returned once it is ready, together with
+    /// the result of waiting on it. Otherwise, the link is returned as soon as it is not
+    /// used by a wait pass, and the result is `None` if the link is not ready yet.
+    ///
+    /// This never blocks.
+    fn poll_reclaim(
+        &self,
+        id: u64,
+        until_ready: bool,
+        cx: &mut Context,
+    ) -> Poll<(Link, Option<Result<(), Error>>)> {
+        let mut state = lock(&self.state);
+
+        let in_pass = state.in_pass.contains(&id);
+
+        let waiter = match state.waiters.get_mut(&id) {
+            Some(waiter) => waiter,
+            None => unreachable!("AsyncLink reactor registration {} is missing", id),
+        };
+
+        if !in_pass && (waiter.result.is_some() || !until_ready) {
+            let waiter = state.waiters.remove(&id).unwrap();
+
+            return Poll::Ready((waiter.link, waiter.result));
+        }
+
+        waiter.reclaiming |= !until_ready;
+
+        match waiter.waker {
+            Some(ref waker) if waker.will_wake(cx.waker()) => (),
+            _ => waiter.waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
+    }
+
+    /// Take back the link registered as `id`, if it is not used by the wait pass in
+    /// progress.
+    fn try_reclaim(&self, id: u64) -> Option<Link> {
+        let mut state = lock(&self.state);
+
+        if state.in_pass.contains(&id) {
+            return None;
+        }
+
+        state.waiters.remove(&id).map(|waiter| waiter.link)
+    }
+
+    /// Release the registration `id`, whose [`AsyncLink`] was dropped, closing the link.
+    ///
+    /// If the link is used by the wait pass in progress, it is closed by the reactor
+    /// once the pass finishes, instead of waiting for the pass here.
+    fn abandon(&self, id: u64) {
+        let mut state = lock(&self.state);
+
+        let waiter = if state.in_pass.contains(&id) {
+            if let Some(waiter) = state.waiters.get_mut(&id) {
+                waiter.abandoned = true;
+            }
+
+            None
+        } else {
+            state.waiters.remove(&id)
+        };
+
+        drop(state);
+
+        // Note: Close the link after the state is unlocked.
+        drop(waiter);
+    }
+}
+
+impl fmt::Debug for Parked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parked")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
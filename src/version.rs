@@ -0,0 +1,114 @@
+//! Runtime detection of the version of the WSTP library, and of the peer of a link.
+
+use std::ffi::CStr;
+use std::fmt::{self, Display};
+use std::os::raw::{c_char, c_int};
+
+use once_cell::sync::OnceCell;
+
+use crate::{sys, Error, Link};
+
+/// Version numbers of the WSTP library linked into the current process.
+///
+/// Returned by [`library_version()`].
+///
+/// *WSTP C API Documentation:* [`WSVersionNumbers()`](https://reference.wolfram.com/language/ref/c/WSVersionNumbers.html)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LibraryVersion {
+    /// The interface number. Newer WSTP C API functions are only available in libraries
+    /// with a sufficiently high interface number.
+    pub interface: i32,
+    /// The revision number of the library.
+    pub revision: i32,
+    /// The build number of the library.
+    pub build: i32,
+}
+
+impl Display for LibraryVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let LibraryVersion {
+            interface,
+            revision,
+            build,
+        } = *self;
+
+        write!(f, "{}.{}.{}", interface, revision, build)
+    }
+}
+
+/// Get the version numbers of the WSTP library linked into the current process.
+///
+/// The version is determined the first time this function is called, and cached for
+/// subsequent calls.
+///
+/// *WSTP C API Documentation:* [`WSVersionNumbers()`](https://reference.wolfram.com/language/ref/c/WSVersionNumbers.html)
+pub fn library_version() -> Result<LibraryVersion, Error> {
+    static VERSION: OnceCell<LibraryVersion> = OnceCell::new();
+
+    VERSION
+        .get_or_try_init(|| {
+            let env = crate::stdenv()?;
+
+            let mut interface: c_int = 0;
+            let mut revision: c_int = 0;
+            let mut build: c_int = 0;
+
+            unsafe {
+                sys::WSVersionNumbers(
+                    env.raw_env,
+                    &mut interface,
+                    &mut revision,
+                    &mut build,
+                )
+            };
+
+            Ok(LibraryVersion {
+                interface,
+                revision,
+                build,
+            })
+        })
+        .map(|version| *version)
+}
+
+/// # Version information
+impl Link {
+    /// Get the environment identification string of the program on the other side of
+    /// this link.
+    ///
+    /// This is the string the peer set using
+    /// [`WSSetEnvIDString()`](https://reference.wolfram.com/language/ref/c/WSSetEnvIDString.html),
+    /// and can be used to identify the program (and version) the link is connected to.
+    /// Returns `None` if the peer has not set an identification string.
+    ///
+    /// WSTP does not report the interface version supported by the peer; the WSTP
+    /// libraries on each side of a link negotiate a compatible protocol when the link is
+    /// activated.
+    ///
+    /// *WSTP C API Documentation:* [`WSGetLinkedEnvIDString()`](https://reference.wolfram.com/language/ref/c/WSGetLinkedEnvIDString.html)
+    pub fn peer_environment_id(&mut self) -> Result<Option<String>, Error> {
+        let mut id: *const c_char = std::ptr::null();
+
+        if unsafe { sys::WSGetLinkedEnvIDString(self.raw_link, &mut id) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        if id.is_null() {
+            return Ok(None);
+        }
+
+        let string = unsafe {
+            let string = CStr::from_ptr(id).to_string_lossy().into_owned();
+
+            sys::WSReleaseEnvIDString(self.raw_link, id);
+
+            string
+        };
+
+        if string.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(string))
+    }
+}
@@ -0,0 +1,132 @@
+//! Streaming traversal of the expression available to read from a [`Link`].
+
+use crate::{Error, Link, Token, TokenType, MAX_EXPR_DEPTH};
+
+/// Callbacks invoked by [`Link::parse_expr()`] for each part of an expression read from
+/// a link.
+///
+/// Every method has a default implementation that does nothing, so a visitor only
+/// needs to implement the callbacks it is interested in. Returning an error from a
+/// callback stops the traversal, and the error is returned by [`Link::parse_expr()`].
+///
+/// # Example
+///
+/// Sum the integers in an expression, without constructing an
+/// [`Expr`][wolfram_expr::Expr]:
+///
+/// ```
+/// use wstp::{Error, ExprVisitor, Link};
+///
+/// struct Sum(i64);
+///
+/// impl ExprVisitor for Sum {
+///     fn on_integer(&mut self, value: i64) -> Result<(), Error> {
+///         self.0 += value;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// link.put(&vec![(1, "a"), (2, "b"), (3, "c")]).unwrap();
+///
+/// let mut sum = Sum(0);
+/// link.parse_expr(&mut sum).unwrap();
+///
+/// assert_eq!(sum.0, 6);
+/// ```
+pub trait ExprVisitor {
+    /// Called for an integer.
+    fn on_integer(&mut self, value: i64) -> Result<(), Error> {
+        let _ = value;
+        Ok(())
+    }
+
+    /// Called for a real number.
+    fn on_real(&mut self, value: f64) -> Result<(), Error> {
+        let _ = value;
+        Ok(())
+    }
+
+    /// Called for a string.
+    fn on_string(&mut self, value: &str) -> Result<(), Error> {
+        let _ = value;
+        Ok(())
+    }
+
+    /// Called for a symbol, other than the head of a function expression.
+    fn on_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+        let _ = symbol;
+        Ok(())
+    }
+
+    /// Called at the start of a function expression with `length` arguments.
+    ///
+    /// If the head of the function is a symbol, `head` is that symbol. Otherwise, `head`
+    /// is `None`, and the head expression is visited next, before the arguments.
+    fn on_function(&mut self, head: Option<&str>, length: usize) -> Result<(), Error> {
+        let _ = (head, length);
+        Ok(())
+    }
+
+    /// Called after the last argument of a function expression has been visited.
+    fn on_function_end(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// # Streaming expressions
+impl Link {
+    /// Read the next expression on this link, invoking the callbacks of `visitor` for
+    /// each of its parts.
+    ///
+    /// Unlike [`Link::get_expr()`], this does not construct an in-memory representation
+    /// of the expression, so large expressions can be processed in constant memory.
+    pub fn parse_expr<V: ExprVisitor + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> Result<(), Error> {
+        self.parse_expr_at_depth(visitor, 0)
+    }
+
+    fn parse_expr_at_depth<V: ExprVisitor + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+        depth: usize,
+    ) -> Result<(), Error> {
+        if depth > MAX_EXPR_DEPTH {
+            return Err(Error::custom(format!(
+                "expression read from link exceeds the maximum nesting depth of {}",
+                MAX_EXPR_DEPTH
+            )));
+        }
+
+        let token = self.get_token()?;
+
+        match token {
+            Token::Integer(value) => visitor.on_integer(value),
+            Token::Real(value) => visitor.on_real(value),
+            Token::String(value) => visitor.on_string(value.as_str()),
+            Token::Symbol(symbol) => visitor.on_symbol(symbol.as_str()),
+            Token::Function { length } => {
+                // Note: `token` borrows this link, so it must be dropped before the
+                //       head and arguments are read.
+                drop(token);
+
+                if self.get_type()? == TokenType::Symbol {
+                    let head = self.get_symbol_ref()?;
+                    visitor.on_function(Some(head.as_str()), length)?;
+                } else {
+                    visitor.on_function(None, length)?;
+                    self.parse_expr_at_depth(visitor, depth + 1)?;
+                }
+
+                for _ in 0..length {
+                    self.parse_expr_at_depth(visitor, depth + 1)?;
+                }
+
+                visitor.on_function_end()
+            },
+        }
+    }
+}
@@ -1,26 +1,8 @@
-use crate::{
-    sys::{self, WSLINK},
-    Error, Link,
-};
+use crate::{lifecycle, link_state, sys, Error, Link};
 
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use once_cell::sync::Lazy;
-
-struct ForceSend<T>(T);
-
-unsafe impl<T> Send for ForceSend<T> {}
-
-/// Hash map used to store the closure passed to [`Link::wait_with_callback()`].
-///
-/// This is a workaround for the fact that [WSWaitForLinkActivityWithCallback][sys::WSWaitForLinkActivityWithCallback]
-/// takes a function pointer as an argument, but provides no way to provide a piece of
-/// data to that function pointer. Both pieces of data are required to pass a Rust
-/// closure across the FFI boundry. Instead, we store the closure in this global static
-/// hash map, and look it up inside the callback trampoline function.
-static WAIT_CALLBACKS: Lazy<Mutex<ForceSend<HashMap<WSLINK, *mut std::ffi::c_void>>>> =
-    Lazy::new(|| Mutex::new(ForceSend(HashMap::new())));
+use wolfram_expr::Expr;
 
 impl Link {
     /// *WSTP C API Documentation:* [`WSWaitForLinkActivity`](https://reference.wolfram.com/language/ref/c/WSWaitForLinkActivity.html)
@@ -31,7 +13,7 @@ impl Link {
 
         match result {
             sys::WSWAITSUCCESS => Ok(()),
-            sys::WSWAITERROR => Err(self.error_or_unknown()),
+            sys::WSWAITERROR => Err(self.wait_error()),
             _ => Err(Error::custom(format!(
                 "WSWaitForLinkActivity returned unexpected value: {}",
                 result
@@ -39,6 +21,39 @@ impl Link {
         }
     }
 
+    /// Wait at most `timeout` for data to become available on this link.
+    ///
+    /// `true` will be returned if data is available. `false` will be returned if no data
+    /// became available before `timeout` elapsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use wstp::{Link, Protocol};
+    ///
+    /// let mut listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+    ///
+    /// assert!(!listener.wait_with_timeout(Duration::from_millis(10)).unwrap());
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSWaitForLinkActivityWithCallback`](https://reference.wolfram.com/language/ref/c/WSWaitForLinkActivityWithCallback.html)
+    pub fn wait_with_timeout(&mut self, timeout: Duration) -> Result<bool, Error> {
+        if self.is_ready() {
+            return Ok(true);
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        self.wait_with_callback(|_: &mut Link| {
+            if Instant::now() < deadline {
+                std::ops::ControlFlow::Continue(())
+            } else {
+                std::ops::ControlFlow::Break(())
+            }
+        })
+    }
+
     /// Wait for data to become available, periodically calling a callback.
     ///
     /// `true` will be returned if data is available. `false` will be returned if the
@@ -87,20 +102,24 @@ impl Link {
         unsafe {
             let boxed_closure_ptr = Box::into_raw(Box::new(callback));
 
-            {
-                let mut lock = WAIT_CALLBACKS
-                    .lock()
-                    .expect("failed to acquire lock on WAIT_CALLBACKS");
+            // Note: WSWaitForLinkActivityWithCallback() provides no way to pass a piece
+            //       of data to the callback function, so the closure is stored in the
+            //       per-link state, and looked up inside the callback trampoline.
+            let already_waiting = link_state::with(raw_link, |state| {
+                if state.wait_callback.is_some() {
+                    return true;
+                }
 
-                let callbacks = &mut lock.0;
+                state.wait_callback = Some(boxed_closure_ptr as *mut std::ffi::c_void);
 
-                if callbacks.contains_key(&raw_link) {
-                    // Drop `lock` so we don't poisen it by panicking here.
-                    drop(lock);
-                    panic!("wait_with_callback: link is already being waited on with a callback");
-                }
+                false
+            });
 
-                callbacks.insert(raw_link, boxed_closure_ptr as *mut std::ffi::c_void);
+            if already_waiting {
+                drop(Box::from_raw(boxed_closure_ptr));
+                panic!(
+                    "wait_with_callback: link is already being waited on with a callback"
+                );
             }
 
             result = sys::WSWaitForLinkActivityWithCallback(
@@ -108,15 +127,7 @@ impl Link {
                 Some(link_wait_callback_trampoline::<F>),
             );
 
-            {
-                let mut lock = WAIT_CALLBACKS
-                    .lock()
-                    .expect("failed to acquire lock on WAIT_CALLBACKS");
-
-                let callbacks = &mut lock.0;
-
-                callbacks.remove(&raw_link);
-            }
+            link_state::with(raw_link, |state| state.wait_callback = None);
 
             // Drop the closure value.
             drop(Box::from_raw(boxed_closure_ptr));
@@ -125,7 +136,7 @@ impl Link {
         match result {
             sys::WSWAITSUCCESS => Ok(true),
             sys::WSWAITCALLBACKABORTED => Ok(false),
-            sys::WSWAITERROR => Err(self.error_or_unknown()),
+            sys::WSWAITERROR => Err(self.wait_error()),
             _ => Err(Error::custom(format!(
                 "WSWaitForLinkActivity returned unexpected value: {}",
                 result
@@ -134,6 +145,89 @@ impl Link {
     }
 }
 
+impl Link {
+    /// Get the error that caused waiting on this link to fail, and report it to the
+    /// lifecycle callbacks of this link.
+    fn wait_error(&self) -> Error {
+        let err = self.error_or_unknown();
+
+        lifecycle::wait_failed(self, &err);
+
+        err
+    }
+}
+
+/// # Reading with a timeout
+///
+/// These methods wait at most `timeout` for data to become available on the link, and
+/// return an error for which [`Error::is_timeout()`] returns `true` if none arrives.
+///
+/// The timeout only applies to waiting for the start of the value to be read. If the
+/// other side of the link sends a partial expression and then stops, reading the rest of
+/// the expression may still block.
+impl Link {
+    /// Read an expression off of this link, waiting at most `timeout` for data to
+    /// arrive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use wstp::{Link, Protocol};
+    ///
+    /// let mut listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+    ///
+    /// let err = listener
+    ///     .get_expr_with_timeout(Duration::from_millis(10))
+    ///     .unwrap_err();
+    ///
+    /// assert!(err.is_timeout());
+    /// ```
+    ///
+    /// See [`Link::get_expr()`].
+    pub fn get_expr_with_timeout(&mut self, timeout: Duration) -> Result<Expr, Error> {
+        self.wait_for_data(timeout)?;
+        self.get_expr()
+    }
+
+    /// Read an integer off of this link, waiting at most `timeout` for data to arrive.
+    ///
+    /// See [`Link::get_i64()`].
+    pub fn get_i64_with_timeout(&mut self, timeout: Duration) -> Result<i64, Error> {
+        self.wait_for_data(timeout)?;
+        self.get_i64()
+    }
+
+    /// Read a real number off of this link, waiting at most `timeout` for data to
+    /// arrive.
+    ///
+    /// See [`Link::get_f64()`].
+    pub fn get_f64_with_timeout(&mut self, timeout: Duration) -> Result<f64, Error> {
+        self.wait_for_data(timeout)?;
+        self.get_f64()
+    }
+
+    /// Read a string off of this link, waiting at most `timeout` for data to arrive.
+    ///
+    /// See [`Link::get_string()`].
+    pub fn get_string_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        self.wait_for_data(timeout)?;
+        self.get_string()
+    }
+
+    /// Wait at most `timeout` for data to become available on this link.
+    fn wait_for_data(&mut self, timeout: Duration) -> Result<(), Error> {
+        if !self.wait_with_timeout(timeout)? {
+            return Err(Error::timeout(timeout));
+        }
+
+        Ok(())
+    }
+}
+
 unsafe extern "C" fn link_wait_callback_trampoline<F>(
     mut raw_link: sys::WSLINK,
     _unused_void: *mut std::ffi::c_void,
@@ -145,16 +239,10 @@ where
     // unwinding over C stack frames.
     let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
         // let (raw_user_closure, _) = link.user_data();
-        let raw_user_closure: *mut std::ffi::c_void = {
-            let lock = WAIT_CALLBACKS
-                .lock()
-                .expect("failed to acquire lock on WAIT_CALLBACKS");
-
-            *lock
-                .0
-                .get(&raw_link)
-                .expect("link has no associated wait closure in WAIT_CALLBACKS")
-        };
+        let raw_user_closure: *mut std::ffi::c_void =
+            link_state::with_existing(raw_link, |state| state.wait_callback)
+                .flatten()
+                .expect("link has no associated wait closure");
 
         let link: &mut Link = Link::unchecked_ref_cast_mut(&mut raw_link);
 
@@ -0,0 +1,165 @@
+//! Waiting for data to become available on any of several links.
+
+use std::os::raw::c_ulong;
+use std::time::Duration;
+
+use crate::{
+    lifecycle,
+    sys::{self, WSLINK},
+    Error, Link,
+};
+
+/// Set of links that can be waited on simultaneously.
+///
+/// [`LinkWaitSet::wait()`] blocks until at least one of the links in the set has data
+/// available to be read, so that a single thread can service many links.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use wstp::{Link, LinkWaitSet};
+///
+/// let mut a = Link::new_loopback().unwrap();
+/// let b = Link::new_loopback().unwrap();
+///
+/// a.put_i64(5).unwrap();
+/// a.flush().unwrap();
+///
+/// let mut set = LinkWaitSet::new();
+/// let a_index = set.add(&a);
+/// let _b_index = set.add(&b);
+///
+/// let ready = set.wait(Some(Duration::from_secs(1))).unwrap();
+///
+/// assert_eq!(ready, vec![a_index]);
+/// ```
+///
+/// *WSTP C API Documentation:* [`WSReadyParallel()`](https://reference.wolfram.com/language/ref/c/WSReadyParallel.html)
+#[derive(Debug, Default)]
+pub struct LinkWaitSet<'link> {
+    links: Vec<&'link Link>,
+}
+
+/// Longest time passed to a single call to `WSReadyParallel()` when waiting without a
+/// timeout.
+const MAX_PARALLEL_WAIT: Duration = Duration::from_secs(60 * 60);
+
+impl<'link> LinkWaitSet<'link> {
+    /// Construct a new, empty [`LinkWaitSet`].
+    pub fn new() -> Self {
+        LinkWaitSet { links: Vec::new() }
+    }
+
+    /// Add `link` to this set, returning its index.
+    ///
+    /// The index identifies `link` in the values returned by [`LinkWaitSet::wait()`].
+    pub fn add(&mut self, link: &'link Link) -> usize {
+        self.links.push(link);
+        self.links.len() - 1
+    }
+
+    /// Get the number of links in this set.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Returns `true` if this set contains no links.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Wait until at least one link in this set has data available to be read, or until
+    /// `timeout` elapses.
+    ///
+    /// Returns the indices of the links that have data available, in increasing order.
+    /// An empty list is returned if `timeout` elapsed before any link became ready. If
+    /// `timeout` is `None`, this waits indefinitely.
+    ///
+    /// Returns an error if the links in this set do not all belong to the same WSTP
+    /// [`Environment`][crate::Environment].
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<Vec<usize>, Error> {
+        if self.links.is_empty() {
+            return Err(Error::custom(
+                "LinkWaitSet::wait(): link set is empty".to_owned(),
+            ));
+        }
+
+        let mut raw_links: Vec<WSLINK> =
+            self.links.iter().map(|link| link.raw_link).collect();
+
+        let count = i32::try_from(raw_links.len()).map_err(|_| {
+            Error::custom(format!(
+                "LinkWaitSet::wait(): too many links: {}",
+                raw_links.len()
+            ))
+        })?;
+
+        // Note: WSReadyParallel() takes a single environment, which must be the one
+        //       the links were created in.
+        let env = unsafe { sys::WSLinkEnvironment(raw_links[0]) };
+
+        if raw_links
+            .iter()
+            .any(|&raw_link| unsafe { sys::WSLinkEnvironment(raw_link) } != env)
+        {
+            return Err(Error::custom(
+                "LinkWaitSet::wait(): links belong to different WSTP environments"
+                    .to_owned(),
+            ));
+        }
+
+        let first_ready: usize = loop {
+            let wait_time = match timeout {
+                Some(timeout) => timeout,
+                None => MAX_PARALLEL_WAIT,
+            };
+
+            // Note: `c_ulong` is 32 bits wide on Windows, so saturate instead of
+            //       truncating very long timeouts.
+            let timeval = sys::wstimeval {
+                tv_sec: c_ulong::try_from(wait_time.as_secs()).unwrap_or(c_ulong::MAX),
+                tv_usec: c_ulong::from(wait_time.subsec_micros()),
+            };
+
+            let result = unsafe {
+                sys::WSReadyParallel(env, raw_links.as_mut_ptr(), count, timeval)
+            };
+
+            match result {
+                sys::WSREADYPARALLELTIMEDOUT if timeout.is_none() => continue,
+                sys::WSREADYPARALLELTIMEDOUT => return Ok(Vec::new()),
+                sys::WSREADYPARALLELERROR | sys::WSREADYPARALLELINVALIDARGUMENT => {
+                    return Err(Error::custom(format!(
+                        "WSReadyParallel() failed with code {}",
+                        result
+                    )))
+                },
+                index => match usize::try_from(index) {
+                    Ok(index) => break index,
+                    Err(_) => {
+                        return Err(Error::custom(format!(
+                            "WSReadyParallel() returned unexpected value: {}",
+                            index
+                        )))
+                    },
+                },
+            }
+        };
+
+        let ready: Vec<usize> = self
+            .links
+            .iter()
+            .enumerate()
+            .filter(|&(index, link)| index == first_ready || link.is_ready())
+            .map(|(index, _)| index)
+            .collect();
+
+        // Links also become ready when their connection fails or is closed.
+        for &index in &ready {
+            lifecycle::check_ready(self.links[index]);
+        }
+
+        Ok(ready)
+    }
+}
@@ -0,0 +1,309 @@
+//! Watchdog that detects links which stay blocked in a WSTP call, e.g. because of a
+//! deadlock.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{Error, Link, YieldParameters};
+
+/// Detects links that stay blocked in a WSTP call for longer than a timeout, and
+/// reports a [`StallReport`] describing them instead of hanging silently.
+///
+/// A watchdog is attached to one or more links using [`Link::set_watchdog()`]. While a
+/// WSTP call on a watched link is blocked, the watchdog tracks how long it has been
+/// blocked. Once a call has been blocked for longer than the stall timeout, the
+/// `on_stall` function passed to [`Watchdog::new()`] is called once with a report, and
+/// decides whether the call should keep waiting (`true`) or be abandoned (`false`). An
+/// abandoned call returns an error.
+///
+/// Attaching the same watchdog to both ends of a connection within this process allows
+/// it to detect the classic WSTP deadlock, where both ends are blocked reading (each
+/// waiting for the other to write) or both are blocked writing. If every link watched
+/// by a watchdog is stalled at the same time, [`StallReport::is_deadlock()`] returns
+/// `true`.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use wstp::{Link, Protocol, Watchdog};
+///
+/// let watchdog = Watchdog::new(Duration::from_secs(5), |report| {
+///     eprintln!("{}", report);
+///
+///     // Abandon the blocked call.
+///     false
+/// });
+///
+/// let mut listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+/// let mut link = Link::connect(Protocol::IntraProcess, &listener.link_name()).unwrap();
+///
+/// listener.set_watchdog(&watchdog).unwrap();
+/// link.set_watchdog(&watchdog).unwrap();
+///
+/// // Neither end ever writes anything, so this would block forever.
+/// std::thread::spawn(move || link.get_i64());
+///
+/// assert!(listener.get_i64().is_err());
+/// ```
+#[derive(Clone)]
+pub struct Watchdog {
+    inner: Arc<Inner>,
+}
+
+/// Diagnostic describing a link that has been blocked in a WSTP call for longer than
+/// the stall timeout of a [`Watchdog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StallReport {
+    /// How long the call has been blocked.
+    pub blocked_for: Duration,
+    /// Number of bytes of the current atomic expression that remain to be written, if
+    /// this could be determined. See [`Link::bytes_to_put()`].
+    pub bytes_to_put: Option<usize>,
+    /// Number of bytes of the current atomic expression that remain to be read, if
+    /// this could be determined. See [`Link::bytes_to_get()`].
+    pub bytes_to_get: Option<usize>,
+    /// Number of links watched by the watchdog that are currently stalled, including
+    /// this one.
+    pub stalled_links: usize,
+    /// Number of links watched by the watchdog.
+    pub watched_links: usize,
+}
+
+struct Inner {
+    stall_timeout: Duration,
+    on_stall: Box<dyn Fn(&StallReport) -> bool + Send + Sync + 'static>,
+    links: Mutex<HashMap<u64, Watched>>,
+    next_id: AtomicU64,
+}
+
+/// State of a link watched by a [`Watchdog`].
+#[derive(Default)]
+struct Watched {
+    /// Time at which the current blocking call started, if the link has blocked.
+    blocked_since: Option<Instant>,
+    /// Time of the most recent call to the yield function of the link.
+    last_yield: Option<Instant>,
+    /// Value of [`YieldParameters::count()`] in the most recent call to the yield
+    /// function, used to detect when a new blocking call starts.
+    last_count: i64,
+    /// Whether the current blocking call has already been reported.
+    reported: bool,
+}
+
+/// Removes a link from its watchdog when the yield function of the link is dropped.
+struct Registration {
+    inner: Arc<Inner>,
+    id: u64,
+}
+
+/// # Deadlock detection
+impl Link {
+    /// Watch this link for WSTP calls that stay blocked for longer than the stall timeout
+    /// of `watchdog`.
+    ///
+    /// The watchdog is implemented using a yield function, so this replaces any function
+    /// registered with [`Link::set_yield_function()`]. The link stops being watched when
+    /// it is closed, or when its yield function is replaced or cleared.
+    pub fn set_watchdog(&mut self, watchdog: &Watchdog) -> Result<(), Error> {
+        let registration = watchdog.register();
+
+        self.set_yield_function(move |params: YieldParameters| {
+            registration.on_yield(&params)
+        })
+    }
+}
+
+impl Watchdog {
+    /// Construct a new watchdog that calls `on_stall` when a watched link has been
+    /// blocked in a single WSTP call for longer than `stall_timeout`.
+    ///
+    /// If `on_stall` returns `false`, the blocked call is abandoned, and returns an
+    /// error. If it returns `true`, the call keeps waiting, and is not reported again.
+    pub fn new<F>(stall_timeout: Duration, on_stall: F) -> Self
+    where
+        F: Fn(&StallReport) -> bool + Send + Sync + 'static,
+    {
+        Watchdog {
+            inner: Arc::new(Inner {
+                stall_timeout,
+                on_stall: Box::new(on_stall),
+                links: Mutex::new(HashMap::new()),
+                next_id: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Time a WSTP call may be blocked for before it is reported.
+    pub fn stall_timeout(&self) -> Duration {
+        self.inner.stall_timeout
+    }
+
+    /// Number of links currently watched by this watchdog.
+    pub fn watched_links(&self) -> usize {
+        self.inner.lock_links().len()
+    }
+
+    fn register(&self) -> Registration {
+        let id = self.inner.next_id.fetch_add(1, Ordering::SeqCst);
+
+        self.inner.lock_links().insert(id, Watched::default());
+
+        Registration {
+            inner: Arc::clone(&self.inner),
+            id,
+        }
+    }
+}
+
+impl StallReport {
+    /// Returns `true` if every link watched by the watchdog was stalled at the time of
+    /// this report, and more than one link is watched.
+    ///
+    /// If both ends of a connection are watched, this indicates that neither end can
+    /// make progress until the other does.
+    pub fn is_deadlock(&self) -> bool {
+        self.watched_links > 1 && self.stalled_links == self.watched_links
+    }
+}
+
+impl Registration {
+    /// Called by the yield function of the watched link. Returns `false` if the blocked
+    /// call should be abandoned.
+    fn on_yield(&self, params: &YieldParameters) -> bool {
+        let Registration { ref inner, id } = *self;
+
+        let now = Instant::now();
+        let count = params.count();
+
+        let mut links = inner.lock_links();
+
+        let watched = match links.get_mut(&id) {
+            Some(watched) => watched,
+            None => return true,
+        };
+
+        // Note: The yield function is only called while a call is blocked, so a count
+        //       that did not increase means a new blocking call has started.
+        let blocked_since = match watched.blocked_since {
+            Some(since) if count > watched.last_count => since,
+            _ => {
+                watched.blocked_since = Some(now);
+                watched.reported = false;
+                now
+            },
+        };
+
+        watched.last_count = count;
+        watched.last_yield = Some(now);
+
+        let blocked_for = now.saturating_duration_since(blocked_since);
+
+        if watched.reported || blocked_for < inner.stall_timeout {
+            return true;
+        }
+
+        watched.reported = true;
+
+        let stalled_links = links
+            .values()
+            .filter(|watched| watched.is_stalled(now, inner.stall_timeout))
+            .count();
+        let watched_links = links.len();
+
+        drop(links);
+
+        // Note: The link is borrowed from WSTP for the duration of the yield function,
+        //       and must not be closed when this value is dropped.
+        let link = ManuallyDrop::new(unsafe { Link::unchecked_new(params.raw_link()) });
+
+        let report = StallReport {
+            blocked_for,
+            bytes_to_put: link.bytes_to_put().ok(),
+            bytes_to_get: link.bytes_to_get().ok(),
+            stalled_links,
+            watched_links,
+        };
+
+        (inner.on_stall)(&report)
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.inner.lock_links().remove(&self.id);
+    }
+}
+
+impl Inner {
+    fn lock_links(&self) -> MutexGuard<HashMap<u64, Watched>> {
+        self.links
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Watched {
+    /// Returns `true` if the link is blocked in a call that started more than `timeout`
+    /// ago.
+    ///
+    /// A link whose yield function has not been called within `timeout` is assumed to
+    /// no longer be blocked.
+    fn is_stalled(&self, now: Instant, timeout: Duration) -> bool {
+        match (self.blocked_since, self.last_yield) {
+            (Some(since), Some(last_yield)) => {
+                now.saturating_duration_since(since) >= timeout
+                    && now.saturating_duration_since(last_yield) < timeout
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for StallReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let StallReport {
+            blocked_for,
+            bytes_to_put,
+            bytes_to_get,
+            stalled_links,
+            watched_links,
+        } = *self;
+
+        write!(
+            f,
+            "WSTP call blocked for {:.1?} ({} of {} watched links stalled",
+            blocked_for, stalled_links, watched_links
+        )?;
+
+        if let Some(bytes) = bytes_to_put {
+            write!(f, ", {} bytes to put", bytes)?;
+        }
+
+        if let Some(bytes) = bytes_to_get {
+            write!(f, ", {} bytes to get", bytes)?;
+        }
+
+        write!(f, ")")?;
+
+        if self.is_deadlock() {
+            write!(f, ": likely deadlock, every watched link is blocked")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("stall_timeout", &self.inner.stall_timeout)
+            .field("watched_links", &self.watched_links())
+            .finish_non_exhaustive()
+    }
+}
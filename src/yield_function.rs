@@ -0,0 +1,194 @@
+//! Yield functions, called periodically while a WSTP function is blocked.
+
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+
+use crate::{
+    link_state::{self, Registered},
+    stdenv,
+    sys::{self, WSLINK},
+    Error, Link,
+};
+
+pub(crate) type YieldFunction = Box<dyn FnMut(YieldParameters) -> bool + Send + 'static>;
+
+/// Parameters passed to the function registered with [`Link::set_yield_function()`].
+///
+/// *WSTP C API Documentation:* [`WSYieldParameters`](https://reference.wolfram.com/language/ref/c/WSYieldParameters.html)
+pub struct YieldParameters<'a> {
+    raw: sys::WSYieldParameters,
+    /// Link whose blocked call the yield function was called for.
+    raw_link: WSLINK,
+    _marker: PhantomData<&'a ()>,
+}
+
+/// # Yield functions
+impl Link {
+    /// Register a function that is called periodically while a WSTP function is blocked
+    /// waiting for data on this link.
+    ///
+    /// This can be used to keep a user interface responsive during long blocking calls.
+    ///
+    /// If `function` returns `false`, the blocked call is abandoned, and returns an
+    /// error for which [`Error::is_would_block()`][crate::Error::is_would_block] returns
+    /// `true`.
+    ///
+    /// `function` is dropped when it is replaced, when it is unregistered using
+    /// [`Link::clear_yield_function()`], or when this link is closed. If `function` is
+    /// running when it is replaced or unregistered, it is dropped once it returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, YieldParameters};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_yield_function(|_: YieldParameters| {
+    ///     // Process user interface events here.
+    ///     true
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSSetYieldFunction()`](https://reference.wolfram.com/language/ref/c/WSSetYieldFunction.html)
+    pub fn set_yield_function<F>(&mut self, function: F) -> Result<(), Error>
+    where
+        F: FnMut(YieldParameters) -> bool + Send + 'static,
+    {
+        let raw_link = self.raw_link;
+        let function: YieldFunction = Box::new(function);
+
+        // Reuse the yield function object already registered with this link, if any.
+        let result = link_state::with(raw_link, |state| match state.yield_function {
+            Some(ref mut registered) => Ok(registered.replace(Some(function))),
+            None => Err(function),
+        });
+
+        let function = match result {
+            Ok(previous) => {
+                drop(previous);
+                return Ok(());
+            },
+            Err(function) => function,
+        };
+
+        let object = unsafe {
+            sys::WSCreateYieldFunction(
+                stdenv()?.raw_env,
+                Some(yield_function_trampoline),
+                std::ptr::null_mut(),
+            )
+        };
+
+        if object.is_none() {
+            return Err(Error::custom(
+                "WSCreateYieldFunction() failed to create yield function".to_owned(),
+            ));
+        }
+
+        // Note: The function is stored before the object is registered with WSTP, so
+        //       that the function can be found as soon as WSTP calls the object.
+        link_state::with(raw_link, |state| {
+            state.yield_function = Some(Registered::new(object, function));
+        });
+
+        if unsafe { sys::WSSetYieldFunction(raw_link, object) } == 0 {
+            let registered =
+                link_state::with(raw_link, |state| state.yield_function.take());
+            drop(registered);
+            unsafe { sys::WSDestroyYieldFunction(object) };
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
+    /// Unregister the function set by [`Link::set_yield_function()`].
+    ///
+    /// Returns `true` if a yield function was registered.
+    pub fn clear_yield_function(&mut self) -> bool {
+        let previous = link_state::with_existing(self.raw_link, |state| {
+            state.yield_function.as_mut()?.replace(None)
+        })
+        .flatten();
+
+        previous.is_some()
+    }
+}
+
+impl<'a> YieldParameters<'a> {
+    /// Suggested time, in milliseconds, that the yield function should sleep for.
+    ///
+    /// *WSTP C API Documentation:* [`WSSleepYP()`](https://reference.wolfram.com/language/ref/c/WSSleepYP.html)
+    pub fn sleep(&self) -> u64 {
+        let sleep = unsafe { sys::WSSleepYP(self.raw) };
+
+        u64::from(sleep)
+    }
+
+    /// Number of times the yield function has been called during the current blocking
+    /// call.
+    ///
+    /// *WSTP C API Documentation:* [`WSCountYP()`](https://reference.wolfram.com/language/ref/c/WSCountYP.html)
+    pub fn count(&self) -> i64 {
+        let count = unsafe { sys::WSCountYP(self.raw) };
+
+        i64::from(count)
+    }
+
+    pub(crate) fn raw_link(&self) -> WSLINK {
+        self.raw_link
+    }
+}
+
+impl<'a> std::fmt::Debug for YieldParameters<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("YieldParameters").finish_non_exhaustive()
+    }
+}
+
+unsafe extern "C" fn yield_function_trampoline(
+    raw_link: WSLINK,
+    raw_params: sys::WSYieldParameters,
+) -> c_int {
+    // Catch any panics which result from `function()` to prevent unwinding over C stack
+    // frames.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // Note: Take the function out of the table while it runs, so that it may call
+        //       `set_yield_function()` without deadlocking.
+        let (mut function, generation) =
+            match link_state::with_existing(raw_link, |state| {
+                state.yield_function.as_mut()?.take()
+            }) {
+                Some(Some(taken)) => taken,
+                // No function is set, or the function was called re-entrantly.
+                _ => return true,
+            };
+
+        let keep_going = function(YieldParameters {
+            raw: raw_params,
+            raw_link,
+            _marker: PhantomData,
+        });
+
+        // Put the function back, unless it was replaced or unregistered while it was
+        // running.
+        let replaced =
+            link_state::with_existing(raw_link, |state| match state.yield_function {
+                Some(ref mut registered) => registered.restore(function, generation),
+                None => Some(function),
+            });
+
+        drop(replaced);
+
+        keep_going
+    }));
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        // If a panic occurs, abandon the blocked call.
+        Err(_) => 1,
+    }
+}
@@ -180,10 +180,30 @@ fn test_bug_tcpip_listen_returns_unknown() {
     );
 }
 
+#[test]
+fn test_tcpip_invalid_address_error_source() {
+    use std::error::Error as _;
+
+    let err = Link::tcpip_connect("not a socket address").unwrap_err();
+
+    assert_eq!(err.code(), None);
+
+    let source = err.source().expect("expected error to have a source");
+    assert!(source.is::<std::io::Error>());
+}
+
 //======================================
 // Misc.
 //======================================
 
+#[test]
+fn test_environment_builder_after_initialization() {
+    // Creating a link ensures that the environment has been initialized.
+    let _link = Link::new_loopback().unwrap();
+
+    assert!(wstp::EnvironmentBuilder::new().initialize().is_err());
+}
+
 //-------------------------------------
 // Test wait() and wait_with_callback()
 //-------------------------------------
@@ -303,6 +323,29 @@ fn test_get_expr_missing_symbol_context_error() {
     );
 }
 
+#[test]
+fn test_get_expr_error_context() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_function("System`List", 2).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_function("System`List", 1).unwrap();
+    link.put_symbol("x").unwrap();
+
+    let err: wstp::Error = link.get_expr().unwrap_err();
+
+    assert_eq!(err.context(), [
+        "while reading argument 1 of 1 of a function expression",
+        "while reading argument 2 of 2 of a function expression",
+    ]);
+    assert_eq!(
+        err.to_string(),
+        "WSTP error: symbol name 'x' has no context, \
+         while reading argument 1 of 1 of a function expression, \
+         while reading argument 2 of 2 of a function expression"
+    );
+}
+
 //--------------------------------
 // Test getting and putting arrays
 //--------------------------------
@@ -332,6 +375,33 @@ fn test_roundtrip_f64_array() {
     assert_eq!(out.dimensions(), &[3]);
 }
 
+#[test]
+fn test_roundtrip_f32_array() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_f32_array(&[1.5, -2.25, 0.125, 4.0], &[2, 2]).unwrap();
+
+    let out = link.get_f32_array().unwrap();
+
+    assert_eq!(out.data(), &[1.5f32, -2.25, 0.125, 4.0]);
+    assert_eq!(out.dimensions(), &[2, 2]);
+}
+
+#[test]
+fn test_roundtrip_rank_3_array() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let data: Vec<f64> = (0..24).map(f64::from).collect();
+
+    link.put_f64_array(&data, &[2, 3, 4]).unwrap();
+
+    let out = link.get_f64_array().unwrap();
+
+    assert_eq!(out.rank(), 3);
+    assert_eq!(out.dimensions(), &[2, 3, 4]);
+    assert_eq!(out.data(), data.as_slice());
+}
+
 // Test that getting an f64 array as an i64 array performs rounding.
 #[test]
 fn test_mismatched_array_type_rounding() {
@@ -356,3 +426,32 @@ fn test_mismatched_type_error() {
         Err(Some(sys::MLEGSEQ))
     );
 }
+
+#[test]
+fn test_expr_builder_argument_count() {
+    use wolfram_expr::{Expr, Symbol};
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.builder()
+        .function("System`List", 2)
+        .and_then(|builder| builder.function(None, 1))
+        .and_then(|builder| builder.symbol("Global`f"))
+        .and_then(|builder| builder.i64(1))
+        .and_then(|builder| builder.str("two"))
+        .and_then(|builder| builder.finish())
+        .unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(Symbol::new("System`List"), vec![
+            Expr::normal(Symbol::new("Global`f"), vec![Expr::from(1)]),
+            Expr::string("two"),
+        ])
+    );
+
+    // Writing more expressions than were declared is an error.
+    let builder = link.builder().i64(1).unwrap();
+    assert!(builder.is_complete());
+    assert!(builder.i64(2).is_err());
+}
@@ -1,5 +1,8 @@
 use wolfram_expr::{Expr, Symbol};
-use wstp::{sys, Link, LinkStr, Protocol, Token, TokenType};
+use wstp::{
+    sys, wstp_put, wstp_service, CloseMode, Error, ExprVisitor, Link, LinkStr, MuxLink,
+    Protocol, RpcLink, ServiceError, SharedLink, Token, TokenType,
+};
 
 fn check_loopback_roundtrip(expr: Expr) {
     let mut link = Link::new_loopback().expect("failed to create Loopback link");
@@ -243,3 +246,832 @@ fn test_loopback_get_token_type_is_idempotent() {
 
     assert!(matches!(link.get_token().unwrap(), Token::Integer(5)));
 }
+
+#[test]
+fn test_loopback_string_chunks_dropped_early() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_str("hello world").unwrap();
+    link.put_i64(5).unwrap();
+
+    let mut chunks = link.get_string_chunks(4).unwrap();
+    assert_eq!(chunks.next().unwrap().unwrap(), "hell");
+    drop(chunks);
+
+    // The rest of the string was discarded.
+    assert_eq!(link.get_i64().unwrap(), 5);
+}
+
+#[test]
+fn test_loopback_wstp_put_long_list() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // Longer than the default `recursion_limit` of 128.
+    wstp_put!(&mut link, "System`List"[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ])
+    .unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(Symbol::new("System`List"), vec![Expr::from(0); 200])
+    );
+}
+
+#[test]
+fn test_loopback_strict_mode_end_packet_incomplete() {
+    let mut link = Link::new_loopback().unwrap();
+    link.set_strict_mode(true);
+    assert!(link.is_strict_mode());
+
+    link.put_function("System`List", 2).unwrap();
+    link.put_i64(1).unwrap();
+
+    let err = link.end_packet().unwrap_err();
+    assert!(err.code().is_none());
+    assert!(err.to_string().contains("end_packet()"));
+
+    // Completing the expression makes end_packet() valid again.
+    link.put_i64(2).unwrap();
+    assert_eq!(link.end_packet(), Ok(()));
+}
+
+#[test]
+fn test_loopback_strict_mode_missing_arg_count() {
+    let mut link = Link::new_loopback().unwrap();
+    link.set_strict_mode(true);
+
+    link.put_raw_type(i32::from(sys::WSTKFUNC)).unwrap();
+
+    let err = link.put_i64(5).unwrap_err();
+    assert!(err.to_string().contains("expected put_arg_count()"));
+
+    link.set_strict_mode(false);
+    assert!(!link.is_strict_mode());
+}
+
+#[test]
+fn test_loopback_strict_mode_incomplete_string() {
+    let mut link = Link::new_loopback().unwrap();
+    link.set_strict_mode(true);
+
+    let mut writer = link.begin_string(5).unwrap();
+    writer.write_chunk("ab").unwrap();
+    drop(writer);
+
+    let err = link.end_packet().unwrap_err();
+    assert!(err.to_string().contains("begin_string()"));
+
+    let err = link.put_i64(1).unwrap_err();
+    assert!(err.to_string().contains("begin_string()"));
+
+    // Writing all of the promised characters completes the string.
+    let mut link = Link::new_loopback().unwrap();
+    link.set_strict_mode(true);
+
+    let mut writer = link.begin_string(5).unwrap();
+    writer.write_chunk("ab").unwrap();
+    writer.write_chunk("cde").unwrap();
+    writer.finish().unwrap();
+
+    assert_eq!(link.end_packet(), Ok(()));
+}
+
+#[test]
+fn test_loopback_strict_mode_transfer() {
+    let mut args = Link::new_loopback().unwrap();
+    args.put_i64(1).unwrap();
+    args.put_i64(2).unwrap();
+
+    let mut dest = Link::new_loopback().unwrap();
+    dest.set_strict_mode(true);
+
+    dest.put_function("System`List", 3).unwrap();
+    args.transfer_to_end_of_loopback_link(&mut dest).unwrap();
+
+    // The transferred expressions count towards the elements of the List.
+    let err = dest.end_packet().unwrap_err();
+    assert!(err.to_string().contains("1 more expression"));
+
+    let mut other = Link::new_loopback().unwrap();
+    other.put_i64(3).unwrap();
+    other.transfer_expr_to(&mut dest).unwrap();
+
+    assert_eq!(dest.end_packet(), Ok(()));
+}
+
+#[test]
+fn test_loopback_close_mode() {
+    let mut link = Link::new_loopback().unwrap();
+
+    assert_eq!(link.close_mode(), CloseMode::Abrupt);
+
+    link.set_close_mode(CloseMode::Flush);
+    assert_eq!(link.close_mode(), CloseMode::Flush);
+
+    link.put_i64(5).unwrap();
+
+    link.close_flushed().unwrap();
+}
+
+#[test]
+fn test_loopback_in_isolated_environment() {
+    let env = wstp::EnvironmentBuilder::new().build().unwrap();
+
+    let mut link = Link::new_loopback_in(&env).unwrap();
+
+    link.put_i64(5).unwrap();
+    assert_eq!(link.get_i64(), Ok(5));
+
+    drop(link);
+
+    unsafe { env.clone().shutdown().unwrap() };
+
+    assert!(Link::new_loopback_in(&env).is_err());
+}
+
+#[test]
+fn test_loopback_byte_array_file_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("wstp-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("input.bin");
+    let output = dir.join("output.bin");
+
+    // Not a multiple of 3 bytes, so the encoded string is padded.
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 256) as u8).collect();
+    std::fs::write(&input, &data).unwrap();
+    std::fs::write(&output, b"existing contents").unwrap();
+
+    let mut link = Link::new_loopback().unwrap();
+
+    // A malformed ByteArray must not overwrite the existing file.
+    link.put_function("System`ByteArray", 1).unwrap();
+    link.put_str("not base64!").unwrap();
+
+    assert!(link.get_byte_array_to_file(&output).is_err());
+    assert_eq!(std::fs::read(&output).unwrap(), b"existing contents");
+
+    link.put_file_as_byte_array(&input).unwrap();
+    link.get_byte_array_to_file(&output).unwrap();
+
+    assert_eq!(std::fs::read(&output).unwrap(), data);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_loopback_serde_nesting_depth_limit() {
+    #[derive(serde::Deserialize)]
+    struct Nested(#[allow(dead_code)] Vec<Nested>);
+
+    // Run on a thread with a large stack, so that reaching the depth limit does not
+    // overflow the stack of the test thread.
+    std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(|| {
+            let mut link = Link::new_loopback().unwrap();
+
+            for _ in 0..100_000 {
+                link.put_function("System`List", 1).unwrap();
+            }
+            link.put_function("System`List", 0).unwrap();
+
+            let err = wstp::serde::from_link::<Nested>(&mut link).err().unwrap();
+
+            assert!(err.to_string().contains("maximum nesting depth"));
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_loopback_serde_struct_option_map() {
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        id: i64,
+        name: String,
+        parent: Option<i64>,
+        tags: BTreeMap<String, bool>,
+    }
+
+    let record = Record {
+        id: 1,
+        name: "first".to_owned(),
+        parent: None,
+        tags: BTreeMap::from([("a".to_owned(), true)]),
+    };
+
+    let mut link = Link::new_loopback().unwrap();
+
+    wstp::serde::to_link(&mut link, &record).unwrap();
+
+    let rule = |key: &str, value: Expr| {
+        Expr::normal(Symbol::new("System`Rule"), vec![Expr::string(key), value])
+    };
+    let association =
+        |rules: Vec<Expr>| Expr::normal(Symbol::new("System`Association"), rules);
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        association(vec![
+            rule("id", Expr::from(1)),
+            rule("name", Expr::string("first")),
+            rule("parent", Expr::symbol(Symbol::new("System`Null"))),
+            rule(
+                "tags",
+                association(vec![rule("a", Expr::symbol(Symbol::new("System`True")))])
+            ),
+        ])
+    );
+
+    for record in [
+        record,
+        Record {
+            id: 2,
+            name: "second".to_owned(),
+            parent: Some(1),
+            tags: BTreeMap::new(),
+        },
+    ] {
+        wstp::serde::to_link(&mut link, &record).unwrap();
+
+        assert_eq!(wstp::serde::from_link::<Record>(&mut link).unwrap(), record);
+    }
+
+    // Missing fields are reported.
+    link.put_expr(&association(vec![rule("id", Expr::from(3))]))
+        .unwrap();
+
+    let err = wstp::serde::from_link::<Record>(&mut link).unwrap_err();
+    assert!(err.to_string().contains("missing field"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_loopback_serde_enum() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(i64),
+        Rect(i64, i64),
+        Named { name: String },
+    }
+
+    let shapes = vec![
+        Shape::Empty,
+        Shape::Circle(1),
+        Shape::Rect(2, 3),
+        Shape::Named {
+            name: "n".to_owned(),
+        },
+    ];
+
+    let mut link = Link::new_loopback().unwrap();
+
+    wstp::serde::to_link(&mut link, &shapes).unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(
+            Symbol::new("System`List"),
+            vec![
+                Expr::symbol(Symbol::new("Global`Empty")),
+                Expr::normal(Symbol::new("Global`Circle"), vec![Expr::from(1)]),
+                Expr::normal(
+                    Symbol::new("Global`Rect"),
+                    vec![Expr::from(2), Expr::from(3)]
+                ),
+                Expr::normal(
+                    Symbol::new("Global`Named"),
+                    vec![Expr::normal(
+                        Symbol::new("System`Association"),
+                        vec![Expr::normal(
+                            Symbol::new("System`Rule"),
+                            vec![Expr::string("name"), Expr::string("n")]
+                        )]
+                    )]
+                ),
+            ]
+        )
+    );
+
+    wstp::serde::to_link(&mut link, &shapes).unwrap();
+    assert_eq!(
+        wstp::serde::from_link::<Vec<Shape>>(&mut link).unwrap(),
+        shapes
+    );
+
+    // The context of the variant symbol is ignored.
+    link.put_expr(&Expr::normal(
+        Symbol::new("MyContext`Circle"),
+        vec![Expr::from(4)],
+    ))
+    .unwrap();
+    assert_eq!(
+        wstp::serde::from_link::<Shape>(&mut link).unwrap(),
+        Shape::Circle(4)
+    );
+
+    // A variant with the wrong number of arguments is an error.
+    link.put_expr(&Expr::normal(
+        Symbol::new("Global`Rect"),
+        vec![Expr::from(5)],
+    ))
+    .unwrap();
+    let err = wstp::serde::from_link::<Shape>(&mut link).unwrap_err();
+    assert!(err.to_string().contains("argument(s)"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_loopback_serde_deserialize_any() {
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    // Untagged enums are deserialized using `deserialize_any()`.
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Null,
+        Bool(bool),
+        Integer(i64),
+        String(String),
+        List(Vec<Value>),
+        Map(BTreeMap<String, Value>),
+    }
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_expr(&Expr::normal(
+        Symbol::new("System`List"),
+        vec![
+            Expr::from(1),
+            Expr::string("s"),
+            Expr::symbol(Symbol::new("System`True")),
+            Expr::symbol(Symbol::new("System`Null")),
+            Expr::normal(
+                Symbol::new("System`Association"),
+                vec![
+                    Expr::normal(
+                        Symbol::new("System`Rule"),
+                        vec![
+                            Expr::string("k"),
+                            Expr::normal(Symbol::new("System`List"), vec![Expr::from(2)]),
+                        ],
+                    ),
+                    Expr::normal(
+                        Symbol::new("System`RuleDelayed"),
+                        vec![
+                            Expr::string("l"),
+                            Expr::symbol(Symbol::new("System`False")),
+                        ],
+                    ),
+                ],
+            ),
+        ],
+    ))
+    .unwrap();
+
+    assert_eq!(
+        wstp::serde::from_link::<Value>(&mut link).unwrap(),
+        Value::List(vec![
+            Value::Integer(1),
+            Value::String("s".to_owned()),
+            Value::Bool(true),
+            Value::Null,
+            Value::Map(BTreeMap::from([
+                ("k".to_owned(), Value::List(vec![Value::Integer(2)])),
+                ("l".to_owned(), Value::Bool(false)),
+            ])),
+        ])
+    );
+
+    // Function expressions other than List and Association are not supported.
+    link.put_expr(&Expr::normal(Symbol::new("Global`f"), vec![Expr::from(1)]))
+        .unwrap();
+
+    let err = wstp::serde::from_link::<Value>(&mut link).unwrap_err();
+    assert!(err.to_string().contains("expected List or Association"));
+}
+
+#[test]
+fn test_loopback_mux_pending_frames() {
+    let mut link = MuxLink::with_max_pending(Link::new_loopback().unwrap(), 2);
+
+    link.send(3, &Expr::from(1)).unwrap();
+    link.send(2, &Expr::from(2)).unwrap();
+    link.send(1, &Expr::from(3)).unwrap();
+    link.send(2, &Expr::from(4)).unwrap();
+
+    // Queues the frames for channels 3 and 2, which fills the queue.
+    assert_eq!(link.recv(1).unwrap(), Expr::from(3));
+    assert_eq!(link.pending_count(), 2);
+
+    // The last frame is not read, because the queue is full.
+    assert!(link.recv(4).is_err());
+    assert_eq!(link.pending_count(), 2);
+
+    // Queued frames are received in the order they arrived in.
+    assert_eq!(link.recv_any().unwrap(), (3, Expr::from(1)));
+    assert_eq!(link.recv_any().unwrap(), (2, Expr::from(2)));
+    assert_eq!(link.recv_any().unwrap(), (2, Expr::from(4)));
+}
+
+#[test]
+fn test_loopback_mux_try_recv_pending() {
+    let mut link = MuxLink::new(Link::new_loopback().unwrap());
+
+    link.send(1, &Expr::from(10)).unwrap();
+    link.send(2, &Expr::from(20)).unwrap();
+
+    // Nothing has been read from the link yet.
+    assert_eq!(link.try_recv_pending(1), None);
+
+    assert_eq!(link.recv(2).unwrap(), Expr::from(20));
+    assert_eq!(link.pending_count(), 1);
+
+    assert_eq!(link.try_recv_pending(1), Some(Expr::from(10)));
+    assert_eq!(link.pending_count(), 0);
+
+    // A frame that is not a List of a channel and an expression is an error.
+    link.link()
+        .put_expr(&Expr::normal(
+            Symbol::new("System`List"),
+            vec![Expr::from(1), Expr::from(2), Expr::from(3)],
+        ))
+        .unwrap();
+
+    let err = link.recv_any().unwrap_err();
+    assert!(err.to_string().contains("expected frame with 2 elements"));
+}
+
+#[test]
+fn test_loopback_rpc_out_of_order_responses() {
+    // A loopback link echoes each request back as its response.
+    let mut client = RpcLink::new(Link::new_loopback().unwrap());
+
+    let ids: Vec<_> = (0..3)
+        .map(|value| client.send_request(&Expr::from(value)).unwrap())
+        .collect();
+
+    assert_eq!(client.wait_response(ids[2]).unwrap(), Expr::from(2));
+
+    // Waiting for a later request retains the responses read before it.
+    assert_eq!(client.call(&Expr::from(3)).unwrap(), Expr::from(3));
+
+    assert_eq!(client.wait_response(ids[0]).unwrap(), Expr::from(0));
+    assert_eq!(client.wait_response(ids[1]).unwrap(), Expr::from(1));
+}
+
+#[test]
+fn test_loopback_service_errors() {
+    wstp_service! {
+        trait Calculator {
+            fn add(a: i64, b: i64) -> i64;
+            fn div(a: i64, b: i64) -> i64;
+        }
+
+        struct CalculatorClient;
+
+        struct CalculatorServer;
+    }
+
+    struct Calc;
+
+    impl Calculator for Calc {
+        fn add(&mut self, a: i64, b: i64) -> Result<i64, ServiceError> {
+            Ok(a + b)
+        }
+
+        fn div(&mut self, a: i64, b: i64) -> Result<i64, ServiceError> {
+            a.checked_div(b)
+                .ok_or_else(|| ServiceError::new("division by zero"))
+        }
+    }
+
+    let mut listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+    let name = listener.link_name();
+
+    let server = std::thread::spawn(move || {
+        listener.activate()?;
+        RpcLink::new(listener).serve(&mut CalculatorServer(Calc))
+    });
+
+    let mut link = Link::connect(Protocol::IntraProcess, &name).unwrap();
+    link.activate().unwrap();
+
+    let mut client = CalculatorClient::new(link);
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    let err = client.div(1, 0).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("service method 'div' failed: division by zero"));
+
+    let mut client = client.into_inner();
+
+    let err = client.call_method("mul", vec![]).unwrap_err();
+    assert!(err.to_string().contains("unknown method: mul"));
+
+    let err = client.call_method("add", vec![Expr::from(1)]).unwrap_err();
+    assert!(err.to_string().contains("more are expected"));
+
+    let err = client
+        .call_method("add", vec![Expr::from(1), Expr::from(2), Expr::from(3)])
+        .unwrap_err();
+    assert!(err.to_string().contains("expected 2"));
+
+    let err = client
+        .call_method("add", vec![Expr::string("one"), Expr::from(2)])
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("invalid argument 1 for method add"));
+
+    // A request that is not of the form method[args...] is answered with a Failure.
+    let response = client.call(&Expr::from(5)).unwrap();
+    assert!(response.to_string().contains("malformed service request"));
+
+    // The server stops once the client closes the link.
+    drop(client);
+    assert_eq!(server.join().unwrap(), Ok(()));
+}
+
+#[test]
+fn test_loopback_shared_link_scoped_writers() {
+    const WORKERS: i64 = 8;
+    const EXPRS_PER_WORKER: i64 = 50;
+
+    let link = SharedLink::new(Link::new_loopback().unwrap());
+
+    std::thread::scope(|scope| {
+        for worker in 0..WORKERS {
+            let link = &link;
+
+            scope.spawn(move || {
+                for index in 0..EXPRS_PER_WORKER {
+                    link.with_write(|link| {
+                        link.put_function("System`List", 3)?;
+                        link.put_i64(worker)?;
+                        link.put_i64(index)?;
+                        link.put_i64(worker * 1000 + index)
+                    })
+                    .unwrap();
+                }
+            });
+        }
+    });
+
+    let mut next_index = vec![0; WORKERS as usize];
+
+    for _ in 0..WORKERS * EXPRS_PER_WORKER {
+        link.with_read(|link| {
+            assert_eq!(link.test_head("System`List").unwrap(), 3);
+
+            let worker = link.get_i64().unwrap();
+            let index = link.get_i64().unwrap();
+            assert_eq!(link.get_i64().unwrap(), worker * 1000 + index);
+
+            // Expressions written by the same thread stay in order.
+            assert_eq!(index, next_index[worker as usize]);
+            next_index[worker as usize] += 1;
+        });
+    }
+
+    assert_eq!(next_index, vec![EXPRS_PER_WORKER; WORKERS as usize]);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_loopback_derive_roundtrip() {
+    use wstp::{GetFromLink, PutOnLink};
+
+    #[derive(PutOnLink, GetFromLink, Debug, PartialEq)]
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    #[derive(PutOnLink, GetFromLink, Debug, PartialEq)]
+    #[wstp(head = "Global`Pair")]
+    struct Labeled(String, Vec<i64>);
+
+    #[derive(PutOnLink, GetFromLink, Debug, PartialEq)]
+    #[wstp(association)]
+    struct Config {
+        name: String,
+        limit: Option<i64>,
+    }
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put(&Point { x: 1, y: 2 }).unwrap();
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(
+            Symbol::new("Global`Point"),
+            vec![Expr::from(1), Expr::from(2)]
+        )
+    );
+
+    link.put(&Point { x: 1, y: 2 }).unwrap();
+    assert_eq!(link.get::<Point>().unwrap(), Point { x: 1, y: 2 });
+
+    let labeled = Labeled("a".to_owned(), vec![1, 2]);
+    link.put(&labeled).unwrap();
+    assert_eq!(link.get::<Labeled>().unwrap(), labeled);
+
+    let config = Config {
+        name: "c".to_owned(),
+        limit: Some(3),
+    };
+    link.put(&config).unwrap();
+    assert_eq!(link.get::<Config>().unwrap(), config);
+
+    // Rules may be delayed, and given in any order.
+    link.put_expr(&Expr::normal(
+        Symbol::new("System`Association"),
+        vec![
+            Expr::normal(
+                Symbol::new("System`RuleDelayed"),
+                vec![
+                    Expr::string("limit"),
+                    Expr::symbol(Symbol::new("System`Null")),
+                ],
+            ),
+            Expr::normal(
+                Symbol::new("System`Rule"),
+                vec![Expr::string("name"), Expr::string("d")],
+            ),
+        ],
+    ))
+    .unwrap();
+    assert_eq!(
+        link.get::<Config>().unwrap(),
+        Config {
+            name: "d".to_owned(),
+            limit: None,
+        }
+    );
+
+    link.put_expr(&Expr::normal(
+        Symbol::new("System`Association"),
+        vec![Expr::normal(
+            Symbol::new("System`Rule"),
+            vec![Expr::string("name"), Expr::string("e")],
+        )],
+    ))
+    .unwrap();
+    let err = link.get::<Config>().unwrap_err();
+    assert!(err.to_string().contains("missing key \"limit\""));
+
+    // The head of a struct written as a function is checked.
+    link.put(&Point { x: 1, y: 2 }).unwrap();
+    assert!(link.get::<Labeled>().is_err());
+}
+
+#[test]
+fn test_loopback_parse_expr_visitor_callbacks() {
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl ExprVisitor for Recorder {
+        fn on_integer(&mut self, value: i64) -> Result<(), Error> {
+            self.0.push(format!("integer {}", value));
+            Ok(())
+        }
+
+        fn on_real(&mut self, value: f64) -> Result<(), Error> {
+            self.0.push(format!("real {}", value));
+            Ok(())
+        }
+
+        fn on_string(&mut self, value: &str) -> Result<(), Error> {
+            self.0.push(format!("string {}", value));
+            Ok(())
+        }
+
+        fn on_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+            self.0.push(format!("symbol {}", symbol));
+            Ok(())
+        }
+
+        fn on_function(
+            &mut self,
+            head: Option<&str>,
+            length: usize,
+        ) -> Result<(), Error> {
+            self.0.push(format!("function {:?} {}", head, length));
+            Ok(())
+        }
+
+        fn on_function_end(&mut self) -> Result<(), Error> {
+            self.0.push("end".to_owned());
+            Ok(())
+        }
+    }
+
+    let mut link = Link::new_loopback().unwrap();
+
+    // f[g[1], "s", "h"[2, x]]
+    link.put_expr(&Expr::normal(
+        Symbol::new("Global`f"),
+        vec![
+            Expr::normal(Symbol::new("Global`g"), vec![Expr::from(1)]),
+            Expr::string("s"),
+            Expr::normal(
+                Expr::string("h"),
+                vec![Expr::from(2), Expr::symbol(Symbol::new("Global`x"))],
+            ),
+        ],
+    ))
+    .unwrap();
+    link.put_i64(5).unwrap();
+
+    let mut recorder = Recorder::default();
+    link.parse_expr(&mut recorder).unwrap();
+
+    assert_eq!(
+        recorder.0,
+        [
+            "function Some(\"Global`f\") 3",
+            "function Some(\"Global`g\") 1",
+            "integer 1",
+            "end",
+            "string s",
+            "function None 2",
+            "string h",
+            "integer 2",
+            "symbol Global`x",
+            "end",
+            "end",
+        ]
+    );
+
+    // The complete expression was consumed.
+    assert_eq!(link.get_i64().unwrap(), 5);
+}
+
+#[test]
+fn test_loopback_progress_observer() {
+    use std::sync::{Arc, Mutex};
+
+    let mut link = Link::new_loopback().unwrap();
+
+    let reported = Arc::new(Mutex::new(Vec::new()));
+
+    link.set_progress_observer(Box::new({
+        let reported = Arc::clone(&reported);
+        move |progress: &wstp::Progress| {
+            reported.lock().unwrap().push(progress.transferred);
+            true
+        }
+    }));
+
+    link.put_str("hello world").unwrap();
+
+    let chunks: Vec<String> = link
+        .get_string_chunks(4)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(chunks, ["hell", "o wo", "rld"]);
+    assert_eq!(*reported.lock().unwrap(), [4, 8, 11]);
+
+    // An observer that unregisters itself is not restored once it returns.
+    let raw_link = unsafe { link.raw_link() } as usize;
+
+    link.set_progress_observer(Box::new(move |_: &wstp::Progress| {
+        let mut raw_link = raw_link as sys::WSLINK;
+        let link = unsafe { Link::unchecked_ref_cast_mut(&mut raw_link) };
+
+        assert!(link.take_progress_observer().is_none());
+        true
+    }));
+
+    let mut writer = link.begin_data(TokenType::Integer, 2).unwrap();
+    writer.write_chunk(b"42").unwrap();
+    writer.finish().unwrap();
+
+    assert!(link.take_progress_observer().is_none());
+}
@@ -0,0 +1,314 @@
+//! Derive macros for the `PutOnLink` and `GetFromLink` traits of the
+//! [`wstp`](https://docs.rs/wstp) crate.
+//!
+//! This crate is re-exported by `wstp` when its `derive` feature is enabled. See the
+//! `wstp::PutOnLink` documentation for usage.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericParam,
+    Generics, Ident, Index, LitStr, Member,
+};
+
+/// Derive `wstp::PutOnLink` for a struct.
+#[proc_macro_derive(PutOnLink, attributes(wstp))]
+pub fn derive_put_on_link(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_put_on_link(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `wstp::GetFromLink` for a struct.
+#[proc_macro_derive(GetFromLink, attributes(wstp))]
+pub fn derive_get_from_link(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_get_from_link(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+//======================================
+// Input
+//======================================
+
+/// How a struct is represented as a Wolfram Language expression.
+enum Form {
+    /// `Head[field1, field2, ...]`
+    Function { head: String },
+    /// `Association["field1" -> value1, "field2" -> value2, ...]`
+    Association,
+}
+
+struct Field {
+    member: Member,
+    /// Name of the field, used as the key in the association form.
+    name: Option<String>,
+}
+
+struct Input {
+    ident: Ident,
+    generics: Generics,
+    form: Form,
+    fields: Vec<Field>,
+}
+
+fn parse_input(input: DeriveInput, trait_name: &str) -> Result<Input, Error> {
+    let data = match input.data {
+        Data::Struct(data) => data,
+        Data::Enum(_) | Data::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                format!("#[derive({})] is only supported for structs", trait_name),
+            ))
+        },
+    };
+
+    let mut head: Option<String> = None;
+    let mut association = false;
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("wstp") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("head") {
+                let value: LitStr = meta.value()?.parse()?;
+                head = Some(value.value());
+                Ok(())
+            } else if meta.path.is_ident("association") {
+                association = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported wstp attribute"))
+            }
+        })?;
+    }
+
+    let fields: Vec<Field> = match data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .into_iter()
+            .map(|field| {
+                let ident = field.ident.expect("named field has no name");
+                Field {
+                    name: Some(ident.to_string().trim_start_matches("r#").to_owned()),
+                    member: Member::Named(ident),
+                }
+            })
+            .collect(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len())
+            .map(|index| Field {
+                member: Member::Unnamed(Index::from(index)),
+                name: None,
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let form = if association {
+        if head.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "#[wstp(head = ..)] and #[wstp(association)] cannot be used together",
+            ));
+        }
+
+        if fields.iter().any(|field| field.name.is_none()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "#[wstp(association)] requires a struct with named fields",
+            ));
+        }
+
+        Form::Association
+    } else {
+        Form::Function {
+            head: head.unwrap_or_else(|| format!("Global`{}", input.ident)),
+        }
+    };
+
+    Ok(Input {
+        ident: input.ident,
+        generics: input.generics,
+        form,
+        fields,
+    })
+}
+
+/// Add a `T: bound` predicate for every type parameter `T` in `generics`.
+fn add_trait_bounds(mut generics: Generics, bound: TokenStream2) -> Generics {
+    let params: Vec<Ident> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let where_clause = generics.make_where_clause();
+
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+
+    generics
+}
+
+//======================================
+// PutOnLink
+//======================================
+
+fn expand_put_on_link(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let Input {
+        ident,
+        generics,
+        form,
+        fields,
+    } = parse_input(input, "PutOnLink")?;
+
+    let generics = add_trait_bounds(generics, quote!(::wstp::PutOnLink));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let count = fields.len();
+
+    let body = match form {
+        Form::Function { head } => {
+            let members = fields.iter().map(|field| &field.member);
+
+            quote! {
+                link.put_function(#head, #count)?;
+                #(
+                    ::wstp::PutOnLink::put_on_link(&self.#members, link)?;
+                )*
+            }
+        },
+        Form::Association => {
+            let members = fields.iter().map(|field| &field.member);
+            let names = fields.iter().map(|field| &field.name);
+
+            quote! {
+                link.put_function("System`Association", #count)?;
+                #(
+                    link.put_function("System`Rule", 2)?;
+                    link.put_str(#names)?;
+                    ::wstp::PutOnLink::put_on_link(&self.#members, link)?;
+                )*
+            }
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::wstp::PutOnLink for #ident #ty_generics #where_clause {
+            fn put_on_link(
+                &self,
+                link: &mut ::wstp::Link,
+            ) -> ::std::result::Result<(), ::wstp::Error> {
+                #body
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+//======================================
+// GetFromLink
+//======================================
+
+fn expand_get_from_link(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let Input {
+        ident,
+        generics,
+        form,
+        fields,
+    } = parse_input(input, "GetFromLink")?;
+
+    let generics = add_trait_bounds(generics, quote!(::wstp::GetFromLink));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let count = fields.len();
+    let members: Vec<&Member> = fields.iter().map(|field| &field.member).collect();
+
+    let body = match form {
+        Form::Function { head } => {
+            quote! {
+                ::wstp::macros::expect_function(link, #head, #count)?;
+
+                ::std::result::Result::Ok(#ident {
+                    #(
+                        #members: ::wstp::GetFromLink::get_from_link(link)?,
+                    )*
+                })
+            }
+        },
+        Form::Association => {
+            let names: Vec<&String> = fields
+                .iter()
+                .filter_map(|field| field.name.as_ref())
+                .collect();
+            let vars: Vec<Ident> = (0..count)
+                .map(|index| Ident::new(&format!("__field{}", index), Span::call_site()))
+                .collect();
+
+            quote! {
+                #(
+                    let mut #vars = ::std::option::Option::None;
+                )*
+
+                for _ in 0..link.test_head("System`Association")? {
+                    let key: ::std::string::String = ::wstp::macros::get_rule_key(link)?;
+
+                    match key.as_str() {
+                        #(
+                            #names if #vars.is_none() => {
+                                #vars = ::std::option::Option::Some(
+                                    ::wstp::GetFromLink::get_from_link(link)?,
+                                );
+                            },
+                        )*
+                        _ => {
+                            return ::std::result::Result::Err(
+                                ::wstp::macros::unexpected_key(
+                                    ::std::stringify!(#ident),
+                                    &key,
+                                ),
+                            );
+                        },
+                    }
+                }
+
+                ::std::result::Result::Ok(#ident {
+                    #(
+                        #members: match #vars {
+                            ::std::option::Option::Some(value) => value,
+                            ::std::option::Option::None => {
+                                return ::std::result::Result::Err(
+                                    ::wstp::macros::missing_key(
+                                        ::std::stringify!(#ident),
+                                        #names,
+                                    ),
+                                );
+                            },
+                        },
+                    )*
+                })
+            }
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::wstp::GetFromLink for #ident #ty_generics #where_clause {
+            fn get_from_link(
+                link: &mut ::wstp::Link,
+            ) -> ::std::result::Result<Self, ::wstp::Error> {
+                #body
+            }
+        }
+    })
+}
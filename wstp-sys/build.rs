@@ -1,14 +1,38 @@
 //! This script links the Mathematica WSTPi4 library.
 //!
-//! It does this by finding the local Mathematica installation by using the users
-//! `wolframscript` to evaluate `$InstallationDirectory`. This script will fail if
-//! `wolframscript` is not on `$PATH`.
+//! The WSTP SDK is located using, in order of preference:
+//!
+//! 1. The `WSTP_COMPILER_ADDITIONS` environment variable, which names a WSTP
+//!    `CompilerAdditions` directory containing `wstp.h` and the WSTP library.
+//! 2. The `WOLFRAM_INSTALLATION_DIRECTORY` environment variable, which names the
+//!    `$InstallationDirectory` of a Wolfram installation.
+//! 3. The `WOLFRAM_APP_DIRECTORY` environment variable, which names the application
+//!    directory of a Wolfram installation.
+//! 4. The default Wolfram installation found by `wolfram-app-discovery`.
+//! 5. The newest Wolfram installation found in the standard installation locations for
+//!    the current operating system, or, on Windows, registered in the Windows registry.
+//!    This does not require `wolframscript` to be available.
+
+
+use std::path::{Path, PathBuf};
+use std::process;
 
+/// Environment variable naming the `$InstallationDirectory` of the Wolfram installation
+/// to build against.
+const WOLFRAM_INSTALLATION_DIRECTORY: &str = "WOLFRAM_INSTALLATION_DIRECTORY";
 
-use std::path::PathBuf;
-use std::process;
+/// Environment variable naming the application directory of the Wolfram installation
+/// to build against.
+const WOLFRAM_APP_DIRECTORY: &str = "WOLFRAM_APP_DIRECTORY";
 
-use wolfram_app_discovery::{WolframApp, WolframVersion};
+/// Windows registry key under which Wolfram products register their installations.
+const WOLFRAM_REGISTRY_KEY: &str = r"HKLM\SOFTWARE\Wolfram Research\Installations";
+
+/// Environment variable naming the WSTP `CompilerAdditions` directory to build against,
+/// overriding the one in the Wolfram installation.
+const WSTP_COMPILER_ADDITIONS: &str = "WSTP_COMPILER_ADDITIONS";
+
+use wolfram_app_discovery::WolframApp;
 
 fn main() {
     // Ensure that changes to environment variables checked by wolfram-app-discovery will
@@ -49,7 +73,7 @@ fn main() {
     }
 
 
-    let app = WolframApp::try_default().ok();
+    let app = find_wolfram_app();
 
     //-------------
     // Link to WSTP
@@ -60,24 +84,13 @@ fn main() {
     //----------------------------------------------------
     // Generate or use pre-generated Rust bindings to WSTP
     //----------------------------------------------------
-    // See docs/Development.md for instructions on how to pre-generate
+    // See docs/Maintenance.md for instructions on how to pre-generate
     // bindings for new WL versions.
 
-    let bindings_path = use_generated_bindings(app.as_ref());
-
-    // TODO: Make use of pre-generated bindings useable via a feature flag?
-    //       Using pre-generated bindings seems to currently only have a distinct
-    //       advantage over compile-time-generated bindings when building on
-    //       docs.rs, where the WSTP SDK is not available.
-    //
-    //       In other situations, using pre-generated bindings doesn't offer the
-    //       advantage of not needing the WSTP SDK available locally, because you
-    //       still need to link against the WSTP static library.
-
-    // let wolfram_version = app
-    //     .wolfram_version()
-    //     .expect("unable to get Wolfram Language vesion number");
-    // let bindings_path = use_pregenerated_bindings(&wolfram_version);
+    let bindings_path = match selected_major_version() {
+        Some(major) => use_pregenerated_bindings_for_major(major, app.as_ref()),
+        None => use_generated_bindings(app.as_ref()),
+    };
 
     println!(
         "cargo:rustc-env=CRATE_WSTP_SYS_BINDINGS={}",
@@ -85,6 +98,212 @@ fn main() {
     );
 }
 
+//======================================
+// Locate the WSTP SDK
+//======================================
+
+/// Find the Wolfram installation to build against.
+///
+/// Returns `None` if no installation could be found. This is not an error if
+/// [`WSTP_COMPILER_ADDITIONS`] is set.
+fn find_wolfram_app() -> Option<WolframApp> {
+    println!(
+        "cargo:rerun-if-env-changed={}",
+        WOLFRAM_INSTALLATION_DIRECTORY
+    );
+
+    if let Some(dir) = std::env::var_os(WOLFRAM_INSTALLATION_DIRECTORY) {
+        let dir = PathBuf::from(dir);
+
+        let app =
+            WolframApp::from_installation_directory(dir.clone()).unwrap_or_else(|err| {
+                panic!(
+                    "error: {} is set to '{}', which is not a valid Wolfram \
+                     installation directory: {}",
+                    WOLFRAM_INSTALLATION_DIRECTORY,
+                    dir.display(),
+                    err
+                )
+            });
+
+        return Some(app);
+    }
+
+    println!("cargo:rerun-if-env-changed={}", WOLFRAM_APP_DIRECTORY);
+
+    // Note: `WolframApp::try_default()` also checks this variable, but checking it here
+    //       produces a clearer error if it is set to an invalid location.
+    if let Some(dir) = std::env::var_os(WOLFRAM_APP_DIRECTORY) {
+        let dir = PathBuf::from(dir);
+
+        let app = WolframApp::from_app_directory(dir.clone()).unwrap_or_else(|err| {
+            panic!(
+                "error: {} is set to '{}', which is not a valid Wolfram application \
+                 directory: {}",
+                WOLFRAM_APP_DIRECTORY,
+                dir.display(),
+                err
+            )
+        });
+
+        return Some(app);
+    }
+
+    if let Ok(app) = WolframApp::try_default() {
+        return Some(app);
+    }
+
+    standard_installation_directories()
+        .into_iter()
+        .find_map(|dir| WolframApp::from_installation_directory(dir).ok())
+}
+
+/// Candidate `$InstallationDirectory` locations of Wolfram products installed in the
+/// standard location for the current operating system, or registered in the Windows
+/// registry, newest first.
+fn standard_installation_directories() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+
+    if cfg!(target_os = "macos") {
+        // E.g. /Applications/Mathematica.app/Contents
+        for app in list_dir(Path::new("/Applications")) {
+            let name = app.file_name().unwrap_or_default().to_string_lossy();
+
+            if (name.starts_with("Mathematica") || name.starts_with("Wolfram"))
+                && name.ends_with(".app")
+            {
+                dirs.push(app.join("Contents"));
+            }
+        }
+
+        dirs.sort_by(|a, b| b.cmp(a));
+
+        return dirs;
+    }
+
+    // E.g. /usr/local/Wolfram/Mathematica/13.0 or
+    //      C:\Program Files\Wolfram Research\Wolfram Engine\13.0
+    let (roots, products): (Vec<PathBuf>, &[&str]) = if cfg!(target_os = "windows") {
+        let program_files = std::env::var_os("ProgramFiles")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\Program Files"));
+
+        (
+            vec![program_files.join("Wolfram Research")],
+            &["Wolfram", "Mathematica", "Wolfram Engine"],
+        )
+    } else {
+        (
+            vec![
+                PathBuf::from("/usr/local/Wolfram"),
+                PathBuf::from("/opt/Wolfram"),
+            ],
+            &["Wolfram", "Mathematica", "WolframEngine"],
+        )
+    };
+
+    for root in &roots {
+        for product in products {
+            dirs.extend(list_dir(&root.join(product)));
+        }
+    }
+
+    if cfg!(target_os = "windows") {
+        dirs.extend(registry_installation_directories());
+    }
+
+    // Prefer the newest version, based on the version number directory name.
+    dirs.sort_by_key(|dir| {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+
+        let version: Vec<u32> = name
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+
+        std::cmp::Reverse(version)
+    });
+
+    dirs
+}
+
+/// `$InstallationDirectory` locations of the Wolfram products registered in the Windows
+/// registry, which may have been installed outside of the standard location.
+///
+/// Each installation is registered as a subkey of [`WOLFRAM_REGISTRY_KEY`], whose
+/// `ExecutablePath` value names the front end or kernel executable in the installation
+/// directory. The registry is read using the `reg` command, to avoid a dependency on a
+/// registry crate.
+fn registry_installation_directories() -> Vec<PathBuf> {
+    let output = process::Command::new("reg")
+        .args(["query", WOLFRAM_REGISTRY_KEY, "/s", "/v", "ExecutablePath"])
+        .output();
+
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        // Note: `reg query` fails if no Wolfram products are registered.
+        _ => return Vec::new(),
+    };
+
+    // Matching values are listed as lines of the form:
+    //
+    //     ExecutablePath    REG_SZ    C:\Program Files\...\13.0\Mathematica.exe
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, path) = line.trim().split_once("REG_SZ")?;
+
+            let executable = PathBuf::from(path.trim());
+
+            Some(executable.parent()?.to_path_buf())
+        })
+        .collect()
+}
+
+/// List the subdirectories of `dir`, or nothing if `dir` cannot be read.
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// Get the WSTP `CompilerAdditions` directory named by [`WSTP_COMPILER_ADDITIONS`], if
+/// set.
+fn compiler_additions_override() -> Option<PathBuf> {
+    println!("cargo:rerun-if-env-changed={}", WSTP_COMPILER_ADDITIONS);
+
+    let dir = PathBuf::from(std::env::var_os(WSTP_COMPILER_ADDITIONS)?);
+
+    if !dir.is_dir() {
+        panic!(
+            "error: {} is set to '{}', which is not a directory",
+            WSTP_COMPILER_ADDITIONS,
+            dir.display()
+        );
+    }
+
+    Some(dir)
+}
+
+/// File name of the WSTP static library within a `CompilerAdditions` directory.
+fn wstp_static_library_file_name() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "libWSTPi4.a"
+    } else if cfg!(target_os = "windows") {
+        "wstp64i4s.lib"
+    } else if cfg!(target_os = "linux") {
+        "libWSTP64i4.a"
+    } else {
+        panic!("error: wstp-sys does not support the target operating system")
+    }
+}
+
 //========================================================================
 // Tell `lib.rs` where to find the file containing the WSTP Rust bindings.
 //========================================================================
@@ -95,9 +314,12 @@ fn main() {
 
 /// Use bindings that we generate now at compile time.
 fn use_generated_bindings(app: Option<&WolframApp>) -> PathBuf {
-    let wstp_h = wolfram_app_discovery::build_scripts::wstp_c_header_path(app)
-        .expect("unable to get 'wstp.h' location")
-        .into_path_buf();
+    let wstp_h = match compiler_additions_override() {
+        Some(dir) => dir.join("wstp.h"),
+        None => wolfram_app_discovery::build_scripts::wstp_c_header_path(app)
+            .expect("unable to get 'wstp.h' location")
+            .into_path_buf(),
+    };
 
     println!(
         "cargo:warning=info: generating WSTP bindings from: {}",
@@ -141,28 +363,68 @@ fn generate_and_save_bindings_to_file(wstp_h: &PathBuf, out_path: &PathBuf) {
 // Pre-generated bindings
 //-----------------------
 
-/// Use bindings that have been pre-generated.
-#[allow(dead_code)]
-fn use_pregenerated_bindings(wolfram_version: &WolframVersion) -> PathBuf {
+/// Cargo features that select the major Wolfram version whose pre-generated bindings
+/// should be used, instead of generating bindings at compile time.
+///
+/// A feature is only provided for release series that have bindings in `generated/`.
+/// Those bindings exist only for some systems; see docs/Development.md.
+const VERSION_FEATURES: &[(&str, u32)] = &[("wstp-12-x", 12), ("wstp-13-x", 13)];
+
+/// Get the major Wolfram version selected by a `wstp-<major>-x` feature, if any.
+fn selected_major_version() -> Option<u32> {
+    let selected: Vec<(&str, u32)> = VERSION_FEATURES
+        .iter()
+        .copied()
+        .filter(|(feature, _)| {
+            let var =
+                format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+            std::env::var_os(var).is_some()
+        })
+        .collect();
+
+    match selected.as_slice() {
+        [] => None,
+        [(_, major)] => Some(*major),
+        _ => {
+            let names: Vec<&str> = selected.iter().map(|(feature, _)| *feature).collect();
+            panic!(
+                "error: wstp-sys features {} are mutually exclusive; enable at most one",
+                names.join(", ")
+            );
+        },
+    }
+}
+
+/// Use the newest pre-generated bindings for Wolfram version `major`.x that are
+/// available for the target system.
+fn use_pregenerated_bindings_for_major(major: u32, app: Option<&WolframApp>) -> PathBuf {
     let system_id =
         wolfram_app_discovery::system_id_from_target(&std::env::var("TARGET").unwrap())
             .expect("unable to get System ID for target system");
 
-    // FIXME: Check that this file actually exists, and generate a nicer error if it
-    //        doesn't.
+    let generated_dir =
+        PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("generated");
 
-    let bindings_path = make_bindings_path(&wolfram_version.to_string(), &system_id);
+    println!("cargo:rerun-if-changed={}", generated_dir.display());
 
-    println!("cargo:rerun-if-changed={}", bindings_path.display());
+    let newest: Option<((u32, u32, u32), String)> = std::fs::read_dir(&generated_dir)
+        .expect("unable to read wstp-sys/generated/ directory")
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|name| Some((parse_version(&name)?, name)))
+        .filter(|((version_major, _, _), _)| *version_major == major)
+        .filter(|(_, name)| make_bindings_path(name, &system_id).is_file())
+        .max();
 
-    if !bindings_path.is_file() {
-        println!(
-            "
+    let version = match newest {
+        Some((_, version)) => version,
+        None => {
+            println!(
+                "
     ==== ERROR: wstp-sys =====
 
     Rust bindings for Wolfram WSTP for target configuration:
 
-        WolframVersion:    {}
+        WolframVersion:    {}.x
         SystemID:          {}
 
     have not been pre-generated.
@@ -171,12 +433,42 @@ fn use_pregenerated_bindings(wolfram_version: &WolframVersion) -> PathBuf {
 
     =========================================
             ",
-            wolfram_version, system_id
-        );
-        panic!("<See printed error>");
+                major, system_id
+            );
+            panic!("<See printed error>");
+        },
+    };
+
+    // The WSTP library is still linked from the local installation, so warn if it
+    // doesn't match the version the bindings were generated for.
+    if let Some(app_version) = app.and_then(|app| app.wolfram_version().ok()) {
+        let app_version = app_version.to_string();
+
+        if parse_version(&app_version).map(|(app_major, _, _)| app_major) != Some(major) {
+            println!(
+                "cargo:warning=wstp-sys: using WSTP {} bindings selected by feature \
+                 `wstp-{}-x`, but linking to the WSTP library from Wolfram {}",
+                version, major, app_version
+            );
+        }
+    }
+
+    make_bindings_path(&version, &system_id)
+}
+
+/// Parse a version number of the form `<major>.<minor>.<patch>`.
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next()??;
+
+    if parts.next().is_some() {
+        return None;
     }
 
-    bindings_path
+    Some((major, minor, patch))
 }
 
 fn make_bindings_path(wolfram_version: &str, system_id: &str) -> PathBuf {
@@ -198,11 +490,31 @@ fn make_bindings_path(wolfram_version: &str, system_id: &str) -> PathBuf {
 /// Emits the necessary `cargo` instructions to link to the WSTP static library,
 /// and also links the WSTP interface libraries (the libraries that WSTP itself
 /// depends on).
+///
+/// If the `dynamic-linking` feature is enabled, the WSTP shared library (or framework,
+/// on macOS) is linked instead.
 fn link_to_wstp(app: Option<&WolframApp>) {
     // Path to the WSTP static library file.
-    let static_lib = wolfram_app_discovery::build_scripts::wstp_static_library_path(app)
-        .expect("unable to get WSTP static library path")
-        .into_path_buf();
+    let static_lib = match compiler_additions_override() {
+        Some(dir) => dir.join(wstp_static_library_file_name()),
+        None => wolfram_app_discovery::build_scripts::wstp_static_library_path(app)
+            .expect("unable to get WSTP static library path")
+            .into_path_buf(),
+    };
+
+    if std::env::var_os("CARGO_FEATURE_DYNAMIC_LINKING").is_some() {
+        // The shared library is located in the same CompilerAdditions directory as
+        // the static library.
+        let compiler_additions = static_lib
+            .parent()
+            .expect("WSTP static library path has no parent directory");
+
+        link_wstp_dynamically(compiler_additions);
+
+        // Note: The interface libraries are dependencies of the shared library, and
+        //       so do not need to be linked explicitly.
+        return;
+    }
 
     link_wstp_statically(&static_lib);
 
@@ -256,6 +568,29 @@ fn link_to_wstp(app: Option<&WolframApp>) {
     }
 }
 
+/// Link to the WSTP shared library in the `compiler_additions` directory.
+///
+/// Note: The shared library must also be locatable at runtime, e.g. by adding
+///       `compiler_additions` to `LD_LIBRARY_PATH` (Linux), `DYLD_FRAMEWORK_PATH`
+///       (macOS), or `PATH` (Windows).
+fn link_wstp_dynamically(compiler_additions: &Path) {
+    let dir = compiler_additions.display();
+
+    if cfg!(target_os = "macos") {
+        println!("cargo:rustc-link-search=framework={}", dir);
+        println!("cargo:rustc-link-lib=framework=wstp");
+    } else if cfg!(target_os = "windows") {
+        // Link to the import library of `wstp64i4.dll`.
+        println!("cargo:rustc-link-search={}", dir);
+        println!("cargo:rustc-link-lib=dylib=wstp64i4m");
+    } else if cfg!(target_os = "linux") {
+        println!("cargo:rustc-link-search={}", dir);
+        println!("cargo:rustc-link-lib=dylib=WSTP64i4");
+    } else {
+        panic!("error: wstp-sys does not support the target operating system")
+    }
+}
+
 fn link_wstp_statically(lib: &PathBuf) {
     let mut lib = lib.clone();
 